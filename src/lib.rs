@@ -34,7 +34,7 @@
 //! [`BasicAsyncMediator`]: asynchronous::basic::BasicAsyncMediator
 //! [`CxAwareAsyncMediator`]: asynchronous::contextaware::CxAwareAsyncMediator
 
-#![doc(html_root_url = "https://docs.rs/mediator-sys/2.0.2")]
+#![doc(html_root_url = "https://docs.rs/mediator-sys/3.0.0")]
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
 mod mediator;
@@ -42,7 +42,18 @@ mod mediator;
 #[cfg(feature = "async")]
 pub use mediator::asynchronous;
 pub use mediator::builder;
+pub use mediator::cache;
+pub use mediator::clock;
+pub use mediator::dead_letter;
+pub use mediator::hierarchy;
+pub use mediator::history;
+pub use mediator::idempotency;
 pub use mediator::listener;
+pub use mediator::priority;
+pub use mediator::retry;
+pub use mediator::saga;
+pub use mediator::schedule;
+pub use mediator::storage;
 pub use mediator::synchronous;
 
 #[cfg(test)]
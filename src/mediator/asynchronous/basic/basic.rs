@@ -1,11 +1,39 @@
-use std::sync::mpsc::TryRecvError;
+use std::{
+    future::Future,
+    io,
+    sync::mpsc::{RecvError, TryRecvError},
+    time::{Duration, Instant},
+};
 
 use async_std::sync::Mutex;
 use async_trait::async_trait;
 use std::fmt::Debug;
 
 use super::*;
-use crate::synchronous::basic::{BasicMediator, SyncMediatorInternal, SyncMediatorInternalNext};
+use crate::mediator::asynchronous::{
+    cancellation::{self, CancellationToken, Interrupted},
+    guard::{BreakerState, GuardError, Guards},
+    notification::NotificationHandlers,
+    sink::RequestSink,
+    stream::{EventStream, Overflow, Subscribers},
+};
+use crate::mediator::{
+    cache::Lookup,
+    dead_letter::DeadLetter,
+    hierarchy::MediatorLink,
+    history::{AsyncAction, CommandHistory, History, Replay, Undoable},
+    idempotency::{wait, Idempotent, InFlight, Reservation},
+    priority::Priority,
+    retry,
+    saga::AsyncSagas,
+    schedule::ScheduledId,
+    storage::Record,
+};
+use crate::synchronous::basic::{
+    BasicMediator, QueryContext, SyncMediatorInternal, SyncMediatorInternalHierarchy,
+    SyncMediatorInternalNext, SyncMediatorInternalOutbox, SyncMediatorInternalPriority,
+    SyncMediatorInternalSchedule, SyncMediatorInternalTopic,
+};
 
 /// Basic async mediator for asynchronous environments with events of type `Ev`.
 ///
@@ -66,6 +94,34 @@ where
     Ev: Debug,
 {
     pub(crate) basic: Mutex<BasicMediator<Ev>>,
+    pub(crate) notifications: NotificationHandlers<()>,
+    pub(crate) subscribers: Subscribers<Ev>,
+    pub(crate) guards: Guards<Ev>,
+    pub(crate) link: MediatorLink<Ev>,
+    pub(crate) sagas: AsyncSagas<BasicAsyncMediator<Ev>>,
+    pub(crate) history: std::sync::Mutex<History<AsyncAction<BasicAsyncMediator<Ev>>>>,
+    pub(crate) in_flight: InFlight,
+}
+
+impl<Ev> BasicAsyncMediator<Ev>
+where
+    Ev: Debug + Send,
+{
+    /// Handles a request via `future` within a transaction, if requests are transactional.
+    pub(crate) async fn transact<E, F>(&self, future: F) -> Result<(), E>
+    where
+        F: Future<Output = Result<(), E>>,
+    {
+        let transaction = self.basic.lock().await.outbox.begin();
+        match transaction {
+            Some(transaction) => {
+                let result = transaction.scope(future).await;
+                self.basic.lock().await.commit(transaction, result.is_ok());
+                result
+            }
+            None => future.await,
+        }
+    }
 }
 
 #[async_trait]
@@ -114,6 +170,176 @@ where
     }
 }
 
+#[async_trait]
+impl<Ev> AsyncMediatorInternalPriority<Ev> for BasicAsyncMediator<Ev>
+where
+    Ev: Debug + Send,
+{
+    /// Publishes an event `Ev` with a [`Priority`] asynchronously.
+    ///
+    /// The event is dispatched by [`BasicAsyncMediator::next()`] before all pending events
+    /// of a lower priority, and after the pending events of the same or a higher priority.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Routine,
+    ///     Alert,
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = BasicAsyncMediator::<MyEvent>::builder()
+    ///         .add_listener(|ev| {
+    ///             /* Your listening logic */
+    ///         })
+    ///         .build();
+    ///
+    ///     mediator.publish(MyEvent::Routine).await;
+    ///     mediator.publish_with_priority(MyEvent::Alert, Priority::HIGH).await;
+    ///
+    ///     // Dispatches `MyEvent::Alert` first.
+    ///     mediator.next().await.ok();
+    /// });
+    ///
+    async fn publish_with_priority(&self, event: Ev, priority: Priority) {
+        let m = self.basic.lock().await;
+        m.publish_with_priority(event, priority)
+    }
+}
+
+#[async_trait]
+impl<Ev> AsyncMediatorInternalTopic<Ev> for BasicAsyncMediator<Ev>
+where
+    Ev: Debug + Send,
+{
+    /// Publishes an event `Ev` to a `topic` asynchronously.
+    ///
+    /// Topics consist of segments separated by dots, e.g. `orders.eu.created`.
+    /// Besides all listeners, the event is dispatched to every listener subscribed
+    /// to a matching pattern via [`super::BasicAsyncBuilder::add_topic_listener()`].
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     OrderCreated(u32),
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = BasicAsyncMediator::<MyEvent>::builder()
+    ///         .add_topic_listener("orders.#", |ev| {
+    ///             /* Your listening logic */
+    ///         })
+    ///         .build();
+    ///
+    ///     mediator.publish_to("orders.eu.created", MyEvent::OrderCreated(1)).await;
+    ///     mediator.next().await.ok();
+    /// });
+    ///
+    async fn publish_to(&self, topic: &str, event: Ev) {
+        let m = self.basic.lock().await;
+        m.publish_to(topic, event)
+    }
+}
+
+#[async_trait]
+impl<Ev> AsyncMediatorInternalOutbox<Ev> for BasicAsyncMediator<Ev>
+where
+    Ev: Debug + Send,
+{
+    /// Publishes an event `Ev` asynchronously right away,
+    /// even from within a transactional handler.
+    ///
+    /// Requests sent via [`AsyncMediatorInternalTryHandle::try_send()`] to a mediator
+    /// built with [`super::BasicAsyncBuilder::transactional()`] buffer the events
+    /// published by their handler in an outbox, until the handler succeeded.
+    /// Events published via this method bypass the outbox,
+    /// so they are delivered even if the handler fails, e.g. progress or audit events.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::basic::*;
+    /// use async_trait::async_trait;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Attempted,
+    ///     Transferred,
+    /// }
+    ///
+    /// struct Transfer;
+    ///
+    /// #[async_trait]
+    /// impl TryAsyncRequestHandler<Transfer, MyEvent> for BasicAsyncMediator<MyEvent> {
+    ///     type Error = String;
+    ///
+    ///     async fn try_handle(&self, _req: Transfer) -> Result<(), String> {
+    ///         self.publish_immediately(MyEvent::Attempted).await;
+    ///         self.publish(MyEvent::Transferred).await;
+    ///         Err(String::from("insufficient funds"))
+    ///     }
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = BasicAsyncMediator::<MyEvent>::builder()
+    ///         .transactional()
+    ///         .build();
+    ///
+    ///     assert!(mediator.try_send(Transfer).await.is_err());
+    ///
+    ///     // Only `MyEvent::Attempted` is dispatched.
+    ///     mediator.next().await.ok();
+    ///     assert!(mediator.next().await.is_err());
+    /// });
+    ///
+    async fn publish_immediately(&self, event: Ev) {
+        let m = self.basic.lock().await;
+        m.publish_immediately(event)
+    }
+}
+
+impl<Ev> SyncMediatorInternalHierarchy<Ev> for BasicAsyncMediator<Ev>
+where
+    Ev: Debug,
+{
+    /// Returns a [`MediatorLink`] to link child mediators to the [`BasicAsyncMediator`].
+    ///
+    /// Children are linked via `parent()` on their builders, e.g.
+    /// [`super::BasicAsyncBuilder::parent()`], which takes the parent mediator itself.
+    ///
+    fn link(&self) -> MediatorLink<Ev> {
+        self.link.clone()
+    }
+
+    /// Returns the number of child mediators linked to the [`BasicAsyncMediator`].
+    ///
+    /// Children are unlinked when they are dropped.
+    ///
+    fn child_count(&self) -> usize {
+        self.link.child_count()
+    }
+}
+
 #[async_trait]
 impl<Ev> AsyncMediatorInternalHandle<Ev> for BasicAsyncMediator<Ev>
 where
@@ -136,21 +362,1256 @@ where
 }
 
 #[async_trait]
-impl<Ev> AsyncMediatorInternalNext for BasicAsyncMediator<Ev>
+impl<Ev> AsyncMediatorInternalCqrs<Ev> for BasicAsyncMediator<Ev>
 where
-    Ev: Debug + Clone + Send,
+    Ev: Debug + Send,
 {
-    /// Process the next published event `Ev` asynchronously.
+    /// Execute a command of type `Cmd` with the mediator asynchronously.
     ///
-    /// This method locks the `Mutex` and instructs
-    /// the underlying [`BasicMediator`] to process the next event.
+    /// The command will be processed internally by [`AsyncCommandHandler::handle()`].
+    /// This is why it is required to implement [`AsyncCommandHandler`] for [`BasicAsyncMediator`].
     ///
-    /// See [`BasicMediator::next()`] for more info.
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn execute<Cmd>(&self, cmd: Cmd)
+    where
+        Self: AsyncCommandHandler<Cmd, Ev>,
+        Cmd: Send,
+    {
+        <Self as AsyncCommandHandler<Cmd, Ev>>::handle(self, cmd).await
+    }
+
+    /// Send a query of type `Q` to the mediator asynchronously and return its response.
+    ///
+    /// The query will be answered by [`AsyncQueryHandler::handle()`].
+    /// This is why it is required to implement [`AsyncQueryHandler`] for [`BasicAsyncMediator`].
+    /// The query handler is given a [`QueryContext`] instead of the mediator,
+    /// so it can answer other queries, but cannot publish events.
     ///
     /// You need to await the `Future` using `.await`.
     ///
-    async fn next(&self) -> Result<(), TryRecvError> {
-        let m = self.basic.lock().await;
-        m.next()
+    async fn query<Q>(&self, query: Q) -> <Self as AsyncQueryHandler<Q, Ev>>::Response
+    where
+        Self: AsyncQueryHandler<Q, Ev>,
+        Q: Send + 'static,
+    {
+        let lookup = {
+            let m = self.basic.lock().await;
+            m.caches.lookup(&query, m.clock.now())
+        };
+        match lookup {
+            Lookup::Hit(response) => response,
+            Lookup::Miss(miss) => {
+                let response =
+                    <Self as AsyncQueryHandler<Q, Ev>>::handle(query, &QueryContext::new(self))
+                        .await;
+                let m = self.basic.lock().await;
+                m.caches.store(miss, &response, m.clock.now());
+                response
+            }
+            Lookup::Uncached => {
+                <Self as AsyncQueryHandler<Q, Ev>>::handle(query, &QueryContext::new(self)).await
+            }
+        }
+    }
+}
+
+impl<'a, Ev> QueryContext<'a, BasicAsyncMediator<Ev>>
+where
+    Ev: Debug + Send,
+{
+    /// Answer another query of type `Q` asynchronously while answering a query.
+    ///
+    /// See [`AsyncMediatorInternalCqrs::query()`].
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    pub async fn query<Q>(
+        &self,
+        query: Q,
+    ) -> <BasicAsyncMediator<Ev> as AsyncQueryHandler<Q, Ev>>::Response
+    where
+        BasicAsyncMediator<Ev>: AsyncQueryHandler<Q, Ev>,
+        Q: Send + 'static,
+    {
+        self.mediator.query(query).await
+    }
+}
+
+#[async_trait]
+impl<Ev> AsyncMediatorInternalIdempotency<Ev> for BasicAsyncMediator<Ev>
+where
+    Ev: Debug + Send,
+{
+    /// Send an [`Idempotent`] request of type `Req` to the mediator asynchronously,
+    /// unless a request of the same type with the same key was already handled.
+    ///
+    /// The request will be processed internally by [`AsyncRequestHandler::handle()`].
+    /// Requests are only deduplicated if enabled via
+    /// [`super::BasicAsyncBuilder::deduplicate_requests()`].
+    /// Duplicates sent while the first request is still being handled wait for it.
+    /// A duplicate sent by the handler of the request itself cannot wait for it
+    /// and returns right away without being handled.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn send_idempotent<Req>(&self, req: Req)
+    where
+        Req: Idempotent + Send,
+        Self: AsyncRequestHandler<Req, Ev>,
+    {
+        let key = self.basic.lock().await.dedup.key(&req);
+        let key = match key {
+            Some(key) => key,
+            None => return self.send(req).await,
+        };
+        loop {
+            match self.in_flight.reserve(&key) {
+                Reservation::Waiting(outcome) => {
+                    if wait::<()>(outcome).await.is_some() {
+                        return;
+                    }
+                }
+                Reservation::Reentered => return,
+                Reservation::Claimed(claim) => {
+                    let cached = self.basic.lock().await.dedup.cached::<()>(&key);
+                    if cached.is_none() {
+                        claim.hold(self.send(req)).await;
+                        self.basic.lock().await.dedup.record(key, ());
+                    }
+                    return claim.complete(());
+                }
+            }
+        }
+    }
+
+    /// Send an [`Idempotent`] request of type `Req` to the mediator asynchronously,
+    /// unless a request of the same type with the same key was already handled.
+    ///
+    /// The request will be processed internally by [`TryAsyncRequestHandler::try_handle()`].
+    /// A successful result is cached and returned for duplicates,
+    /// whereas a failed request is handled again when it is sent again with the same key.
+    /// Duplicates sent while the first request is still being handled
+    /// wait for it and return its result, whether it succeeded or not.
+    /// A duplicate sent by the handler of the request itself cannot wait for it
+    /// and returns `Ok(())` right away without being handled,
+    /// leaving the result to the request that is being handled.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn try_send_idempotent<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), <Self as TryAsyncRequestHandler<Req, Ev>>::Error>
+    where
+        Req: Idempotent + Send,
+        Self: TryAsyncRequestHandler<Req, Ev>,
+        <Self as TryAsyncRequestHandler<Req, Ev>>::Error: Clone + Sync + 'static,
+    {
+        let key = self.basic.lock().await.dedup.key(&req);
+        let key = match key {
+            Some(key) => key,
+            None => return self.try_send(req).await,
+        };
+        loop {
+            match self.in_flight.reserve(&key) {
+                Reservation::Waiting(outcome) => {
+                    if let Some(outcome) = wait(outcome).await {
+                        return outcome;
+                    }
+                }
+                Reservation::Reentered => return Ok(()),
+                Reservation::Claimed(claim) => {
+                    let cached = self.basic.lock().await.dedup.cached(&key);
+                    let outcome = match cached {
+                        Some(outcome) => outcome,
+                        None => {
+                            let outcome = claim.hold(self.try_send(req)).await;
+                            if outcome.is_ok() {
+                                self.basic.lock().await.dedup.record(key, outcome.clone());
+                            }
+                            outcome
+                        }
+                    };
+                    claim.complete(outcome.clone());
+                    return outcome;
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<Ev> AsyncMediatorInternalHistory<Ev> for BasicAsyncMediator<Ev>
+where
+    Ev: Debug + Send + 'static,
+{
+    /// Send an [`Undoable`] request of type `Req` to the mediator asynchronously
+    /// and record it in the command history.
+    ///
+    /// The request will be processed internally by [`AsyncRequestHandler::handle()`].
+    /// It is only recorded if the history was enabled via
+    /// [`super::BasicAsyncBuilder::command_history()`].
+    /// Undoing it sends its [`Undoable::inverse()`], redoing it sends it again.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn send_undoable<Req>(&self, req: Req)
+    where
+        Req: Undoable,
+        Self: AsyncRequestHandler<Req, Ev> + AsyncRequestHandler<Req::Inverse, Ev>,
+    {
+        let (undo, redo) = (req.clone(), req.clone());
+        self.history.lock().unwrap().record(
+            req.label(),
+            Box::new(move |m| {
+                let inverse = undo.inverse();
+                Box::pin(async move { m.send(inverse).await })
+            }),
+            Box::new(move |m| {
+                let req = redo.clone();
+                Box::pin(async move { m.send(req).await })
+            }),
+        );
+        self.send(req).await
+    }
+
+    /// Undoes the last step of the command history asynchronously by sending
+    /// the inverses of its requests in reverse order.
+    ///
+    /// Returns `false` if there is nothing to undo.
+    /// The inverses are sent as plain requests, so they are not recorded.
+    /// If undoing is interrupted, e.g. the `Future` is dropped or a request panics,
+    /// the step stays in the history to be undone again.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn undo(&self) -> bool {
+        match Replay::undo(&self.history) {
+            Some(replay) => {
+                for command in replay.step().commands.iter().rev() {
+                    (command.undo)(self).await;
+                }
+                replay.complete();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Redoes the last undone step of the command history asynchronously
+    /// by sending its requests again.
+    ///
+    /// Returns `false` if there is nothing to redo.
+    /// Sending a new [`Undoable`] request discards the steps to redo.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn redo(&self) -> bool {
+        match Replay::redo(&self.history) {
+            Some(replay) => {
+                for command in &replay.step().commands {
+                    (command.redo)(self).await;
+                }
+                replay.complete();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Groups the [`Undoable`] requests sent until `end_group()`
+    /// into a single step named `label`.
+    ///
+    /// Groups may be nested, the outermost one forms the step.
+    ///
+    fn begin_group(&self, label: &str) {
+        self.history.lock().unwrap().begin_group(label.to_owned());
+    }
+
+    /// Closes the group opened by `begin_group()`.
+    ///
+    fn end_group(&self) {
+        self.history.lock().unwrap().end_group();
+    }
+
+    /// Returns a snapshot of the steps that can be undone and redone.
+    ///
+    fn history(&self) -> CommandHistory {
+        self.history.lock().unwrap().snapshot()
+    }
+
+    /// Discards all steps of the command history.
+    ///
+    fn clear_history(&self) {
+        self.history.lock().unwrap().clear();
+    }
+}
+
+#[async_trait]
+impl<Ev> AsyncMediatorInternalTimeout<Ev> for BasicAsyncMediator<Ev>
+where
+    Ev: Debug,
+{
+    /// Send a request of type `Req` to the mediator asynchronously
+    /// and interrupt the handler once `timeout` elapsed.
+    ///
+    /// The request will be processed internally by [`AsyncRequestHandler::handle()`].
+    /// If the handler does not finish in time, it is dropped and
+    /// [`Interrupted::TimedOut`] is returned.
+    ///
+    /// If this request is sent from within another handler,
+    /// the earlier of both deadlines applies and the cancellation tokens
+    /// of the triggering request are inherited.
+    /// Handlers can observe the deadline via [`cancellation::current()`].
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::basic::*;
+    /// use async_trait::async_trait;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Done,
+    /// }
+    ///
+    /// struct SlowRequest;
+    ///
+    /// #[async_trait]
+    /// impl AsyncRequestHandler<SlowRequest, MyEvent> for BasicAsyncMediator<MyEvent> {
+    ///     async fn handle(&self, _req: SlowRequest) {
+    ///         async_std::task::sleep(Duration::from_secs(60)).await;
+    ///         self.publish(MyEvent::Done).await
+    ///     }
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = BasicAsyncMediator::<MyEvent>::builder().build();
+    ///
+    ///     let result = mediator.send_with_timeout(SlowRequest, Duration::from_millis(10)).await;
+    ///     assert_eq!(result, Err(Interrupted::TimedOut));
+    /// });
+    ///
+    async fn send_with_timeout<Req>(&self, req: Req, timeout: Duration) -> Result<(), Interrupted>
+    where
+        Self: AsyncRequestHandler<Req, Ev>,
+        Req: Send,
+    {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.send_with_deadline(req, deadline).await,
+            None => cancellation::guard(cancellation::current(), self.send(req)).await,
+        }
+    }
+
+    /// Send a request of type `Req` to the mediator asynchronously
+    /// and interrupt the handler once `deadline` passed.
+    ///
+    /// See [`BasicAsyncMediator::send_with_timeout()`] for more info.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn send_with_deadline<Req>(&self, req: Req, deadline: Instant) -> Result<(), Interrupted>
+    where
+        Self: AsyncRequestHandler<Req, Ev>,
+        Req: Send,
+    {
+        let cx = cancellation::current().with_deadline(deadline);
+        cancellation::guard(cx, self.send(req)).await
+    }
+
+    /// Send a request of type `Req` to the mediator asynchronously
+    /// and interrupt the handler once `token` is cancelled.
+    ///
+    /// The request will be processed internally by [`AsyncRequestHandler::handle()`].
+    /// If the token is cancelled before the handler finished, the handler is dropped
+    /// and [`Interrupted::Cancelled`] is returned.
+    ///
+    /// If this request is sent from within another handler,
+    /// the deadline and cancellation tokens of the triggering request are inherited.
+    /// Handlers can observe the cancellation via [`cancellation::current()`].
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::basic::*;
+    /// use async_trait::async_trait;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Done,
+    /// }
+    ///
+    /// struct Request;
+    ///
+    /// #[async_trait]
+    /// impl AsyncRequestHandler<Request, MyEvent> for BasicAsyncMediator<MyEvent> {
+    ///     async fn handle(&self, _req: Request) {
+    ///         self.publish(MyEvent::Done).await
+    ///     }
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = BasicAsyncMediator::<MyEvent>::builder().build();
+    ///     let token = CancellationToken::new();
+    ///
+    ///     assert!(mediator.send_with_cancellation(Request, token.clone()).await.is_ok());
+    ///
+    ///     token.cancel();
+    ///     let result = mediator.send_with_cancellation(Request, token).await;
+    ///     assert_eq!(result, Err(Interrupted::Cancelled));
+    /// });
+    ///
+    async fn send_with_cancellation<Req>(
+        &self,
+        req: Req,
+        token: CancellationToken,
+    ) -> Result<(), Interrupted>
+    where
+        Self: AsyncRequestHandler<Req, Ev>,
+        Req: Send,
+    {
+        let cx = cancellation::current().with_token(token);
+        cancellation::guard(cx, self.send(req)).await
+    }
+}
+
+#[async_trait]
+impl<Ev> AsyncMediatorInternalTryHandle<Ev> for BasicAsyncMediator<Ev>
+where
+    Ev: Debug + Send,
+{
+    /// Send a request of type `Req` to the mediator asynchronously.
+    ///
+    /// The request will be processed internally by [`TryAsyncRequestHandler::try_handle()`].
+    /// This is why it is required to implement [`TryAsyncRequestHandler`] for [`BasicAsyncMediator`].
+    /// The result of the handler is returned.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::basic::*;
+    /// use async_trait::async_trait;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     One,
+    ///     Two
+    /// }
+    ///
+    /// struct Request(u32);
+    ///
+    /// #[async_trait]
+    /// impl TryAsyncRequestHandler<Request, MyEvent> for BasicAsyncMediator<MyEvent> {
+    ///     type Error = String;
+    ///
+    ///     async fn try_handle(&self, req: Request) -> Result<(), String> {
+    ///         match req.0 {
+    ///             1 => self.publish(MyEvent::One).await,
+    ///             2 => self.publish(MyEvent::Two).await,
+    ///             n => return Err(format!("unknown request {}", n)),
+    ///         };
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = BasicAsyncMediator::<MyEvent>::builder().build();
+    ///
+    ///     assert!(mediator.try_send(Request(1)).await.is_ok());
+    ///     assert!(mediator.try_send(Request(3)).await.is_err());
+    /// });
+    ///
+    async fn try_send<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), <Self as TryAsyncRequestHandler<Req, Ev>>::Error>
+    where
+        Self: TryAsyncRequestHandler<Req, Ev>,
+        Req: Send,
+    {
+        self.transact(<Self as TryAsyncRequestHandler<Req, Ev>>::try_handle(
+            self, req,
+        ))
+        .await
+    }
+}
+
+#[async_trait]
+impl<Ev> AsyncMediatorInternalRetry<Ev> for BasicAsyncMediator<Ev>
+where
+    Ev: Debug + Send,
+{
+    /// Send a request of type `Req` to the mediator asynchronously
+    /// and retry it if the handler fails.
+    ///
+    /// The request will be processed internally by [`TryAsyncRequestHandler::try_handle()`].
+    /// If a [`crate::retry::RetryPolicy`] was added for `Req` via
+    /// [`super::BasicAsyncBuilder::add_retry_policy()`], failed attempts are retried
+    /// according to it, waiting on the [`crate::clock::Clock`] of the mediator in between.
+    /// Otherwise, the request is sent exactly once.
+    /// The error of the last attempt is returned.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::basic::*;
+    /// use async_trait::async_trait;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     One,
+    ///     Two
+    /// }
+    ///
+    /// #[derive(Clone)]
+    /// struct Request;
+    ///
+    /// #[async_trait]
+    /// impl TryAsyncRequestHandler<Request, MyEvent> for BasicAsyncMediator<MyEvent> {
+    ///     type Error = String;
+    ///
+    ///     async fn try_handle(&self, _req: Request) -> Result<(), String> {
+    ///         Err(String::from("service unavailable"))
+    ///     }
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let clock = std::sync::Arc::new(MockClock::new());
+    ///     let mediator = BasicAsyncMediator::<MyEvent>::builder()
+    ///         .add_retry_policy::<Request>(RetryPolicy::new(3).fixed(Duration::from_secs(1)))
+    ///         .clock(clock.clone())
+    ///         .build();
+    ///
+    ///     assert!(mediator.send_with_retry(Request).await.is_err());
+    ///     assert_eq!(clock.sleeps(), vec![Duration::from_secs(1); 2]);
+    /// });
+    ///
+    async fn send_with_retry<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), <Self as TryAsyncRequestHandler<Req, Ev>>::Error>
+    where
+        Req: Clone + Send + 'static,
+        Self: TryAsyncRequestHandler<Req, Ev>,
+        <Self as TryAsyncRequestHandler<Req, Ev>>::Error: 'static,
+    {
+        let (policy, clock, dead) = {
+            let m = self.basic.lock().await;
+            (
+                m.retry
+                    .get::<Req, <Self as TryAsyncRequestHandler<Req, Ev>>::Error>()
+                    .cloned(),
+                m.clock.clone(),
+                m.dead_letters.is_enabled().then(|| req.clone()),
+            )
+        };
+
+        let result = match policy {
+            Some(policy) => {
+                retry::retry_async(&policy, &*clock, req, |req| self.try_send(req)).await
+            }
+            None => self.try_send(req).await,
+        };
+
+        match (dead, result) {
+            (Some(req), Err(err)) => {
+                self.basic.lock().await.dead_letters.request(&req, &err);
+                Err(err)
+            }
+            (_, result) => result,
+        }
+    }
+}
+
+#[async_trait]
+impl<Ev> AsyncMediatorInternalGuard<Ev> for BasicAsyncMediator<Ev>
+where
+    Ev: Debug + Send,
+{
+    /// Send a request of type `Req` to the mediator asynchronously
+    /// through the circuit breaker and bulkhead of its request type.
+    ///
+    /// The request will be processed internally by [`TryAsyncRequestHandler::try_handle()`],
+    /// unless the [`crate::asynchronous::guard::CircuitBreaker`] added via
+    /// [`super::BasicAsyncBuilder::add_circuit_breaker()`] is open or the bulkhead
+    /// added via [`super::BasicAsyncBuilder::add_bulkhead()`] is full.
+    /// Then, the request is rejected right away.
+    /// Every state transition of the breaker is published as an event
+    /// if mapped via [`super::BasicAsyncBuilder::on_breaker_transition()`].
+    ///
+    /// Without a breaker or bulkhead for `Req`, this behaves like `try_send()`.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::basic::*;
+    /// use async_trait::async_trait;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Breaker(BreakerTransition),
+    /// }
+    ///
+    /// struct Request;
+    ///
+    /// #[async_trait]
+    /// impl TryAsyncRequestHandler<Request, MyEvent> for BasicAsyncMediator<MyEvent> {
+    ///     type Error = String;
+    ///
+    ///     async fn try_handle(&self, _req: Request) -> Result<(), String> {
+    ///         Err(String::from("service unavailable"))
+    ///     }
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = BasicAsyncMediator::<MyEvent>::builder()
+    ///         .add_circuit_breaker::<Request>(CircuitBreaker::new(1))
+    ///         .on_breaker_transition(MyEvent::Breaker)
+    ///         .build();
+    ///
+    ///     assert!(mediator.send_guarded(Request).await.is_err());
+    ///     assert_eq!(mediator.breaker_state::<Request>(), Some(BreakerState::Open));
+    ///     assert_eq!(mediator.send_guarded(Request).await, Err(GuardError::CircuitOpen));
+    /// });
+    ///
+    async fn send_guarded<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), GuardError<<Self as TryAsyncRequestHandler<Req, Ev>>::Error>>
+    where
+        Req: Send + 'static,
+        Self: TryAsyncRequestHandler<Req, Ev>,
+    {
+        let clock = self.basic.lock().await.clock.clone();
+        let (result, events) = self
+            .guards
+            .run::<Req, _, _>(&*clock, self.try_send(req))
+            .await;
+        for event in events {
+            self.publish(event).await;
+        }
+        result
+    }
+
+    /// Returns the [`BreakerState`] of the circuit breaker for `Req`, if any.
+    ///
+    fn breaker_state<Req>(&self) -> Option<BreakerState>
+    where
+        Req: 'static,
+    {
+        self.guards.state::<Req>()
+    }
+}
+
+impl<Ev> AsyncMediatorInternalSink<Ev> for BasicAsyncMediator<Ev>
+where
+    Ev: Debug + Send,
+{
+    /// Creates a [`RequestSink`] that sends every request of type `Req` to the mediator.
+    ///
+    /// The requests are processed by [`TryAsyncRequestHandler::try_handle()`].
+    /// At most `concurrency` handlers run at the same time; a `concurrency` of `0` is treated as `1`.
+    /// The first error of a failed handler is returned by the sink.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::basic::*;
+    /// use async_trait::async_trait;
+    /// use futures::{stream, StreamExt};
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     One,
+    ///     Two
+    /// }
+    ///
+    /// struct Request(u32);
+    ///
+    /// #[async_trait]
+    /// impl TryAsyncRequestHandler<Request, MyEvent> for BasicAsyncMediator<MyEvent> {
+    ///     type Error = String;
+    ///
+    ///     async fn try_handle(&self, req: Request) -> Result<(), String> {
+    ///         match req.0 {
+    ///             1 => self.publish(MyEvent::One).await,
+    ///             2 => self.publish(MyEvent::Two).await,
+    ///             n => return Err(format!("unknown request {}", n)),
+    ///         };
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = BasicAsyncMediator::<MyEvent>::builder().build();
+    ///
+    ///     let requests = stream::iter(vec![Request(1), Request(2)]).map(Ok);
+    ///     assert!(requests.forward(mediator.request_sink(4)).await.is_ok());
+    ///
+    ///     let requests = stream::iter(vec![Request(1), Request(3)]).map(Ok);
+    ///     assert!(requests.forward(mediator.request_sink(4)).await.is_err());
+    /// });
+    ///
+    fn request_sink<'a, Req>(
+        &'a self,
+        concurrency: usize,
+    ) -> RequestSink<'a, Req, <Self as TryAsyncRequestHandler<Req, Ev>>::Error>
+    where
+        Req: Send + 'a,
+        Self: TryAsyncRequestHandler<Req, Ev>,
+    {
+        RequestSink::new(concurrency, move |req| self.try_send(req))
+    }
+}
+
+#[async_trait]
+impl<Ev> AsyncMediatorInternalDeadLetter<Ev> for BasicAsyncMediator<Ev>
+where
+    Ev: Debug + Send,
+{
+    /// Returns all [`DeadLetter`]s in the order they were collected.
+    ///
+    /// The dead-letter queue is enabled via [`super::BasicAsyncBuilder::dead_letter_queue()`].
+    /// It collects events dispatched while there are no listeners,
+    /// events whose listener panicked and requests of the types added via
+    /// [`super::BasicAsyncBuilder::dead_letter_requests()`] that failed in
+    /// [`AsyncMediatorInternalRetry::send_with_retry()`].
+    ///
+    /// See [`crate::synchronous::basic::SyncMediatorInternalDeadLetter::dead_letters()`] for more info.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone, PartialEq)]
+    /// enum MyEvent {
+    ///     One,
+    ///     Two
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = BasicAsyncMediator::<MyEvent>::builder()
+    ///         .dead_letter_queue(MemoryStorage::new())
+    ///         .build();
+    ///
+    ///     mediator.publish(MyEvent::Two).await;
+    ///     mediator.next().await.ok();
+    ///
+    ///     let letters = mediator.dead_letters().await.unwrap();
+    ///     assert_eq!(letters[0].letter, Letter::Event(MyEvent::Two));
+    ///
+    ///     assert_eq!(mediator.purge_dead_letters().await.unwrap(), 1);
+    /// });
+    ///
+    async fn dead_letters(&self) -> io::Result<Vec<DeadLetter<Ev>>> {
+        self.basic.lock().await.dead_letters.load()
+    }
+
+    /// Removes all dead events from the queue and publishes them again.
+    ///
+    /// Returns the number of re-published events.
+    ///
+    async fn redispatch_events(&self) -> io::Result<usize> {
+        let m = self.basic.lock().await;
+        let events = m.dead_letters.take_events()?;
+        let count = events.len();
+        for ev in events {
+            m.publish(ev);
+        }
+        Ok(count)
+    }
+
+    /// Removes all failed requests of type `Req` from the queue
+    /// and sends them again via [`AsyncMediatorInternalRetry::send_with_retry()`].
+    ///
+    /// Returns the result of every request. Requests that fail again
+    /// are collected again with a new id.
+    ///
+    async fn redispatch_requests<Req>(
+        &self,
+    ) -> io::Result<Vec<Result<(), <Self as TryAsyncRequestHandler<Req, Ev>>::Error>>>
+    where
+        Req: Record + Clone + Send + 'static,
+        Self: TryAsyncRequestHandler<Req, Ev>,
+        <Self as TryAsyncRequestHandler<Req, Ev>>::Error: 'static,
+    {
+        let requests = self
+            .basic
+            .lock()
+            .await
+            .dead_letters
+            .take_requests::<Req>()?;
+        let mut results = Vec::with_capacity(requests.len());
+        for req in requests {
+            results.push(self.send_with_retry(req).await);
+        }
+        Ok(results)
+    }
+
+    /// Removes all [`DeadLetter`]s and returns how many were removed.
+    ///
+    async fn purge_dead_letters(&self) -> io::Result<usize> {
+        self.basic.lock().await.dead_letters.purge()
+    }
+}
+
+#[async_trait]
+impl<Ev> AsyncMediatorInternalNext for BasicAsyncMediator<Ev>
+where
+    Ev: Debug + Clone + Send,
+{
+    /// Process the next published event `Ev` asynchronously.
+    ///
+    /// This method locks the `Mutex` and instructs
+    /// the underlying [`BasicMediator`] to process the next event.
+    ///
+    /// See [`BasicMediator::next()`] for more info.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn next(&self) -> Result<(), TryRecvError> {
+        let result = self.basic.lock().await.next();
+        self.sagas.run(self).await;
+        result
+    }
+
+    /// Process the next event asynchronously, waiting for one to arrive if necessary.
+    ///
+    /// Behaves like [`BasicAsyncMediator::next()`], but if no event is ready,
+    /// it waits until an event is published on the mediator, propagated to it
+    /// by a linked mediator or forwarded to it by a bridge,
+    /// e.g. from a [`BasicMediator`] in a blocking worker.
+    /// The mediator is not locked and the executor is not blocked while waiting.
+    /// Scheduled events are only published once another event arrives,
+    /// use [`BasicAsyncMediator::next_scheduled()`] to wait for them.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::basic::*;
+    /// use mediator_sys::synchronous::basic::{
+    ///     BasicMediator, SyncMediatorInternal, SyncMediatorInternalNext,
+    /// };
+    /// use std::thread;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum Job {
+    ///     Done(u32),
+    /// }
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum Message {
+    ///     Ready(u32),
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let server = BasicAsyncMediator::<Message>::builder()
+    ///         .add_listener(|ev| {
+    ///             /* Your listening logic */
+    ///         })
+    ///         .build();
+    ///     let server_link = server.link();
+    ///
+    ///     let worker = thread::spawn(move || {
+    ///         let worker = BasicMediator::<Job>::builder()
+    ///             .add_bridge(&server_link, |Job::Done(id)| Some(Message::Ready(id)))
+    ///             .build();
+    ///         worker.publish(Job::Done(1));
+    ///         worker.recv().unwrap();
+    ///     });
+    ///
+    ///     // Waits until the event is forwarded by the worker.
+    ///     server.recv().await.unwrap();
+    ///     worker.join().unwrap();
+    /// });
+    ///
+    async fn recv(&self) -> Result<(), RecvError> {
+        let result = loop {
+            let arrival = {
+                let m = self.basic.lock().await;
+                let arrival = m.channel.0.arrivals.next();
+                match m.next() {
+                    Err(TryRecvError::Empty) => arrival,
+                    result => break result.map_err(|_| RecvError),
+                }
+            };
+            arrival.await;
+        };
+        self.sagas.run(self).await;
+        result
+    }
+}
+
+#[async_trait]
+impl<Ev> AsyncMediatorInternalSchedule<Ev> for BasicAsyncMediator<Ev>
+where
+    Ev: Debug + Clone + Send,
+{
+    /// Schedules the event `Ev` to be published after `delay`.
+    ///
+    /// The event is published by the first call of [`BasicAsyncMediator::next()`]
+    /// once `delay` passed on the [`crate::clock::Clock`] of the mediator.
+    /// Returns a [`ScheduledId`] to cancel the event.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::basic::*;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     TimedOut,
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = BasicAsyncMediator::<MyEvent>::builder()
+    ///         .add_listener(|ev| {
+    ///             /* Your listening logic */
+    ///         })
+    ///         .build();
+    ///
+    ///     mediator.publish_after(MyEvent::TimedOut, Duration::from_millis(10)).await;
+    ///     assert!(mediator.next().await.is_err());
+    ///
+    ///     // Waits on the runtime timer until the event is due.
+    ///     assert!(mediator.next_scheduled().await.is_ok());
+    /// });
+    ///
+    async fn publish_after(&self, event: Ev, delay: Duration) -> ScheduledId {
+        self.basic.lock().await.publish_after(event, delay)
+    }
+
+    /// Schedules the event `Ev` to be published at `at`.
+    ///
+    /// See [`BasicAsyncMediator::publish_after()`] for more info.
+    ///
+    async fn publish_at(&self, event: Ev, at: Instant) -> ScheduledId {
+        self.basic.lock().await.publish_at(event, at)
+    }
+
+    /// Schedules an event created by `f` to be published every `interval`,
+    /// starting one `interval` from now.
+    ///
+    /// See [`BasicMediator::publish_every()`] for more info.
+    ///
+    async fn publish_every<F>(&self, f: F, interval: Duration) -> ScheduledId
+    where
+        F: FnMut() -> Ev + Send + 'static,
+    {
+        self.basic.lock().await.publish_every(f, interval)
+    }
+
+    /// Cancels the scheduled event with `id`.
+    ///
+    /// Returns `false` if the event was already published or cancelled before.
+    ///
+    async fn cancel_scheduled(&self, id: ScheduledId) -> bool {
+        self.basic.lock().await.cancel_scheduled(id)
+    }
+
+    /// Process the next event asynchronously, waiting for scheduled events if necessary.
+    ///
+    /// Behaves like [`BasicAsyncMediator::next()`], but if no event is ready,
+    /// it waits asynchronously on the [`crate::clock::Clock`] of the mediator
+    /// until the next scheduled or debounced event is due.
+    /// The mediator is not locked while waiting.
+    /// Only returns [`TryRecvError::Empty`] if nothing is scheduled.
+    ///
+    async fn next_scheduled(&self) -> Result<(), TryRecvError> {
+        let result = loop {
+            let wait = {
+                let m = self.basic.lock().await;
+                match m.next() {
+                    Err(TryRecvError::Empty) => match m.next_due() {
+                        Some(due) => m
+                            .clock
+                            .sleep_async(due.saturating_duration_since(m.clock.now())),
+                        None => break Err(TryRecvError::Empty),
+                    },
+                    result => break result,
+                }
+            };
+            wait.await;
+        };
+        self.sagas.run(self).await;
+        result
+    }
+}
+
+#[async_trait]
+impl<Ev> AsyncMediatorInternalFlush for BasicAsyncMediator<Ev>
+where
+    Ev: Debug + Clone + Send,
+{
+    /// Flushes the events buffered by batch and debounced listeners asynchronously.
+    ///
+    /// Every batch listener is called with its pending batch
+    /// and every debounced listener with its pending event,
+    /// regardless of whether they are due.
+    /// It also waits until parallel listeners processed all dispatched events,
+    /// without holding the mediator or blocking the executor meanwhile.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::basic::*;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Indexed(u32),
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = BasicAsyncMediator::<MyEvent>::builder()
+    ///         .add_batch_listener(100, Duration::from_secs(60), |batch: Vec<MyEvent>| {
+    ///             assert_eq!(batch.len(), 2);
+    ///         })
+    ///         .build();
+    ///
+    ///     mediator.publish(MyEvent::Indexed(1)).await;
+    ///     mediator.publish(MyEvent::Indexed(2)).await;
+    ///     mediator.next().await.ok();
+    ///     mediator.next().await.ok();
+    ///
+    ///     // Called with both events, long before the batch is due.
+    ///     mediator.flush().await;
+    /// });
+    ///
+    async fn flush(&self) {
+        let idle = self.basic.lock().await.flush_timed();
+        // Waits without the mediator, so other tasks can use it meanwhile.
+        for idle in idle {
+            async_std::task::spawn_blocking(move || idle.wait()).await;
+        }
+    }
+}
+
+#[async_trait]
+impl<Ev> AsyncMediatorInternalNotify for BasicAsyncMediator<Ev>
+where
+    Ev: Debug + Send,
+{
+    /// Publishes a [`Notification`] `N` asynchronously.
+    ///
+    /// Every notification handler registered for `N` via
+    /// [`super::BasicAsyncBuilder::add_notification_handler()`] receives
+    /// a clone of the notification. The handlers are invoked according to
+    /// the [`NotificationStrategy`] chosen on the builder.
+    /// The errors of all failed handlers are returned.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     One,
+    ///     Two
+    /// }
+    ///
+    /// #[derive(Clone)]
+    /// struct UserRegistered(String);
+    ///
+    /// impl Notification for UserRegistered {
+    ///     type Error = String;
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = BasicAsyncMediator::<MyEvent>::builder()
+    ///         .add_notification_handler(|n: UserRegistered| async move {
+    ///             /* Send a welcome mail */
+    ///             Ok(())
+    ///         })
+    ///         .add_notification_handler(|n: UserRegistered| async move {
+    ///             Err(format!("could not update statistics for {}", n.0))
+    ///         })
+    ///         .build();
+    ///
+    ///     let result = mediator.publish_notification(UserRegistered(String::from("Jan"))).await;
+    ///     assert_eq!(result.unwrap_err().len(), 1);
+    /// });
+    ///
+    async fn publish_notification<N>(&self, notification: N) -> Result<(), Vec<N::Error>>
+    where
+        N: Notification,
+    {
+        self.notifications
+            .publish(notification, (), self.notifications.strategy)
+            .await
+    }
+
+    /// Publishes a [`Notification`] `N` asynchronously using the given [`NotificationStrategy`].
+    ///
+    /// See [`BasicAsyncMediator::publish_notification()`] for more info.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn publish_notification_with<N>(
+        &self,
+        notification: N,
+        strategy: NotificationStrategy,
+    ) -> Result<(), Vec<N::Error>>
+    where
+        N: Notification,
+    {
+        self.notifications.publish(notification, (), strategy).await
+    }
+}
+
+#[async_trait]
+impl<Ev> AsyncMediatorInternalSubscribe<Ev> for BasicAsyncMediator<Ev>
+where
+    Ev: Debug + Clone + Send + 'static,
+{
+    /// Subscribes to the events `Ev` of the mediator as a [`futures::Stream`].
+    ///
+    /// Every event dispatched by [`BasicAsyncMediator::next()`] after the subscription
+    /// is buffered for this subscriber until the returned [`EventStream`] consumes it.
+    /// The buffer is unbounded, so a stream that is not consumed keeps every event;
+    /// see [`BasicAsyncMediator::subscribe_stream_bounded()`] to limit it.
+    /// The stream ends once the mediator is dropped.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::basic::*;
+    /// use async_trait::async_trait;
+    /// use futures::StreamExt;
+    ///
+    /// #[derive(Debug, Clone, PartialEq)]
+    /// enum MyEvent {
+    ///     One,
+    ///     Two
+    /// }
+    ///
+    /// struct Request(u32);
+    ///
+    /// #[async_trait]
+    /// impl AsyncRequestHandler<Request, MyEvent> for BasicAsyncMediator<MyEvent> {
+    ///     async fn handle(&self, req: Request) {
+    ///         match req.0 {
+    ///             1 => self.publish(MyEvent::One).await,
+    ///             2 => self.publish(MyEvent::Two).await,
+    ///             _ => ()
+    ///         };
+    ///     }
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = BasicAsyncMediator::<MyEvent>::builder().build();
+    ///     let stream = mediator.subscribe_stream().await;
+    ///
+    ///     mediator.send(Request(1)).await;
+    ///     mediator.send(Request(2)).await;
+    ///     while mediator.next().await.is_ok() {}
+    ///     drop(mediator);
+    ///
+    ///     let events: Vec<MyEvent> = stream.collect().await;
+    ///     assert_eq!(events, vec![MyEvent::One, MyEvent::Two]);
+    /// });
+    ///
+    async fn subscribe_stream(&self) -> EventStream<Ev> {
+        let mut m = self.basic.lock().await;
+        self.subscribers.subscribe(&mut m)
+    }
+
+    /// Subscribes to the events `Ev` of the mediator as a [`futures::Stream`],
+    /// buffering at most `capacity` events for this subscriber.
+    ///
+    /// If the buffer is full when an event is dispatched, the [`Overflow`] policy applies:
+    /// the event is either dropped for this subscriber and counted by [`EventStream::lagged()`],
+    /// or the stream is closed.
+    /// Unlike [`BasicAsyncMediator::subscribe_stream()`], a slow or forgotten stream
+    /// therefore never buffers more than `capacity` events.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::basic::*;
+    /// use futures::StreamExt;
+    ///
+    /// #[derive(Debug, Clone, PartialEq)]
+    /// struct Tick(u32);
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = BasicAsyncMediator::<Tick>::builder().build();
+    ///     let stream = mediator.subscribe_stream_bounded(2, Overflow::DropNewest).await;
+    ///
+    ///     for n in 0..5 {
+    ///         mediator.publish(Tick(n)).await;
+    ///     }
+    ///     while mediator.next().await.is_ok() {}
+    ///     assert_eq!(stream.lagged(), 3);
+    ///     drop(mediator);
+    ///
+    ///     let events: Vec<Tick> = stream.collect().await;
+    ///     assert_eq!(events, vec![Tick(0), Tick(1)]);
+    /// });
+    ///
+    async fn subscribe_stream_bounded(
+        &self,
+        capacity: usize,
+        overflow: Overflow,
+    ) -> EventStream<Ev> {
+        let mut m = self.basic.lock().await;
+        self.subscribers
+            .subscribe_bounded(&mut m, capacity, overflow)
     }
 }
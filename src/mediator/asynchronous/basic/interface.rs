@@ -1,5 +1,31 @@
 use async_trait::async_trait;
-use std::{fmt::Debug, sync::mpsc::TryRecvError};
+use std::{
+    fmt::Debug,
+    future::Future,
+    hash::Hash,
+    io,
+    sync::mpsc::{RecvError, TryRecvError},
+    time::{Duration, Instant},
+};
+
+use crate::mediator::asynchronous::{
+    cancellation::{CancellationToken, Interrupted},
+    guard::{BreakerState, BreakerTransition, CircuitBreaker, GuardError},
+    notification::{Notification, NotificationStrategy},
+    sink::RequestSink,
+    stream::{EventStream, Overflow},
+};
+use crate::mediator::{
+    cache::QueryCache,
+    dead_letter::DeadLetter,
+    history::{CommandHistory, Undoable},
+    idempotency::Idempotent,
+    priority::Priority,
+    saga::{Saga, SagaInstance},
+    schedule::ScheduledId,
+    storage::{Record, Storage},
+};
+use crate::synchronous::basic::QueryContext;
 
 /// Publish an event `Ev` asynchronously from within a handler.
 #[async_trait]
@@ -7,6 +33,25 @@ pub trait AsyncMediatorInternal<Ev: Debug> {
     async fn publish(&self, event: Ev);
 }
 
+/// Publish an event `Ev` with a [`Priority`] asynchronously from within a handler.
+#[async_trait]
+pub trait AsyncMediatorInternalPriority<Ev: Debug> {
+    async fn publish_with_priority(&self, event: Ev, priority: Priority);
+}
+
+/// Publish an event `Ev` to a topic asynchronously from within a handler.
+#[async_trait]
+pub trait AsyncMediatorInternalTopic<Ev: Debug> {
+    async fn publish_to(&self, topic: &str, event: Ev);
+}
+
+/// Publish an event `Ev` asynchronously from within a transactional handler,
+/// bypassing the outbox of the request.
+#[async_trait]
+pub trait AsyncMediatorInternalOutbox<Ev: Debug> {
+    async fn publish_immediately(&self, event: Ev);
+}
+
 /// Send a request `Req` asynchronously for processing to the mediator.
 /// This will call the handler.
 #[async_trait]
@@ -17,11 +62,228 @@ pub trait AsyncMediatorInternalHandle<Ev: Debug> {
         Self: AsyncRequestHandler<Req, Ev>;
 }
 
-/// Process the next event `Ev` from the channel asynchronously.
+/// Execute a command `Cmd` asynchronously, which may publish events, or
+/// answer a query `Q` asynchronously, which cannot.
+/// This will call the command or query handler.
+#[async_trait]
+pub trait AsyncMediatorInternalCqrs<Ev: Debug> {
+    async fn execute<Cmd>(&self, cmd: Cmd)
+    where
+        Cmd: Send,
+        Self: AsyncCommandHandler<Cmd, Ev>;
+
+    async fn query<Q>(&self, query: Q) -> <Self as AsyncQueryHandler<Q, Ev>>::Response
+    where
+        Q: Send + 'static,
+        Self: AsyncQueryHandler<Q, Ev>;
+}
+
+/// Send an [`Idempotent`] request `Req` asynchronously for processing to the mediator,
+/// unless a request with the same key was already handled.
+/// This will call the handler.
+#[async_trait]
+pub trait AsyncMediatorInternalIdempotency<Ev: Debug> {
+    async fn send_idempotent<Req>(&self, req: Req)
+    where
+        Req: Idempotent + Send,
+        Self: AsyncRequestHandler<Req, Ev>;
+
+    async fn try_send_idempotent<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), <Self as TryAsyncRequestHandler<Req, Ev>>::Error>
+    where
+        Req: Idempotent + Send,
+        Self: TryAsyncRequestHandler<Req, Ev>,
+        <Self as TryAsyncRequestHandler<Req, Ev>>::Error: Clone + Sync + 'static;
+}
+
+/// Send an [`Undoable`] request `Req` asynchronously for processing to the mediator
+/// and undo or redo the recorded requests.
+/// This will call the handler.
+#[async_trait]
+pub trait AsyncMediatorInternalHistory<Ev: Debug> {
+    async fn send_undoable<Req>(&self, req: Req)
+    where
+        Req: Undoable,
+        Self: AsyncRequestHandler<Req, Ev> + AsyncRequestHandler<Req::Inverse, Ev>;
+
+    async fn undo(&self) -> bool;
+
+    async fn redo(&self) -> bool;
+
+    fn begin_group(&self, label: &str);
+
+    fn end_group(&self);
+
+    fn history(&self) -> CommandHistory;
+
+    fn clear_history(&self);
+}
+
+/// Send a request `Req` asynchronously for processing to the mediator
+/// and interrupt the handler on a timeout, deadline or cancellation.
+/// This will call the handler.
+#[async_trait]
+pub trait AsyncMediatorInternalTimeout<Ev: Debug> {
+    async fn send_with_timeout<Req>(&self, req: Req, timeout: Duration) -> Result<(), Interrupted>
+    where
+        Req: Send,
+        Self: AsyncRequestHandler<Req, Ev>;
+
+    async fn send_with_deadline<Req>(&self, req: Req, deadline: Instant) -> Result<(), Interrupted>
+    where
+        Req: Send,
+        Self: AsyncRequestHandler<Req, Ev>;
+
+    async fn send_with_cancellation<Req>(
+        &self,
+        req: Req,
+        token: CancellationToken,
+    ) -> Result<(), Interrupted>
+    where
+        Req: Send,
+        Self: AsyncRequestHandler<Req, Ev>;
+}
+
+/// Send a request `Req` asynchronously for processing to the mediator.
+/// This will call the fallible handler.
+#[async_trait]
+pub trait AsyncMediatorInternalTryHandle<Ev: Debug> {
+    async fn try_send<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), <Self as TryAsyncRequestHandler<Req, Ev>>::Error>
+    where
+        Req: Send,
+        Self: TryAsyncRequestHandler<Req, Ev>;
+}
+
+/// Send a request `Req` asynchronously for processing to the mediator
+/// and retry it according to its [`crate::retry::RetryPolicy`].
+/// This will call the fallible handler.
+#[async_trait]
+pub trait AsyncMediatorInternalRetry<Ev: Debug> {
+    async fn send_with_retry<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), <Self as TryAsyncRequestHandler<Req, Ev>>::Error>
+    where
+        Req: Clone + Send + 'static,
+        Self: TryAsyncRequestHandler<Req, Ev>,
+        <Self as TryAsyncRequestHandler<Req, Ev>>::Error: 'static;
+}
+
+/// Send a request `Req` asynchronously through the circuit breaker
+/// and bulkhead of its request type.
+/// This will call the fallible handler unless the request is rejected.
+#[async_trait]
+pub trait AsyncMediatorInternalGuard<Ev: Debug> {
+    async fn send_guarded<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), GuardError<<Self as TryAsyncRequestHandler<Req, Ev>>::Error>>
+    where
+        Req: Send + 'static,
+        Self: TryAsyncRequestHandler<Req, Ev>;
+
+    fn breaker_state<Req>(&self) -> Option<BreakerState>
+    where
+        Req: 'static;
+}
+
+/// Feed requests `Req` to the mediator through a [`futures::Sink`].
+/// Every request will call the fallible handler.
+pub trait AsyncMediatorInternalSink<Ev: Debug> {
+    fn request_sink<'a, Req>(
+        &'a self,
+        concurrency: usize,
+    ) -> RequestSink<'a, Req, <Self as TryAsyncRequestHandler<Req, Ev>>::Error>
+    where
+        Req: Send + 'a,
+        Self: TryAsyncRequestHandler<Req, Ev>;
+}
+
+/// Inspect, re-dispatch and purge the [`DeadLetter`]s of the mediator asynchronously.
+#[async_trait]
+pub trait AsyncMediatorInternalDeadLetter<Ev: Debug> {
+    async fn dead_letters(&self) -> io::Result<Vec<DeadLetter<Ev>>>;
+
+    async fn redispatch_events(&self) -> io::Result<usize>;
+
+    #[allow(clippy::type_complexity)]
+    async fn redispatch_requests<Req>(
+        &self,
+    ) -> io::Result<Vec<Result<(), <Self as TryAsyncRequestHandler<Req, Ev>>::Error>>>
+    where
+        Req: Record + Clone + Send + 'static,
+        Self: TryAsyncRequestHandler<Req, Ev>,
+        <Self as TryAsyncRequestHandler<Req, Ev>>::Error: 'static;
+
+    async fn purge_dead_letters(&self) -> io::Result<usize>;
+}
+
+/// Process the next event `Ev` from the channel asynchronously, or wait until it arrives.
 /// This will call all listeners with a clone of that event.
 #[async_trait]
 pub trait AsyncMediatorInternalNext {
     async fn next(&self) -> Result<(), TryRecvError>;
+
+    async fn recv(&self) -> Result<(), RecvError>;
+}
+
+/// Schedule an event `Ev` asynchronously to be published later or periodically.
+/// Due events are published on the next call of `next()`.
+#[async_trait]
+pub trait AsyncMediatorInternalSchedule<Ev: Debug> {
+    async fn publish_after(&self, event: Ev, delay: Duration) -> ScheduledId;
+
+    async fn publish_at(&self, event: Ev, at: Instant) -> ScheduledId;
+
+    async fn publish_every<F>(&self, f: F, interval: Duration) -> ScheduledId
+    where
+        F: FnMut() -> Ev + Send + 'static;
+
+    async fn cancel_scheduled(&self, id: ScheduledId) -> bool;
+
+    async fn next_scheduled(&self) -> Result<(), TryRecvError>;
+}
+
+/// Flush the events buffered by batch and debounced listeners asynchronously
+/// and wait for parallel listeners to process their events.
+#[async_trait]
+pub trait AsyncMediatorInternalFlush {
+    async fn flush(&self);
+}
+
+/// Publish a [`Notification`] `N` asynchronously.
+/// This will call all notification handlers registered for `N`.
+#[async_trait]
+pub trait AsyncMediatorInternalNotify {
+    async fn publish_notification<N>(&self, notification: N) -> Result<(), Vec<N::Error>>
+    where
+        N: Notification;
+
+    async fn publish_notification_with<N>(
+        &self,
+        notification: N,
+        strategy: NotificationStrategy,
+    ) -> Result<(), Vec<N::Error>>
+    where
+        N: Notification;
+}
+
+/// Subscribe to the events `Ev` as a [`futures::Stream`].
+/// The stream yields every event dispatched by `next()`.
+#[async_trait]
+pub trait AsyncMediatorInternalSubscribe<Ev> {
+    async fn subscribe_stream(&self) -> EventStream<Ev>;
+
+    async fn subscribe_stream_bounded(
+        &self,
+        capacity: usize,
+        overflow: Overflow,
+    ) -> EventStream<Ev>;
 }
 
 /// Handles the request `Req` asynchronously.
@@ -33,3 +295,96 @@ where
 {
     async fn handle(&self, req: Req);
 }
+
+/// Handles the request `Req` asynchronously and may fail.
+/// Implemented by the user.
+#[async_trait]
+pub trait TryAsyncRequestHandler<Req, Res>
+where
+    Self: Sync,
+{
+    type Error: Send;
+    async fn try_handle(&self, req: Req) -> Result<(), Self::Error>;
+}
+
+/// Handles the command `Cmd` asynchronously, which may publish events `Ev`.
+/// Implemented by the user.
+/// Unlike an [`AsyncRequestHandler`], it is only called by [`AsyncMediatorInternalCqrs::execute()`],
+/// so the write side of the mediator is kept apart from plain requests.
+#[async_trait]
+pub trait AsyncCommandHandler<Cmd, Ev>
+where
+    Self: Sync,
+{
+    async fn handle(&self, cmd: Cmd);
+}
+
+/// Answers the query `Q` asynchronously with a `Response`.
+/// Implemented by the user.
+/// A query only reads, so the handler is not given the mediator but a
+/// [`QueryContext`], which can only answer other queries.
+#[async_trait]
+pub trait AsyncQueryHandler<Q, Ev>
+where
+    Self: Sized + Sync,
+{
+    type Response: Send + 'static;
+    async fn handle(query: Q, cx: &QueryContext<'_, Self>) -> Self::Response;
+}
+
+/// Notification builder fuctionality:
+/// Adding a notification handler for a [`Notification`] `N`
+/// and choosing the [`NotificationStrategy`].
+pub trait AsyncNotificationBuilderInterface<M, Ev> {
+    fn add_notification_handler<N, F, Fut>(self, f: F) -> Self
+    where
+        N: Notification,
+        F: Fn(N) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), N::Error>> + Send + 'static;
+
+    fn notification_strategy(self, strategy: NotificationStrategy) -> Self;
+}
+
+/// Guard builder fuctionality:
+/// Adding a [`CircuitBreaker`] or a bulkhead for a request type `Req`
+/// and mapping the [`BreakerTransition`]s to events `Ev`.
+pub trait AsyncGuardBuilderInterface<M, Ev> {
+    fn add_circuit_breaker<Req>(self, breaker: CircuitBreaker) -> Self
+    where
+        Req: 'static;
+
+    fn add_bulkhead<Req>(self, max_in_flight: usize) -> Self
+    where
+        Req: 'static;
+
+    fn on_breaker_transition<F>(self, f: F) -> Self
+    where
+        F: Fn(BreakerTransition) -> Ev + Send + Sync + 'static;
+}
+
+/// Query cache builder fuctionality:
+/// Caching the responses to queries of type `Q` with a [`QueryCache`].
+pub trait AsyncQueryCacheBuilderInterface<M, Ev> {
+    fn cache_queries<Q>(self, cache: QueryCache<Ev>) -> Self
+    where
+        M: AsyncQueryHandler<Q, Ev>,
+        Q: Hash + Eq + Clone + Send + 'static,
+        <M as AsyncQueryHandler<Q, Ev>>::Response: Clone;
+}
+
+/// Saga builder fuctionality:
+/// Adding a [`Saga`], whose follow-up requests are handled by the mediator.
+pub trait AsyncSagaBuilderInterface<M, Ev> {
+    fn add_saga<S>(self, saga: S) -> Self
+    where
+        S: Saga<Ev>,
+        M: TryAsyncRequestHandler<S::Request, Ev>,
+        Ev: Debug + Clone + Send + 'static;
+
+    fn add_saga_with_storage<S, St>(self, saga: S, storage: St) -> Self
+    where
+        S: Saga<Ev>,
+        St: Storage<SagaInstance<S::Id, S::State>> + 'static,
+        M: TryAsyncRequestHandler<S::Request, Ev>,
+        Ev: Debug + Clone + Send + 'static;
+}
@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 pub(crate) mod basic;
 pub(crate) mod builder;
 pub(crate) mod interface;
@@ -7,4 +8,27 @@ pub use builder::*;
 pub use interface::*;
 
 pub use crate::builder::{BuilderFlow, BuilderInternal};
+pub use crate::clock::{Clock, MockClock, SystemClock};
+pub use crate::dead_letter::{DeadLetter, DeadLetterReason, Letter};
+pub use crate::hierarchy::{MediatorLink, Propagation};
+pub use crate::history::{CommandHistory, HistoryStep, Undoable};
+pub use crate::idempotency::{CachedOutcome, DedupStore, Idempotent, MemoryDedupStore};
 pub use crate::listener::*;
+pub use crate::mediator::asynchronous::cancellation::{CancellationToken, Interrupted};
+pub use crate::mediator::asynchronous::guard::{
+    BreakerState, BreakerTransition, CircuitBreaker, GuardError,
+};
+pub use crate::mediator::asynchronous::notification::{Notification, NotificationStrategy};
+pub use crate::mediator::asynchronous::sink::RequestSink;
+pub use crate::mediator::asynchronous::stream::{EventStream, Overflow};
+pub use crate::priority::Priority;
+pub use crate::retry::{Backoff, RetryPolicy};
+pub use crate::saga::{Saga, SagaInstance, Step};
+pub use crate::schedule::ScheduledId;
+pub use crate::storage::{FileStorage, MemoryStorage, Record, Storage};
+pub use crate::synchronous::basic::{
+    BridgeBuilderInterface, DeadLetterBuilderInterface, HierarchyBuilderInterface,
+    HistoryBuilderInterface, ListenerOperatorBuilderInterface, OutboxBuilderInterface,
+    PriorityBuilderInterface, ProjectionBuilderInterface, QueryContext, RetryBuilderInterface,
+    SyncMediatorInternalHierarchy, TopicBuilderInterface,
+};
@@ -0,0 +1,303 @@
+use std::{
+    cell::RefCell,
+    fmt::Display,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
+};
+
+use futures::future::{select, select_all, Either};
+
+/// A token to cancel requests sent via
+/// [`super::basic::AsyncMediatorInternalTimeout::send_with_cancellation()`].
+///
+/// Clones of a [`CancellationToken`] share their state,
+/// so cancelling one clone cancels all of them.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mediator_sys::asynchronous::cancellation::CancellationToken;
+///
+/// let token = CancellationToken::new();
+/// let cloned = token.clone();
+///
+/// cloned.cancel();
+/// assert!(token.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<TokenInner>,
+}
+
+#[derive(Debug, Default)]
+struct TokenInner {
+    cancelled: AtomicBool,
+    wakers: Mutex<Vec<Waker>>,
+}
+
+impl CancellationToken {
+    /// Creates a [`CancellationToken`] that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the token and wakes every task waiting for it.
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        let wakers = std::mem::take(&mut *self.inner.wakers.lock().unwrap());
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Returns `true` if the token was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Returns a `Future` that completes once the token is cancelled.
+    pub fn cancelled(&self) -> WaitForCancellation {
+        WaitForCancellation {
+            token: self.clone(),
+        }
+    }
+}
+
+/// A `Future` that completes once its [`CancellationToken`] is cancelled.
+///
+/// Created by [`CancellationToken::cancelled()`].
+#[derive(Debug)]
+#[must_use = "futures do nothing unless polled"]
+pub struct WaitForCancellation {
+    token: CancellationToken,
+}
+
+impl Future for WaitForCancellation {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.token.is_cancelled() {
+            return Poll::Ready(());
+        }
+
+        {
+            let mut wakers = self.token.inner.wakers.lock().unwrap();
+            if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                wakers.push(cx.waker().clone());
+            }
+        }
+
+        // The token may have been cancelled while registering the waker.
+        if self.token.is_cancelled() {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// The reason a request was interrupted before its handler finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interrupted {
+    /// The deadline of the request passed.
+    TimedOut,
+    /// A [`CancellationToken`] of the request was cancelled.
+    Cancelled,
+}
+
+impl Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Interrupted::TimedOut => write!(f, "request timed out"),
+            Interrupted::Cancelled => write!(f, "request was cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+/// The deadline and cancellation tokens of the request that is currently being handled.
+///
+/// Handlers observe it via [`current()`]. Requests sent from within a handler
+/// inherit the context of the request that triggered them, so deadlines
+/// and cancellation propagate into follow-up requests.
+#[derive(Debug, Clone, Default)]
+pub struct RequestContext {
+    deadline: Option<Instant>,
+    tokens: Vec<CancellationToken>,
+}
+
+impl RequestContext {
+    /// Returns the deadline of the request, if any.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    /// Returns the time left until the deadline of the request, if any.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+    }
+
+    /// Returns `true` if the request was cancelled or its deadline passed.
+    pub fn is_interrupted(&self) -> bool {
+        self.is_cancelled() || self.is_timed_out()
+    }
+
+    /// Returns `true` if one of the [`CancellationToken`]s of the request was cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.tokens.iter().any(CancellationToken::is_cancelled)
+    }
+
+    /// Returns `true` if the deadline of the request passed.
+    pub fn is_timed_out(&self) -> bool {
+        self.deadline
+            .map(|deadline| Instant::now() >= deadline)
+            .unwrap_or(false)
+    }
+
+    pub(crate) fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(match self.deadline {
+            Some(inherited) => inherited.min(deadline),
+            None => deadline,
+        });
+        self
+    }
+
+    pub(crate) fn with_token(mut self, token: CancellationToken) -> Self {
+        self.tokens.push(token);
+        self
+    }
+
+    /// Completes once the request is interrupted, never if it cannot be.
+    async fn interrupted(&self) -> Interrupted {
+        let cancelled = async {
+            if self.tokens.is_empty() {
+                futures::future::pending::<()>().await
+            } else {
+                select_all(self.tokens.iter().map(CancellationToken::cancelled)).await;
+            }
+        };
+        let timed_out = async {
+            match self.remaining() {
+                Some(remaining) => async_std::task::sleep(remaining).await,
+                None => futures::future::pending::<()>().await,
+            }
+        };
+
+        futures::pin_mut!(cancelled, timed_out);
+        match select(cancelled, timed_out).await {
+            Either::Left(_) => Interrupted::Cancelled,
+            Either::Right(_) => Interrupted::TimedOut,
+        }
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<RequestContext>> = const { RefCell::new(None) };
+}
+
+/// Returns the [`RequestContext`] of the request that is currently being handled.
+///
+/// Outside of a request sent with a timeout, deadline or cancellation token,
+/// an empty context is returned, which is never interrupted.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mediator_sys::asynchronous::basic::*;
+/// use mediator_sys::asynchronous::cancellation;
+/// use async_trait::async_trait;
+/// use std::time::Duration;
+///
+/// #[derive(Debug, Clone)]
+/// enum MyEvent {
+///     Progress(u32),
+/// }
+///
+/// struct Request;
+///
+/// #[async_trait]
+/// impl AsyncRequestHandler<Request, MyEvent> for BasicAsyncMediator<MyEvent> {
+///     async fn handle(&self, _req: Request) {
+///         for step in 0..10 {
+///             if cancellation::current().is_interrupted() {
+///                 return;
+///             }
+///             self.publish(MyEvent::Progress(step)).await;
+///         }
+///     }
+/// }
+/// ```
+pub fn current() -> RequestContext {
+    CURRENT.with(|current| current.borrow().clone().unwrap_or_default())
+}
+
+/// Runs `future` within the [`RequestContext`] `cx`
+/// and interrupts it once `cx` is cancelled or timed out.
+///
+/// If `future` completes first, its output is returned, even if `cx` was interrupted
+/// in the meantime, e.g. because its handler observed the interruption and returned early.
+/// The handler already took effect, so reporting an interruption would invite a retry.
+pub(crate) async fn guard<F>(cx: RequestContext, future: F) -> Result<F::Output, Interrupted>
+where
+    F: Future,
+{
+    if cx.is_cancelled() {
+        return Err(Interrupted::Cancelled);
+    }
+    if cx.is_timed_out() {
+        return Err(Interrupted::TimedOut);
+    }
+
+    let scoped = Scoped {
+        cx: cx.clone(),
+        future: Box::pin(future),
+    };
+    let interrupted = cx.interrupted();
+
+    futures::pin_mut!(interrupted);
+    match select(scoped, interrupted).await {
+        Either::Left((output, _)) => Ok(output),
+        Either::Right((reason, _)) => Err(reason),
+    }
+}
+
+/// Sets the [`RequestContext`] as current while polling the inner future.
+struct Scoped<F> {
+    cx: RequestContext,
+    future: Pin<Box<F>>,
+}
+
+impl<F> Future for Scoped<F>
+where
+    F: Future,
+{
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let previous = CURRENT.with(|current| current.replace(Some(self.cx.clone())));
+        let _restore = Restore(previous);
+        self.future.as_mut().poll(cx)
+    }
+}
+
+/// Restores the previous [`RequestContext`], even if polling panicked.
+struct Restore(Option<RequestContext>);
+
+impl Drop for Restore {
+    fn drop(&mut self) {
+        let previous = self.0.take();
+        CURRENT.with(|current| *current.borrow_mut() = previous);
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    fmt::Debug,
+};
+
+/// A type-map based service container.
+///
+/// A [`Container`] stores at most one service per type.
+/// It is meant to be used as the dependency `Dep` of a
+/// [`super::CxAwareAsyncMediator`], so that handlers can resolve
+/// multiple services by their type instead of relying on
+/// a hand-written aggregate context struct.
+///
+/// Services are usually registered through
+/// [`super::CxAwareAsyncBuilder::add_service()`].
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mediator_sys::asynchronous::contextaware::*;
+///
+/// #[derive(Debug)]
+/// struct Db(String);
+///
+/// #[derive(Debug)]
+/// struct Clock(u64);
+///
+/// let mut container = Container::new();
+/// container.insert(Db(String::from("postgres://localhost")));
+/// container.insert(Clock(42));
+///
+/// assert_eq!(container.get::<Clock>().unwrap().0, 42);
+/// assert!(container.contains::<Db>());
+/// assert!(!container.contains::<u32>());
+/// ```
+#[derive(Default)]
+pub struct Container {
+    services: HashMap<TypeId, Service>,
+}
+
+struct Service {
+    name: &'static str,
+    value: Box<dyn Any + Send + Sync>,
+}
+
+impl Container {
+    /// Creates an empty [`Container`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a service of type `T`.
+    ///
+    /// If a service of the same type was already present,
+    /// it is replaced and returned.
+    pub fn insert<T>(&mut self, service: T) -> Option<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.services
+            .insert(
+                TypeId::of::<T>(),
+                Service {
+                    name: type_name::<T>(),
+                    value: Box::new(service),
+                },
+            )
+            .and_then(|old| old.value.downcast::<T>().ok())
+            .map(|old| *old)
+    }
+
+    /// Returns a reference to the service of type `T`, if present.
+    pub fn get<T>(&self) -> Option<&T>
+    where
+        T: 'static,
+    {
+        self.services
+            .get(&TypeId::of::<T>())
+            .and_then(|service| service.value.downcast_ref::<T>())
+    }
+
+    /// Returns a mutable reference to the service of type `T`, if present.
+    pub fn get_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: 'static,
+    {
+        self.services
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|service| service.value.downcast_mut::<T>())
+    }
+
+    /// Removes the service of type `T` and returns it, if present.
+    pub fn remove<T>(&mut self) -> Option<T>
+    where
+        T: 'static,
+    {
+        self.services
+            .remove(&TypeId::of::<T>())
+            .and_then(|service| service.value.downcast::<T>().ok())
+            .map(|service| *service)
+    }
+
+    /// Returns `true` if a service of type `T` is present.
+    pub fn contains<T>(&self) -> bool
+    where
+        T: 'static,
+    {
+        self.services.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Returns the number of registered services.
+    pub fn len(&self) -> usize {
+        self.services.len()
+    }
+
+    /// Returns `true` if no services are registered.
+    pub fn is_empty(&self) -> bool {
+        self.services.is_empty()
+    }
+}
+
+impl Debug for Container {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set()
+            .entries(self.services.values().map(|service| service.name))
+            .finish()
+    }
+}
@@ -1,17 +1,51 @@
-use std::sync::mpsc::TryRecvError;
+use std::sync::mpsc::{RecvError, TryRecvError};
 
-use async_std::sync::Mutex;
+use async_std::sync::RwLock;
 use async_trait::async_trait;
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    io,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use crate::asynchronous::basic::BasicAsyncMediator;
+use crate::asynchronous::basic::{
+    AsyncMediatorInternalDeadLetter, AsyncMediatorInternalFlush, AsyncMediatorInternalOutbox,
+    AsyncMediatorInternalPriority, AsyncMediatorInternalSchedule, AsyncMediatorInternalTopic,
+    BasicAsyncMediator,
+};
+use crate::mediator::asynchronous::{
+    cancellation,
+    guard::{BreakerState, GuardError},
+    notification::NotificationHandlers,
+    sink::RequestSink,
+};
+use crate::mediator::{
+    cache::Lookup,
+    dead_letter::DeadLetter,
+    hierarchy::MediatorLink,
+    history::{AsyncAction, CommandHistory, History, Replay, Undoable},
+    idempotency::{wait, Idempotent, Reservation},
+    priority::Priority,
+    retry,
+    saga::AsyncSagas,
+    schedule::ScheduledId,
+    storage::Record,
+};
 
-use super::*;
+use super::{
+    scope::{Scope, ScopedFactory},
+    *,
+};
 
 /// Context aware async mediator for asynchronous environments with events of type `Ev`.
 ///
 /// Uses an underlying [`BasicAsyncMediator`] for base functionality
-/// and a `Mutex` to store the user-defined dependency `Dep`.
+/// and a `RwLock` to store the user-defined dependency `Dep`.
+///
+/// The dependency can be replaced at runtime via
+/// [`CxAwareAsyncMediatorInternalDependency::replace_dependency()`]
+/// without losing listeners or queued events.
 ///
 /// # Examples
 ///
@@ -70,13 +104,17 @@ where
     Ev: Debug,
 {
     pub(crate) basic: BasicAsyncMediator<Ev>,
-    pub(crate) dep: Mutex<Dep>,
+    pub(crate) dep: RwLock<Arc<Dep>>,
+    pub(crate) scoped: Vec<ScopedFactory<Dep>>,
+    pub(crate) notifications: NotificationHandlers<Arc<Dep>>,
+    pub(crate) sagas: AsyncSagas<CxAwareAsyncMediator<Dep, Ev>>,
+    pub(crate) history: std::sync::Mutex<History<AsyncAction<CxAwareAsyncMediator<Dep, Ev>>>>,
 }
 
 #[async_trait]
 impl<Dep, Ev> AsyncMediatorInternal<Ev> for CxAwareAsyncMediator<Dep, Ev>
 where
-    Dep: Debug + Send,
+    Dep: Debug + Send + Sync,
     Ev: Debug + Send,
 {
     /// Publishes an event `Ev` asynchronously.
@@ -125,6 +163,74 @@ where
     }
 }
 
+#[async_trait]
+impl<Dep, Ev> AsyncMediatorInternalPriority<Ev> for CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug + Send + Sync,
+    Ev: Debug + Send,
+{
+    /// Publishes an event `Ev` with a [`Priority`] asynchronously.
+    ///
+    /// See [`BasicAsyncMediator::publish_with_priority()`] for more info.
+    ///
+    async fn publish_with_priority(&self, event: Ev, priority: Priority) {
+        self.basic.publish_with_priority(event, priority).await
+    }
+}
+
+#[async_trait]
+impl<Dep, Ev> AsyncMediatorInternalTopic<Ev> for CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug + Send + Sync,
+    Ev: Debug + Send,
+{
+    /// Publishes an event `Ev` to a `topic` asynchronously.
+    ///
+    /// See [`BasicAsyncMediator::publish_to()`] for more info.
+    ///
+    async fn publish_to(&self, topic: &str, event: Ev) {
+        self.basic.publish_to(topic, event).await
+    }
+}
+
+#[async_trait]
+impl<Dep, Ev> AsyncMediatorInternalOutbox<Ev> for CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug + Send + Sync,
+    Ev: Debug + Send,
+{
+    /// Publishes an event `Ev` asynchronously right away,
+    /// even from within a transactional handler.
+    ///
+    /// See [`BasicAsyncMediator::publish_immediately()`] for more info.
+    ///
+    async fn publish_immediately(&self, event: Ev) {
+        self.basic.publish_immediately(event).await
+    }
+}
+
+impl<Dep, Ev> SyncMediatorInternalHierarchy<Ev> for CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug,
+    Ev: Debug,
+{
+    /// Returns a [`MediatorLink`] to link child mediators to the [`CxAwareAsyncMediator`].
+    ///
+    /// See [`BasicAsyncMediator::link()`] for more info.
+    ///
+    fn link(&self) -> MediatorLink<Ev> {
+        self.basic.link()
+    }
+
+    /// Returns the number of child mediators linked to the [`CxAwareAsyncMediator`].
+    ///
+    /// Children are unlinked when they are dropped.
+    ///
+    fn child_count(&self) -> usize {
+        self.basic.child_count()
+    }
+}
+
 #[async_trait]
 impl<Dep, Ev> CxAwareAsyncMediatorInternalHandle<Dep, Ev> for CxAwareAsyncMediator<Dep, Ev>
 where
@@ -135,7 +241,14 @@ where
     ///
     /// The request will be processed internally by [`CxAwareAsyncRequestHandler::handle()`].
     /// This is why it is required to implement [`CxAwareAsyncRequestHandler`] for [`CxAwareAsyncMediator`].
-    /// A `Mutex` will be locked in order to gain access to the context `Dep`.
+    /// A `RwLock` will be read-locked briefly in order to take a snapshot of the context `Dep`.
+    /// The request is handled with this snapshot, even if the dependency is
+    /// replaced while the handler is running.
+    ///
+    /// The scoped dependencies added via [`super::CxAwareAsyncBuilder::add_scoped()`]
+    /// are created for the request and passed to [`CxAwareAsyncRequestHandler::handle_scoped()`].
+    /// They are torn down with [`Outcome::Success`] once the handler finished,
+    /// or with [`Outcome::Failure`] if it panicked or the `Future` was dropped.
     ///
     /// You need to await the `Future` using `.await`.
     ///
@@ -144,15 +257,901 @@ where
         Self: CxAwareAsyncRequestHandler<Dep, Req, Ev>,
         Req: Send,
     {
-        let m = self.dep.lock().await;
-        <Self as CxAwareAsyncRequestHandler<Dep, Req, Ev>>::handle(self, req, &m).await
+        let m = self.dependency().await;
+        let mut scope = Scope::open(&self.scoped, &m);
+        <Self as CxAwareAsyncRequestHandler<Dep, Req, Ev>>::handle_scoped(
+            self,
+            req,
+            &m,
+            &mut scope.container,
+        )
+        .await;
+        scope.close(Outcome::Success);
+    }
+}
+
+#[async_trait]
+impl<Dep, Ev> CxAwareAsyncMediatorInternalCqrs<Dep, Ev> for CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug + Send + Sync,
+    Ev: Debug + Send,
+{
+    /// Execute a command of type `Cmd` with the mediator asynchronously.
+    ///
+    /// The command will be processed internally by [`CxAwareAsyncCommandHandler::handle()`].
+    /// This is why it is required to implement [`CxAwareAsyncCommandHandler`] for [`CxAwareAsyncMediator`].
+    /// Like [`CxAwareAsyncMediatorInternalHandle::send()`], the command is handled with a snapshot
+    /// of the context `Dep` and its scoped dependencies are passed to
+    /// [`CxAwareAsyncCommandHandler::handle_scoped()`].
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn execute<Cmd>(&self, cmd: Cmd)
+    where
+        Self: CxAwareAsyncCommandHandler<Dep, Cmd, Ev>,
+        Cmd: Send,
+    {
+        let m = self.dependency().await;
+        let mut scope = Scope::open(&self.scoped, &m);
+        <Self as CxAwareAsyncCommandHandler<Dep, Cmd, Ev>>::handle_scoped(
+            self,
+            cmd,
+            &m,
+            &mut scope.container,
+        )
+        .await;
+        scope.close(Outcome::Success);
+    }
+
+    /// Send a query of type `Q` to the mediator asynchronously and return its response.
+    ///
+    /// The query will be answered by [`CxAwareAsyncQueryHandler::handle()`].
+    /// This is why it is required to implement [`CxAwareAsyncQueryHandler`] for [`CxAwareAsyncMediator`].
+    /// The query handler is given a [`CxAwareQueryContext`] instead of the mediator,
+    /// so it can read the context `Dep` and answer other queries, but cannot publish events.
+    /// A `RwLock` is read-locked for the whole query, so the dependency
+    /// is not replaced while the query reads it.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::contextaware::*;
+    /// use std::sync::Mutex;
+    /// use async_trait::async_trait;
+    /// use async_std;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Added(String),
+    /// }
+    ///
+    /// #[derive(Debug, Default)]
+    /// struct Inventory(Mutex<Vec<String>>);
+    ///
+    /// struct Add(String);
+    ///
+    /// struct Count;
+    ///
+    /// struct Empty;
+    ///
+    /// #[async_trait]
+    /// impl CxAwareAsyncCommandHandler<Inventory, Add, MyEvent> for CxAwareAsyncMediator<Inventory, MyEvent> {
+    ///     async fn handle(&self, cmd: Add, dep: &Inventory) {
+    ///         dep.0.lock().unwrap().push(cmd.0.clone());
+    ///         self.publish(MyEvent::Added(cmd.0)).await;
+    ///     }
+    /// }
+    ///
+    /// #[async_trait]
+    /// impl CxAwareAsyncQueryHandler<Inventory, Count, MyEvent> for CxAwareAsyncMediator<Inventory, MyEvent> {
+    ///     type Response = usize;
+    ///
+    ///     async fn handle(_: Count, cx: &CxAwareQueryContext<'_, Inventory, MyEvent>) -> usize {
+    ///         cx.dep().0.lock().unwrap().len()
+    ///     }
+    /// }
+    ///
+    /// #[async_trait]
+    /// impl CxAwareAsyncQueryHandler<Inventory, Empty, MyEvent> for CxAwareAsyncMediator<Inventory, MyEvent> {
+    ///     type Response = bool;
+    ///
+    ///     async fn handle(_: Empty, cx: &CxAwareQueryContext<'_, Inventory, MyEvent>) -> bool {
+    ///         cx.query(Count).await == 0
+    ///     }
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = CxAwareAsyncMediator::<Inventory, MyEvent>::builder()
+    ///         .add_dependency(Inventory::default())
+    ///         .build()
+    ///         .unwrap();
+    ///
+    ///     assert!(mediator.query(Empty).await);
+    ///
+    ///     mediator.execute(Add(String::from("apple"))).await;
+    ///     mediator.execute(Add(String::from("pear"))).await;
+    ///
+    ///     assert_eq!(mediator.query(Count).await, 2);
+    ///     assert!(!mediator.query(Empty).await);
+    /// })
+    ///
+    async fn query<Q>(&self, query: Q) -> <Self as CxAwareAsyncQueryHandler<Dep, Q, Ev>>::Response
+    where
+        Self: CxAwareAsyncQueryHandler<Dep, Q, Ev>,
+        Q: Send + 'static,
+    {
+        let dep = self.dep.read().await;
+        self.answer(query, &dep).await
+    }
+}
+
+impl<Dep, Ev> CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug + Send + Sync,
+    Ev: Debug + Send,
+{
+    /// Answers the query `Q` with the dependency `dep`, which is read-locked by the caller,
+    /// or from the cache of the query type.
+    pub(crate) async fn answer<Q>(
+        &self,
+        query: Q,
+        dep: &Dep,
+    ) -> <Self as CxAwareAsyncQueryHandler<Dep, Q, Ev>>::Response
+    where
+        Self: CxAwareAsyncQueryHandler<Dep, Q, Ev>,
+        Q: Send + 'static,
+    {
+        let cx = CxAwareQueryContext {
+            mediator: self,
+            dep,
+        };
+        let lookup = {
+            let m = self.basic.basic.lock().await;
+            m.caches.lookup(&query, m.clock.now())
+        };
+        match lookup {
+            Lookup::Hit(response) => response,
+            Lookup::Miss(miss) => {
+                let response =
+                    <Self as CxAwareAsyncQueryHandler<Dep, Q, Ev>>::handle(query, &cx).await;
+                let m = self.basic.basic.lock().await;
+                m.caches.store(miss, &response, m.clock.now());
+                response
+            }
+            Lookup::Uncached => {
+                <Self as CxAwareAsyncQueryHandler<Dep, Q, Ev>>::handle(query, &cx).await
+            }
+        }
+    }
+}
+
+impl<'a, Dep, Ev> CxAwareQueryContext<'a, Dep, Ev>
+where
+    Dep: Debug + Send + Sync,
+    Ev: Debug + Send,
+{
+    /// Read-only access to the dependency `Dep`.
+    ///
+    pub fn dep(&self) -> &Dep {
+        self.dep
+    }
+
+    /// Answer another query of type `Q` asynchronously while answering a query.
+    ///
+    /// The query reads the same dependency `Dep` under the read lock
+    /// that is already held, instead of locking it again.
+    /// See [`CxAwareAsyncMediatorInternalCqrs::query()`].
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    pub async fn query<Q>(
+        &self,
+        query: Q,
+    ) -> <CxAwareAsyncMediator<Dep, Ev> as CxAwareAsyncQueryHandler<Dep, Q, Ev>>::Response
+    where
+        CxAwareAsyncMediator<Dep, Ev>: CxAwareAsyncQueryHandler<Dep, Q, Ev>,
+        Q: Send + 'static,
+    {
+        self.mediator.answer(query, self.dep).await
+    }
+}
+
+#[async_trait]
+impl<Dep, Ev> CxAwareAsyncMediatorInternalIdempotency<Dep, Ev> for CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug + Send + Sync,
+    Ev: Debug + Send,
+{
+    /// Send an [`Idempotent`] request of type `Req` to the mediator asynchronously,
+    /// unless a request of the same type with the same key was already handled.
+    ///
+    /// The request will be processed internally by [`CxAwareAsyncRequestHandler::handle()`].
+    /// Requests are only deduplicated if enabled via
+    /// [`super::CxAwareAsyncBuilder::deduplicate_requests()`].
+    /// Duplicates sent while the first request is still being handled wait for it.
+    /// A duplicate sent by the handler of the request itself cannot wait for it
+    /// and returns right away without being handled.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn send_idempotent<Req>(&self, req: Req)
+    where
+        Req: Idempotent + Send,
+        Self: CxAwareAsyncRequestHandler<Dep, Req, Ev>,
+    {
+        let key = self.basic.basic.lock().await.dedup.key(&req);
+        let key = match key {
+            Some(key) => key,
+            None => return self.send(req).await,
+        };
+        loop {
+            match self.basic.in_flight.reserve(&key) {
+                Reservation::Waiting(outcome) => {
+                    if wait::<()>(outcome).await.is_some() {
+                        return;
+                    }
+                }
+                Reservation::Reentered => return,
+                Reservation::Claimed(claim) => {
+                    let cached = self.basic.basic.lock().await.dedup.cached::<()>(&key);
+                    if cached.is_none() {
+                        claim.hold(self.send(req)).await;
+                        self.basic.basic.lock().await.dedup.record(key, ());
+                    }
+                    return claim.complete(());
+                }
+            }
+        }
+    }
+
+    /// Send an [`Idempotent`] request of type `Req` to the mediator asynchronously,
+    /// unless a request of the same type with the same key was already handled.
+    ///
+    /// The request will be processed internally by [`TryCxAwareAsyncRequestHandler::try_handle()`].
+    /// A successful result is cached and returned for duplicates,
+    /// whereas a failed request is handled again when it is sent again with the same key.
+    /// Duplicates sent while the first request is still being handled
+    /// wait for it and return its result, whether it succeeded or not.
+    /// A duplicate sent by the handler of the request itself cannot wait for it
+    /// and returns `Ok(())` right away without being handled,
+    /// leaving the result to the request that is being handled.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn try_send_idempotent<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), <Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error>
+    where
+        Req: Idempotent + Send,
+        Self: TryCxAwareAsyncRequestHandler<Dep, Req, Ev>,
+        <Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error: Clone + Sync + 'static,
+    {
+        let key = self.basic.basic.lock().await.dedup.key(&req);
+        let key = match key {
+            Some(key) => key,
+            None => return self.try_send(req).await,
+        };
+        loop {
+            match self.basic.in_flight.reserve(&key) {
+                Reservation::Waiting(outcome) => {
+                    if let Some(outcome) = wait(outcome).await {
+                        return outcome;
+                    }
+                }
+                Reservation::Reentered => return Ok(()),
+                Reservation::Claimed(claim) => {
+                    let cached = self.basic.basic.lock().await.dedup.cached(&key);
+                    let outcome = match cached {
+                        Some(outcome) => outcome,
+                        None => {
+                            let outcome = claim.hold(self.try_send(req)).await;
+                            if outcome.is_ok() {
+                                self.basic
+                                    .basic
+                                    .lock()
+                                    .await
+                                    .dedup
+                                    .record(key, outcome.clone());
+                            }
+                            outcome
+                        }
+                    };
+                    claim.complete(outcome.clone());
+                    return outcome;
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl<Dep, Ev> CxAwareAsyncMediatorInternalHistory<Dep, Ev> for CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug + Send + Sync + 'static,
+    Ev: Debug + Send + 'static,
+{
+    /// Send an [`Undoable`] request of type `Req` to the mediator asynchronously
+    /// and record it in the command history.
+    ///
+    /// The request will be processed internally by [`CxAwareAsyncRequestHandler::handle()`].
+    /// It is only recorded if the history was enabled via
+    /// [`super::CxAwareAsyncBuilder::command_history()`].
+    /// Undoing it sends its [`Undoable::inverse()`], redoing it sends it again.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn send_undoable<Req>(&self, req: Req)
+    where
+        Req: Undoable,
+        Self: CxAwareAsyncRequestHandler<Dep, Req, Ev>
+            + CxAwareAsyncRequestHandler<Dep, Req::Inverse, Ev>,
+    {
+        let (undo, redo) = (req.clone(), req.clone());
+        self.history.lock().unwrap().record(
+            req.label(),
+            Box::new(move |m| {
+                let inverse = undo.inverse();
+                Box::pin(async move { m.send(inverse).await })
+            }),
+            Box::new(move |m| {
+                let req = redo.clone();
+                Box::pin(async move { m.send(req).await })
+            }),
+        );
+        self.send(req).await
+    }
+
+    /// Undoes the last step of the command history asynchronously by sending
+    /// the inverses of its requests in reverse order.
+    ///
+    /// Returns `false` if there is nothing to undo.
+    /// The inverses are sent as plain requests, so they are not recorded.
+    /// If undoing is interrupted, e.g. the `Future` is dropped or a request panics,
+    /// the step stays in the history to be undone again.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn undo(&self) -> bool {
+        match Replay::undo(&self.history) {
+            Some(replay) => {
+                for command in replay.step().commands.iter().rev() {
+                    (command.undo)(self).await;
+                }
+                replay.complete();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Redoes the last undone step of the command history asynchronously
+    /// by sending its requests again.
+    ///
+    /// Returns `false` if there is nothing to redo.
+    /// Sending a new [`Undoable`] request discards the steps to redo.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn redo(&self) -> bool {
+        match Replay::redo(&self.history) {
+            Some(replay) => {
+                for command in &replay.step().commands {
+                    (command.redo)(self).await;
+                }
+                replay.complete();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Groups the [`Undoable`] requests sent until `end_group()`
+    /// into a single step named `label`.
+    ///
+    /// Groups may be nested, the outermost one forms the step.
+    ///
+    fn begin_group(&self, label: &str) {
+        self.history.lock().unwrap().begin_group(label.to_owned());
+    }
+
+    /// Closes the group opened by `begin_group()`.
+    ///
+    fn end_group(&self) {
+        self.history.lock().unwrap().end_group();
+    }
+
+    /// Returns a snapshot of the steps that can be undone and redone.
+    ///
+    fn history(&self) -> CommandHistory {
+        self.history.lock().unwrap().snapshot()
+    }
+
+    /// Discards all steps of the command history.
+    ///
+    fn clear_history(&self) {
+        self.history.lock().unwrap().clear();
+    }
+}
+
+#[async_trait]
+impl<Dep, Ev> CxAwareAsyncMediatorInternalTimeout<Dep, Ev> for CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug + Send + Sync,
+    Ev: Debug + Send,
+{
+    /// Send a request of type `Req` to the mediator asynchronously
+    /// and interrupt the handler once `timeout` elapsed.
+    ///
+    /// The request will be processed internally by [`CxAwareAsyncRequestHandler::handle()`].
+    /// If the handler does not finish in time, it is dropped and
+    /// [`Interrupted::TimedOut`] is returned.
+    ///
+    /// See [`BasicAsyncMediator::send_with_timeout()`] for more info.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn send_with_timeout<Req>(&self, req: Req, timeout: Duration) -> Result<(), Interrupted>
+    where
+        Self: CxAwareAsyncRequestHandler<Dep, Req, Ev>,
+        Req: Send,
+    {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.send_with_deadline(req, deadline).await,
+            None => cancellation::guard(cancellation::current(), self.send(req)).await,
+        }
+    }
+
+    /// Send a request of type `Req` to the mediator asynchronously
+    /// and interrupt the handler once `deadline` passed.
+    ///
+    /// See [`BasicAsyncMediator::send_with_timeout()`] for more info.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn send_with_deadline<Req>(&self, req: Req, deadline: Instant) -> Result<(), Interrupted>
+    where
+        Self: CxAwareAsyncRequestHandler<Dep, Req, Ev>,
+        Req: Send,
+    {
+        let cx = cancellation::current().with_deadline(deadline);
+        cancellation::guard(cx, self.send(req)).await
+    }
+
+    /// Send a request of type `Req` to the mediator asynchronously
+    /// and interrupt the handler once `token` is cancelled.
+    ///
+    /// See [`BasicAsyncMediator::send_with_cancellation()`] for more info.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn send_with_cancellation<Req>(
+        &self,
+        req: Req,
+        token: CancellationToken,
+    ) -> Result<(), Interrupted>
+    where
+        Self: CxAwareAsyncRequestHandler<Dep, Req, Ev>,
+        Req: Send,
+    {
+        let cx = cancellation::current().with_token(token);
+        cancellation::guard(cx, self.send(req)).await
+    }
+}
+
+#[async_trait]
+impl<Dep, Ev> TryCxAwareAsyncMediatorInternalHandle<Dep, Ev> for CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug + Send + Sync,
+    Ev: Debug + Send,
+{
+    /// Send a request of type `Req` to the mediator asynchronously.
+    ///
+    /// The request will be processed internally by [`TryCxAwareAsyncRequestHandler::try_handle()`].
+    /// This is why it is required to implement [`TryCxAwareAsyncRequestHandler`] for [`CxAwareAsyncMediator`].
+    /// A `RwLock` will be read-locked briefly in order to take a snapshot of the context `Dep`.
+    ///
+    /// Before the handler is invoked, every scoped dependency added via
+    /// [`super::CxAwareAsyncBuilder::add_scoped()`] is created.
+    /// After the handler finished, their teardowns are called in reverse order
+    /// with the [`Outcome`] of the handler.
+    /// If the handler panics or the `Future` is dropped before it finished,
+    /// they are torn down with [`Outcome::Failure`].
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::contextaware::*;
+    /// use async_trait::async_trait;
+    /// use std::sync::Mutex;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Stored(u32),
+    /// }
+    ///
+    /// #[derive(Debug, Default)]
+    /// struct MyContext;
+    ///
+    /// struct Transaction(Mutex<Vec<u32>>);
+    ///
+    /// struct Request(u32);
+    ///
+    /// #[async_trait]
+    /// impl TryCxAwareAsyncRequestHandler<MyContext, Request, MyEvent> for CxAwareAsyncMediator<MyContext, MyEvent> {
+    ///     type Error = String;
+    ///
+    ///     async fn try_handle(&self, req: Request, dep: &MyContext, scope: &mut Container) -> Result<(), String> {
+    ///         let tx = scope.get::<Transaction>().unwrap();
+    ///         tx.0.lock().unwrap().push(req.0);
+    ///         self.publish(MyEvent::Stored(req.0)).await;
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = CxAwareAsyncMediator::<MyContext, MyEvent>::builder()
+    ///         .add_dependency(MyContext)
+    ///         .add_scoped(
+    ///             |_| Transaction(Mutex::new(vec![])),
+    ///             |tx, outcome| {
+    ///                 if outcome.is_success() {
+    ///                     /* Commit the transaction */
+    ///                 }
+    ///             },
+    ///         )
+    ///         .build()
+    ///         .unwrap();
+    ///
+    ///     mediator.try_send(Request(1)).await.unwrap();
+    /// });
+    ///
+    async fn try_send<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), <Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error>
+    where
+        Self: TryCxAwareAsyncRequestHandler<Dep, Req, Ev>,
+        Req: Send,
+    {
+        let m = self.dependency().await;
+        let mut scope = Scope::open(&self.scoped, &m);
+
+        let result = self
+            .basic
+            .transact(
+                <Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::try_handle(
+                    self,
+                    req,
+                    &m,
+                    &mut scope.container,
+                ),
+            )
+            .await;
+
+        scope.close(Outcome::of(&result));
+        result
+    }
+}
+
+#[async_trait]
+impl<Dep, Ev> CxAwareAsyncMediatorInternalRetry<Dep, Ev> for CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug + Send + Sync,
+    Ev: Debug + Send,
+{
+    /// Send a request of type `Req` to the mediator asynchronously
+    /// and retry it if the handler fails.
+    ///
+    /// Every attempt is processed by [`CxAwareAsyncMediator::try_send()`],
+    /// so scoped dependencies are created anew and torn down with the
+    /// [`Outcome`] of each attempt.
+    /// If a [`crate::retry::RetryPolicy`] was added for `Req` via
+    /// [`super::CxAwareAsyncBuilder::add_retry_policy()`], failed attempts are retried
+    /// according to it. Otherwise, the request is sent exactly once.
+    ///
+    /// See [`BasicAsyncMediator::send_with_retry()`] for more info.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn send_with_retry<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), <Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error>
+    where
+        Req: Clone + Send + 'static,
+        Self: TryCxAwareAsyncRequestHandler<Dep, Req, Ev>,
+        <Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error: 'static,
+    {
+        let (policy, clock, dead) = {
+            let m = self.basic.basic.lock().await;
+            (
+                m.retry
+                    .get::<Req, <Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error>()
+                    .cloned(),
+                m.clock.clone(),
+                m.dead_letters.is_enabled().then(|| req.clone()),
+            )
+        };
+
+        let result = match policy {
+            Some(policy) => {
+                retry::retry_async(&policy, &*clock, req, |req| self.try_send(req)).await
+            }
+            None => self.try_send(req).await,
+        };
+
+        match (dead, result) {
+            (Some(req), Err(err)) => {
+                self.basic
+                    .basic
+                    .lock()
+                    .await
+                    .dead_letters
+                    .request(&req, &err);
+                Err(err)
+            }
+            (_, result) => result,
+        }
+    }
+}
+
+#[async_trait]
+impl<Dep, Ev> CxAwareAsyncMediatorInternalGuard<Dep, Ev> for CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug + Send + Sync,
+    Ev: Debug + Send,
+{
+    /// Send a request of type `Req` to the mediator asynchronously
+    /// through the circuit breaker and bulkhead of its request type.
+    ///
+    /// Requests that are let through are processed by [`CxAwareAsyncMediator::try_send()`].
+    /// Rejected requests never reach the handler, so no scoped dependencies are created.
+    ///
+    /// See [`BasicAsyncMediator::send_guarded()`] for more info.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn send_guarded<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), GuardError<<Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error>>
+    where
+        Req: Send + 'static,
+        Self: TryCxAwareAsyncRequestHandler<Dep, Req, Ev>,
+    {
+        let clock = self.basic.basic.lock().await.clock.clone();
+        let (result, events) = self
+            .basic
+            .guards
+            .run::<Req, _, _>(&*clock, self.try_send(req))
+            .await;
+        for event in events {
+            self.publish(event).await;
+        }
+        result
+    }
+
+    /// Returns the [`BreakerState`] of the circuit breaker for `Req`, if any.
+    ///
+    fn breaker_state<Req>(&self) -> Option<BreakerState>
+    where
+        Req: 'static,
+    {
+        self.basic.guards.state::<Req>()
+    }
+}
+
+impl<Dep, Ev> CxAwareAsyncMediatorInternalSink<Dep, Ev> for CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug + Send + Sync,
+    Ev: Debug + Send,
+{
+    /// Creates a [`RequestSink`] that sends every request of type `Req` to the mediator.
+    ///
+    /// The requests are processed by [`CxAwareAsyncMediator::try_send()`].
+    /// At most `concurrency` handlers run at the same time; a `concurrency` of `0` is treated as `1`.
+    /// The first error of a failed handler is returned by the sink.
+    ///
+    /// See [`BasicAsyncMediator::request_sink()`] for more info.
+    ///
+    fn request_sink<'a, Req>(
+        &'a self,
+        concurrency: usize,
+    ) -> RequestSink<'a, Req, <Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error>
+    where
+        Req: Send + 'a,
+        Self: TryCxAwareAsyncRequestHandler<Dep, Req, Ev>,
+    {
+        RequestSink::new(concurrency, move |req| self.try_send(req))
+    }
+}
+
+#[async_trait]
+impl<Dep, Ev> CxAwareAsyncMediatorInternalDependency<Dep> for CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug + Send + Sync,
+    Ev: Debug + Send,
+{
+    /// Returns a snapshot of the current dependency `Dep`.
+    ///
+    /// The snapshot stays valid even if the dependency is replaced afterwards.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn dependency(&self) -> Arc<Dep> {
+        self.dep.read().await.clone()
+    }
+
+    /// Replaces the dependency `Dep` and returns the previous one.
+    ///
+    /// Requests that are already being handled finish with the previous dependency,
+    /// requests sent afterwards see the new one.
+    /// Listeners and queued events are not affected, cached query responses are dropped.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::contextaware::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     One,
+    ///     Two
+    /// }
+    ///
+    /// #[derive(Debug)]
+    /// struct Credentials(String);
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = CxAwareAsyncMediator::<Credentials, MyEvent>::builder()
+    ///         .add_dependency(Credentials(String::from("old-secret")))
+    ///         .build()
+    ///         .unwrap();
+    ///
+    ///     let old = mediator.replace_dependency(Credentials(String::from("new-secret"))).await;
+    ///     assert_eq!(old.0, "old-secret");
+    ///     assert_eq!(mediator.dependency().await.0, "new-secret");
+    /// });
+    ///
+    async fn replace_dependency(&self, dep: Dep) -> Arc<Dep> {
+        let mut m = self.dep.write().await;
+        self.basic.basic.lock().await.caches.clear();
+        std::mem::replace(&mut *m, Arc::new(dep))
+    }
+
+    /// Updates the dependency `Dep` by deriving a new value from the current one.
+    ///
+    /// The closure receives the current dependency and returns its replacement.
+    /// Concurrent updates are serialized, so no update is lost.
+    /// Like [`CxAwareAsyncMediatorInternalDependency::replace_dependency()`],
+    /// requests that are already being handled finish with the previous dependency.
+    /// Cached query responses are dropped.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::contextaware::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     One,
+    ///     Two
+    /// }
+    ///
+    /// #[derive(Debug, Clone)]
+    /// struct Config {
+    ///     verbose: bool,
+    ///     retries: u32,
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = CxAwareAsyncMediator::<Config, MyEvent>::builder()
+    ///         .add_dependency(Config { verbose: false, retries: 3 })
+    ///         .build()
+    ///         .unwrap();
+    ///
+    ///     mediator.update_dependency(|config| Config { verbose: true, ..config.clone() }).await;
+    ///     assert!(mediator.dependency().await.verbose);
+    /// });
+    ///
+    async fn update_dependency<F>(&self, f: F) -> Arc<Dep>
+    where
+        F: FnOnce(&Dep) -> Dep + Send,
+    {
+        let mut m = self.dep.write().await;
+        let updated = Arc::new(f(&m));
+        self.basic.basic.lock().await.caches.clear();
+        std::mem::replace(&mut *m, updated)
+    }
+}
+
+#[async_trait]
+impl<Dep, Ev> CxAwareAsyncMediatorInternalDeadLetter<Dep, Ev> for CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug + Send + Sync,
+    Ev: Debug + Send,
+{
+    /// Returns all [`DeadLetter`]s in the order they were collected.
+    ///
+    /// See [`BasicAsyncMediator::dead_letters()`] for more info.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn dead_letters(&self) -> io::Result<Vec<DeadLetter<Ev>>> {
+        <BasicAsyncMediator<Ev> as AsyncMediatorInternalDeadLetter<Ev>>::dead_letters(&self.basic)
+            .await
+    }
+
+    /// Removes all dead events from the queue and publishes them again.
+    ///
+    /// Returns the number of re-published events.
+    ///
+    async fn redispatch_events(&self) -> io::Result<usize> {
+        <BasicAsyncMediator<Ev> as AsyncMediatorInternalDeadLetter<Ev>>::redispatch_events(
+            &self.basic,
+        )
+        .await
+    }
+
+    /// Removes all failed requests of type `Req` from the queue
+    /// and sends them again via [`CxAwareAsyncMediator::send_with_retry()`].
+    ///
+    /// Returns the result of every request. Requests that fail again
+    /// are collected again with a new id.
+    ///
+    async fn redispatch_requests<Req>(
+        &self,
+    ) -> io::Result<Vec<Result<(), <Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error>>>
+    where
+        Req: Record + Clone + Send + 'static,
+        Self: TryCxAwareAsyncRequestHandler<Dep, Req, Ev>,
+        <Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error: 'static,
+    {
+        let requests = self
+            .basic
+            .basic
+            .lock()
+            .await
+            .dead_letters
+            .take_requests::<Req>()?;
+        let mut results = Vec::with_capacity(requests.len());
+        for req in requests {
+            results.push(self.send_with_retry(req).await);
+        }
+        Ok(results)
+    }
+
+    /// Removes all [`DeadLetter`]s and returns how many were removed.
+    ///
+    async fn purge_dead_letters(&self) -> io::Result<usize> {
+        <BasicAsyncMediator<Ev> as AsyncMediatorInternalDeadLetter<Ev>>::purge_dead_letters(
+            &self.basic,
+        )
+        .await
     }
 }
 
 #[async_trait]
 impl<Dep, Ev> AsyncMediatorInternalNext for CxAwareAsyncMediator<Dep, Ev>
 where
-    Dep: Debug + Send,
+    Dep: Debug + Send + Sync,
     Ev: Debug + Clone + Send,
 {
     /// Process the next published event `Ev` asynchronously.
@@ -165,6 +1164,207 @@ where
     /// You need to await the `Future` using `.await`.
     ///
     async fn next(&self) -> Result<(), TryRecvError> {
-        self.basic.next().await
+        let result = self.basic.next().await;
+        self.sagas.run(self).await;
+        result
+    }
+
+    /// Process the next event asynchronously, waiting for one to arrive if necessary.
+    ///
+    /// See [`BasicAsyncMediator::recv()`] for more info.
+    ///
+    async fn recv(&self) -> Result<(), RecvError> {
+        let result = self.basic.recv().await;
+        self.sagas.run(self).await;
+        result
+    }
+}
+
+#[async_trait]
+impl<Dep, Ev> AsyncMediatorInternalFlush for CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug + Send + Sync,
+    Ev: Debug + Clone + Send,
+{
+    /// Flushes the events buffered by batch and debounced listeners asynchronously.
+    ///
+    /// See [`BasicAsyncMediator::flush()`] for more info.
+    ///
+    async fn flush(&self) {
+        self.basic.flush().await
+    }
+}
+
+#[async_trait]
+impl<Dep, Ev> AsyncMediatorInternalSchedule<Ev> for CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug + Send + Sync,
+    Ev: Debug + Clone + Send,
+{
+    /// Schedules the event `Ev` to be published after `delay`.
+    ///
+    /// See [`BasicAsyncMediator::publish_after()`] for more info.
+    ///
+    async fn publish_after(&self, event: Ev, delay: Duration) -> ScheduledId {
+        self.basic.publish_after(event, delay).await
+    }
+
+    /// Schedules the event `Ev` to be published at `at`.
+    ///
+    /// See [`BasicAsyncMediator::publish_at()`] for more info.
+    ///
+    async fn publish_at(&self, event: Ev, at: Instant) -> ScheduledId {
+        self.basic.publish_at(event, at).await
+    }
+
+    /// Schedules an event created by `f` to be published every `interval`.
+    ///
+    /// See [`BasicAsyncMediator::publish_every()`] for more info.
+    ///
+    async fn publish_every<F>(&self, f: F, interval: Duration) -> ScheduledId
+    where
+        F: FnMut() -> Ev + Send + 'static,
+    {
+        self.basic.publish_every(f, interval).await
+    }
+
+    /// Cancels the scheduled event with `id`.
+    ///
+    /// See [`BasicAsyncMediator::cancel_scheduled()`] for more info.
+    ///
+    async fn cancel_scheduled(&self, id: ScheduledId) -> bool {
+        self.basic.cancel_scheduled(id).await
+    }
+
+    /// Process the next event asynchronously, waiting for scheduled events if necessary.
+    ///
+    /// See [`BasicAsyncMediator::next_scheduled()`] for more info.
+    ///
+    async fn next_scheduled(&self) -> Result<(), TryRecvError> {
+        let result = self.basic.next_scheduled().await;
+        self.sagas.run(self).await;
+        result
+    }
+}
+
+#[async_trait]
+impl<Dep, Ev> AsyncMediatorInternalNotify for CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug + Send + Sync + 'static,
+    Ev: Debug + Send,
+{
+    /// Publishes a [`Notification`] `N` asynchronously.
+    ///
+    /// Every notification handler registered for `N` via
+    /// [`super::CxAwareAsyncBuilder::add_notification_handler()`] receives
+    /// a clone of the notification and a snapshot of the dependency `Dep`.
+    /// The handlers are invoked according to the [`NotificationStrategy`]
+    /// chosen on the builder. The errors of all failed handlers are returned.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::asynchronous::contextaware::*;
+    /// use std::sync::Arc;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     One,
+    ///     Two
+    /// }
+    ///
+    /// #[derive(Debug, Default)]
+    /// struct MyContext(u32);
+    ///
+    /// #[derive(Clone)]
+    /// struct UserRegistered(String);
+    ///
+    /// impl Notification for UserRegistered {
+    ///     type Error = String;
+    /// }
+    ///
+    /// async_std::task::block_on(async {
+    ///     let mediator = CxAwareAsyncMediator::<MyContext, MyEvent>::builder()
+    ///         .add_dependency(MyContext::default())
+    ///         .add_notification_handler(|n: UserRegistered, cx: Arc<MyContext>| async move {
+    ///             /* Send a welcome mail */
+    ///             Ok(())
+    ///         })
+    ///         .build()
+    ///         .unwrap();
+    ///
+    ///     let result = mediator.publish_notification(UserRegistered(String::from("Jan"))).await;
+    ///     assert!(result.is_ok());
+    /// });
+    ///
+    async fn publish_notification<N>(&self, notification: N) -> Result<(), Vec<N::Error>>
+    where
+        N: Notification,
+    {
+        let dep = self.dependency().await;
+        self.notifications
+            .publish(notification, dep, self.notifications.strategy)
+            .await
+    }
+
+    /// Publishes a [`Notification`] `N` asynchronously using the given [`NotificationStrategy`].
+    ///
+    /// See [`CxAwareAsyncMediator::publish_notification()`] for more info.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn publish_notification_with<N>(
+        &self,
+        notification: N,
+        strategy: NotificationStrategy,
+    ) -> Result<(), Vec<N::Error>>
+    where
+        N: Notification,
+    {
+        let dep = self.dependency().await;
+        self.notifications
+            .publish(notification, dep, strategy)
+            .await
+    }
+}
+
+#[async_trait]
+impl<Dep, Ev> AsyncMediatorInternalSubscribe<Ev> for CxAwareAsyncMediator<Dep, Ev>
+where
+    Dep: Debug + Send + Sync,
+    Ev: Debug + Clone + Send + 'static,
+{
+    /// Subscribes to the events `Ev` of the mediator as a [`futures::Stream`].
+    ///
+    /// This method instructs the underlying [`BasicAsyncMediator`]
+    /// to create a new subscription.
+    ///
+    /// See [`BasicAsyncMediator::subscribe_stream()`] for more info.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn subscribe_stream(&self) -> EventStream<Ev> {
+        self.basic.subscribe_stream().await
+    }
+
+    /// Subscribes to the events `Ev` of the mediator as a [`futures::Stream`],
+    /// buffering at most `capacity` events for this subscriber.
+    ///
+    /// See [`BasicAsyncMediator::subscribe_stream_bounded()`] for more info.
+    ///
+    /// You need to await the `Future` using `.await`.
+    ///
+    async fn subscribe_stream_bounded(
+        &self,
+        capacity: usize,
+        overflow: Overflow,
+    ) -> EventStream<Ev> {
+        self.basic
+            .subscribe_stream_bounded(capacity, overflow)
+            .await
     }
 }
@@ -1,5 +1,29 @@
 use async_trait::async_trait;
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    future::Future,
+    hash::Hash,
+    io,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::mediator::asynchronous::{
+    cancellation::{CancellationToken, Interrupted},
+    guard::{BreakerState, GuardError},
+    notification::{Notification, NotificationStrategy},
+    sink::RequestSink,
+};
+use crate::mediator::{
+    cache::QueryCache,
+    dead_letter::DeadLetter,
+    history::{CommandHistory, Undoable},
+    idempotency::Idempotent,
+    saga::{Saga, SagaInstance},
+    storage::{Record, Storage},
+};
+
+use super::{container::Container, scope::Outcome, CxAwareAsyncMediator};
 
 /// Send a request `Req` asynchronously for processing to the mediator.
 /// This will call the handler.
@@ -12,12 +36,304 @@ pub trait CxAwareAsyncMediatorInternalHandle<Dep, Ev: Debug> {
         Self: CxAwareAsyncRequestHandler<Dep, Req, Ev>;
 }
 
+/// Execute a command `Cmd` asynchronously, which may publish events, or
+/// answer a query `Q` asynchronously, which cannot.
+/// This will call the command or query handler.
+/// The handlers here are context-dependent.
+#[async_trait]
+pub trait CxAwareAsyncMediatorInternalCqrs<Dep: Debug, Ev: Debug> {
+    async fn execute<Cmd>(&self, cmd: Cmd)
+    where
+        Cmd: Send,
+        Self: CxAwareAsyncCommandHandler<Dep, Cmd, Ev>;
+
+    async fn query<Q>(&self, query: Q) -> <Self as CxAwareAsyncQueryHandler<Dep, Q, Ev>>::Response
+    where
+        Q: Send + 'static,
+        Self: CxAwareAsyncQueryHandler<Dep, Q, Ev>;
+}
+
+/// Send an [`Idempotent`] request `Req` asynchronously for processing to the mediator,
+/// unless a request with the same key was already handled.
+/// This will call the handler.
+/// The handler here is context-dependent.
+#[async_trait]
+pub trait CxAwareAsyncMediatorInternalIdempotency<Dep, Ev: Debug> {
+    async fn send_idempotent<Req>(&self, req: Req)
+    where
+        Req: Idempotent + Send,
+        Self: CxAwareAsyncRequestHandler<Dep, Req, Ev>;
+
+    async fn try_send_idempotent<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), <Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error>
+    where
+        Req: Idempotent + Send,
+        Self: TryCxAwareAsyncRequestHandler<Dep, Req, Ev>,
+        <Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error: Clone + Sync + 'static;
+}
+
+/// Send an [`Undoable`] request `Req` asynchronously for processing to the mediator
+/// and undo or redo the recorded requests.
+/// This will call the handler.
+/// The handler here is context-dependent.
+#[async_trait]
+pub trait CxAwareAsyncMediatorInternalHistory<Dep, Ev: Debug> {
+    async fn send_undoable<Req>(&self, req: Req)
+    where
+        Req: Undoable,
+        Self: CxAwareAsyncRequestHandler<Dep, Req, Ev>
+            + CxAwareAsyncRequestHandler<Dep, Req::Inverse, Ev>;
+
+    async fn undo(&self) -> bool;
+
+    async fn redo(&self) -> bool;
+
+    fn begin_group(&self, label: &str);
+
+    fn end_group(&self);
+
+    fn history(&self) -> CommandHistory;
+
+    fn clear_history(&self);
+}
+
 /// Handles the request `Req` asynchronously.
 /// Implemented by the user.
 /// Gives access to the dependency `Dep`.
 #[async_trait]
 pub trait CxAwareAsyncRequestHandler<Dep, Req, Res> {
     async fn handle(&self, req: Req, dep: &Dep);
+
+    /// Handles the request `Req` with access to its scoped dependencies.
+    /// Calls [`CxAwareAsyncRequestHandler::handle()`] by default.
+    async fn handle_scoped(&self, req: Req, dep: &Dep, _scope: &mut Container)
+    where
+        Self: Sync,
+        Req: Send + 'async_trait,
+        Dep: Sync,
+    {
+        self.handle(req, dep).await
+    }
+}
+
+/// Handles the command `Cmd` asynchronously, which may publish events `Ev`.
+/// Implemented by the user.
+/// Gives access to the dependency `Dep`.
+/// Unlike a [`CxAwareAsyncRequestHandler`], it is only called by
+/// [`CxAwareAsyncMediatorInternalCqrs::execute()`],
+/// so the write side of the mediator is kept apart from plain requests.
+#[async_trait]
+pub trait CxAwareAsyncCommandHandler<Dep, Cmd, Ev> {
+    async fn handle(&self, cmd: Cmd, dep: &Dep);
+
+    /// Handles the command `Cmd` with access to its scoped dependencies.
+    /// Calls [`CxAwareAsyncCommandHandler::handle()`] by default.
+    async fn handle_scoped(&self, cmd: Cmd, dep: &Dep, _scope: &mut Container)
+    where
+        Self: Sync,
+        Cmd: Send + 'async_trait,
+        Dep: Sync,
+    {
+        self.handle(cmd, dep).await
+    }
+}
+
+/// Answers the query `Q` asynchronously with a `Response`.
+/// Implemented by the user.
+/// A query only reads, so the handler is not given the mediator but a
+/// [`CxAwareQueryContext`], which gives read-only access to the dependency `Dep`
+/// and can only answer other queries.
+#[async_trait]
+pub trait CxAwareAsyncQueryHandler<Dep, Q, Ev>
+where
+    Self: Sized,
+    Dep: Debug,
+    Ev: Debug,
+{
+    type Response: Send + 'static;
+    async fn handle(query: Q, cx: &CxAwareQueryContext<'_, Dep, Ev>) -> Self::Response;
+}
+
+/// Read-only access to the context-aware mediator while answering a query.
+///
+/// A query handler can read the dependency `Dep` and answer other queries through it,
+/// but cannot publish events, send requests or execute commands.
+/// The dependency is read-locked until the outermost query is answered.
+///
+/// # Examples
+///
+/// Publishing from a query handler does not compile:
+///
+/// ```compile_fail
+/// use mediator_sys::asynchronous::contextaware::*;
+/// use async_trait::async_trait;
+///
+/// #[derive(Debug, Clone)]
+/// enum MyEvent {
+///     Read,
+/// }
+///
+/// #[derive(Debug)]
+/// struct Inventory;
+///
+/// struct Count;
+///
+/// #[async_trait]
+/// impl CxAwareAsyncQueryHandler<Inventory, Count, MyEvent> for CxAwareAsyncMediator<Inventory, MyEvent> {
+///     type Response = usize;
+///
+///     async fn handle(_: Count, cx: &CxAwareQueryContext<'_, Inventory, MyEvent>) -> usize {
+///         cx.publish(MyEvent::Read).await;
+///         0
+///     }
+/// }
+///
+#[derive(Debug)]
+pub struct CxAwareQueryContext<'a, Dep, Ev>
+where
+    Dep: Debug,
+    Ev: Debug,
+{
+    pub(crate) mediator: &'a CxAwareAsyncMediator<Dep, Ev>,
+    pub(crate) dep: &'a Dep,
+}
+
+/// Send a request `Req` asynchronously for processing to the mediator
+/// and interrupt the handler on a timeout, deadline or cancellation.
+/// This will call the handler.
+/// The handler here is context-dependent.
+#[async_trait]
+pub trait CxAwareAsyncMediatorInternalTimeout<Dep, Ev: Debug> {
+    async fn send_with_timeout<Req>(&self, req: Req, timeout: Duration) -> Result<(), Interrupted>
+    where
+        Req: Send,
+        Self: CxAwareAsyncRequestHandler<Dep, Req, Ev>;
+
+    async fn send_with_deadline<Req>(&self, req: Req, deadline: Instant) -> Result<(), Interrupted>
+    where
+        Req: Send,
+        Self: CxAwareAsyncRequestHandler<Dep, Req, Ev>;
+
+    async fn send_with_cancellation<Req>(
+        &self,
+        req: Req,
+        token: CancellationToken,
+    ) -> Result<(), Interrupted>
+    where
+        Req: Send,
+        Self: CxAwareAsyncRequestHandler<Dep, Req, Ev>;
+}
+
+/// Send a request `Req` asynchronously for processing to the mediator.
+/// This will set up the scoped dependencies and call the fallible handler.
+/// The handler here is context-dependent.
+#[async_trait]
+pub trait TryCxAwareAsyncMediatorInternalHandle<Dep, Ev: Debug> {
+    async fn try_send<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), <Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error>
+    where
+        Req: Send,
+        Self: TryCxAwareAsyncRequestHandler<Dep, Req, Ev>;
+}
+
+/// Handles the request `Req` asynchronously and may fail.
+/// Implemented by the user.
+/// Gives access to the dependency `Dep` and to the
+/// scoped dependencies of this request.
+#[async_trait]
+pub trait TryCxAwareAsyncRequestHandler<Dep, Req, Res> {
+    type Error: Send;
+    async fn try_handle(
+        &self,
+        req: Req,
+        dep: &Dep,
+        scope: &mut Container,
+    ) -> Result<(), Self::Error>;
+}
+
+/// Send a request `Req` asynchronously for processing to the mediator
+/// and retry it according to its [`crate::retry::RetryPolicy`].
+/// This will set up the scoped dependencies and call the fallible handler for every attempt.
+/// The handler here is context-dependent.
+#[async_trait]
+pub trait CxAwareAsyncMediatorInternalRetry<Dep, Ev: Debug> {
+    async fn send_with_retry<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), <Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error>
+    where
+        Req: Clone + Send + 'static,
+        Self: TryCxAwareAsyncRequestHandler<Dep, Req, Ev>,
+        <Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error: 'static;
+}
+
+/// Send a request `Req` asynchronously through the circuit breaker
+/// and bulkhead of its request type.
+/// This will set up the scoped dependencies and call the fallible handler
+/// unless the request is rejected.
+/// The handler here is context-dependent.
+#[async_trait]
+pub trait CxAwareAsyncMediatorInternalGuard<Dep, Ev: Debug> {
+    async fn send_guarded<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), GuardError<<Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error>>
+    where
+        Req: Send + 'static,
+        Self: TryCxAwareAsyncRequestHandler<Dep, Req, Ev>;
+
+    fn breaker_state<Req>(&self) -> Option<BreakerState>
+    where
+        Req: 'static;
+}
+
+/// Inspect, re-dispatch and purge the [`DeadLetter`]s of the mediator asynchronously.
+/// Re-dispatched requests are handled context-dependent.
+#[async_trait]
+pub trait CxAwareAsyncMediatorInternalDeadLetter<Dep, Ev: Debug> {
+    async fn dead_letters(&self) -> io::Result<Vec<DeadLetter<Ev>>>;
+
+    async fn redispatch_events(&self) -> io::Result<usize>;
+
+    #[allow(clippy::type_complexity)]
+    async fn redispatch_requests<Req>(
+        &self,
+    ) -> io::Result<Vec<Result<(), <Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error>>>
+    where
+        Req: Record + Clone + Send + 'static,
+        Self: TryCxAwareAsyncRequestHandler<Dep, Req, Ev>,
+        <Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error: 'static;
+
+    async fn purge_dead_letters(&self) -> io::Result<usize>;
+}
+
+/// Feed requests `Req` to the mediator through a [`futures::Sink`].
+/// Every request will set up the scoped dependencies and call the fallible handler.
+/// The handler here is context-dependent.
+pub trait CxAwareAsyncMediatorInternalSink<Dep, Ev: Debug> {
+    fn request_sink<'a, Req>(
+        &'a self,
+        concurrency: usize,
+    ) -> RequestSink<'a, Req, <Self as TryCxAwareAsyncRequestHandler<Dep, Req, Ev>>::Error>
+    where
+        Req: Send + 'a,
+        Self: TryCxAwareAsyncRequestHandler<Dep, Req, Ev>;
+}
+
+/// Access and replace the dependency `Dep` at runtime.
+#[async_trait]
+pub trait CxAwareAsyncMediatorInternalDependency<Dep> {
+    async fn dependency(&self) -> Arc<Dep>;
+
+    async fn replace_dependency(&self, dep: Dep) -> Arc<Dep>;
+
+    async fn update_dependency<F>(&self, f: F) -> Arc<Dep>
+    where
+        F: FnOnce(&Dep) -> Dep + Send;
 }
 
 /// Advanced builder fuctionality:
@@ -27,3 +343,70 @@ pub trait CxAwareMediatorBuilderInterface<M, Dep, Ev> {
     where
         Ev: Debug;
 }
+
+/// Service container builder fuctionality:
+/// Adding services to a [`super::Container`] and
+/// declaring services as required.
+pub trait CxAwareContainerBuilderInterface<M, Ev> {
+    fn add_service<T>(self, service: T) -> Self
+    where
+        T: Send + Sync + 'static;
+
+    fn require_service<T>(self) -> Self
+    where
+        T: 'static;
+}
+
+/// Scoped builder fuctionality:
+/// Adding a factory for a dependency that lives for a single request.
+pub trait CxAwareScopedBuilderInterface<M, Dep, Ev> {
+    fn add_scoped<S, F, T>(self, factory: F, teardown: T) -> Self
+    where
+        S: Send + Sync + 'static,
+        F: Fn(&Dep) -> S + Send + Sync + 'static,
+        T: Fn(S, Outcome) + Send + Sync + 'static;
+}
+
+/// Context aware notification builder fuctionality:
+/// Adding a notification handler for a [`Notification`] `N`
+/// that has access to the dependency `Dep`,
+/// and choosing the [`NotificationStrategy`].
+pub trait CxAwareNotificationBuilderInterface<M, Dep, Ev> {
+    fn add_notification_handler<N, F, Fut>(self, f: F) -> Self
+    where
+        N: Notification,
+        F: Fn(N, Arc<Dep>) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), N::Error>> + Send + 'static;
+
+    fn notification_strategy(self, strategy: NotificationStrategy) -> Self;
+}
+
+/// Query cache builder fuctionality:
+/// Caching the responses to queries of type `Q` with a [`QueryCache`].
+pub trait CxAwareQueryCacheBuilderInterface<M, Dep, Ev> {
+    fn cache_queries<Q>(self, cache: QueryCache<Ev>) -> Self
+    where
+        M: CxAwareAsyncQueryHandler<Dep, Q, Ev>,
+        Q: Hash + Eq + Clone + Send + 'static,
+        <M as CxAwareAsyncQueryHandler<Dep, Q, Ev>>::Response: Clone,
+        Dep: Debug,
+        Ev: Debug;
+}
+
+/// Saga builder fuctionality:
+/// Adding a [`Saga`], whose follow-up requests are handled by the mediator
+/// with access to the dependency `Dep`.
+pub trait CxAwareSagaBuilderInterface<M, Dep, Ev> {
+    fn add_saga<S>(self, saga: S) -> Self
+    where
+        S: Saga<Ev>,
+        M: TryCxAwareAsyncRequestHandler<Dep, S::Request, Ev>,
+        Ev: Debug + Clone + Send + 'static;
+
+    fn add_saga_with_storage<S, St>(self, saga: S, storage: St) -> Self
+    where
+        S: Saga<Ev>,
+        St: Storage<SagaInstance<S::Id, S::State>> + 'static,
+        M: TryCxAwareAsyncRequestHandler<Dep, S::Request, Ev>,
+        Ev: Debug + Clone + Send + 'static;
+}
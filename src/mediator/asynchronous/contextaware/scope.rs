@@ -0,0 +1,109 @@
+use std::fmt::Debug;
+
+use super::container::Container;
+
+/// The outcome of a request, as seen by the teardown of a scoped dependency.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// The handler returned `Ok`, or an infallible handler finished.
+    Success,
+    /// The handler returned `Err`, panicked or was dropped before it finished.
+    Failure,
+}
+
+impl Outcome {
+    /// Returns `true` if the handler succeeded.
+    pub fn is_success(&self) -> bool {
+        matches!(self, Outcome::Success)
+    }
+
+    pub(crate) fn of<T, E>(result: &Result<T, E>) -> Self {
+        match result {
+            Ok(_) => Outcome::Success,
+            Err(_) => Outcome::Failure,
+        }
+    }
+}
+
+/// A factory for a scoped dependency.
+///
+/// Creates a fresh value from the dependency `Dep` when a request starts
+/// and hands it to the user-defined teardown once the handler finished.
+pub(crate) struct ScopedFactory<Dep> {
+    create: Create<Dep>,
+    teardown: Teardown,
+}
+
+type Create<Dep> = Box<dyn Fn(&Dep, &mut Container) + Send + Sync>;
+type Teardown = Box<dyn Fn(&mut Container, Outcome) + Send + Sync>;
+
+impl<Dep> ScopedFactory<Dep> {
+    pub(crate) fn new<S, F, T>(factory: F, teardown: T) -> Self
+    where
+        S: Send + Sync + 'static,
+        F: Fn(&Dep) -> S + Send + Sync + 'static,
+        T: Fn(S, Outcome) + Send + Sync + 'static,
+    {
+        Self {
+            create: Box::new(move |dep, scope| {
+                scope.insert(factory(dep));
+            }),
+            teardown: Box::new(move |scope, outcome| {
+                if let Some(value) = scope.remove::<S>() {
+                    teardown(value, outcome)
+                }
+            }),
+        }
+    }
+
+    pub(crate) fn create(&self, dep: &Dep, scope: &mut Container) {
+        (self.create)(dep, scope)
+    }
+
+    pub(crate) fn teardown(&self, scope: &mut Container, outcome: Outcome) {
+        (self.teardown)(scope, outcome)
+    }
+}
+
+/// The scoped dependencies of a single request.
+///
+/// They are torn down in reverse order once the [`Scope`] is dropped,
+/// with the [`Outcome`] passed to [`Scope::close()`] or [`Outcome::Failure`],
+/// if the handler panicked or its future was dropped before it finished.
+pub(crate) struct Scope<'a, Dep> {
+    factories: &'a [ScopedFactory<Dep>],
+    pub(crate) container: Container,
+    outcome: Outcome,
+}
+
+impl<'a, Dep> Scope<'a, Dep> {
+    pub(crate) fn open(factories: &'a [ScopedFactory<Dep>], dep: &Dep) -> Self {
+        let mut container = Container::new();
+        for factory in factories {
+            factory.create(dep, &mut container);
+        }
+        Self {
+            factories,
+            container,
+            outcome: Outcome::Failure,
+        }
+    }
+
+    pub(crate) fn close(mut self, outcome: Outcome) {
+        self.outcome = outcome;
+    }
+}
+
+impl<Dep> Drop for Scope<'_, Dep> {
+    fn drop(&mut self) {
+        for factory in self.factories.iter().rev() {
+            factory.teardown(&mut self.container, self.outcome);
+        }
+    }
+}
+
+impl<Dep> Debug for ScopedFactory<Dep> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Scoped Factory")
+    }
+}
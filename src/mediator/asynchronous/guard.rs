@@ -0,0 +1,393 @@
+use std::{
+    any::{type_name, TypeId},
+    collections::HashMap,
+    fmt::{Debug, Display},
+    future::Future,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::mediator::clock::Clock;
+
+type TransitionFn<Ev> = Box<dyn Fn(BreakerTransition) -> Ev + Send + Sync>;
+
+/// A [`CircuitBreaker`] stops sending requests of one type
+/// after its handler failed too often in a row.
+///
+/// Once `failure_threshold` consecutive requests failed, the breaker opens
+/// and rejects every request for the `open_for` duration.
+/// Afterwards, it is half-open and lets a single trial request through at a time.
+/// After `success_threshold` successful trials it closes again,
+/// while a failed trial opens it again.
+///
+/// Breakers are added per request type on the async builders, e.g. via
+/// [`crate::asynchronous::basic::BasicAsyncBuilder::add_circuit_breaker()`],
+/// and applied by `send_guarded()`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mediator_sys::asynchronous::guard::CircuitBreaker;
+/// use std::time::Duration;
+///
+/// let breaker = CircuitBreaker::new(5)
+///     .open_for(Duration::from_secs(30))
+///     .success_threshold(2);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_for: Duration,
+    success_threshold: u32,
+}
+
+impl CircuitBreaker {
+    /// Creates a [`CircuitBreaker`] that opens after `failure_threshold`
+    /// consecutive failures for 30 seconds and closes after one successful trial.
+    pub fn new(failure_threshold: u32) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            open_for: Duration::from_secs(30),
+            success_threshold: 1,
+        }
+    }
+
+    /// Keeps the breaker open for `duration` before trial requests are let through.
+    pub fn open_for(mut self, duration: Duration) -> Self {
+        self.open_for = duration;
+        self
+    }
+
+    /// Closes the half-open breaker after `successes` successful trial requests.
+    pub fn success_threshold(mut self, successes: u32) -> Self {
+        self.success_threshold = successes.max(1);
+        self
+    }
+
+    /// Returns the number of consecutive failures that open the breaker.
+    pub fn failure_threshold(&self) -> u32 {
+        self.failure_threshold
+    }
+
+    /// Returns the duration the breaker stays open.
+    pub fn open_duration(&self) -> Duration {
+        self.open_for
+    }
+}
+
+/// The state of a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Requests are sent, failures are counted.
+    Closed,
+    /// Requests are rejected.
+    Open,
+    /// A single trial request is sent at a time.
+    HalfOpen,
+}
+
+/// A state transition of the [`CircuitBreaker`] of the request type `request`.
+///
+/// Transitions are published as events of the mediator
+/// once they are mapped to the event type via `on_breaker_transition()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BreakerTransition {
+    /// The name of the request type.
+    pub request: &'static str,
+    /// The state before the transition.
+    pub from: BreakerState,
+    /// The state after the transition.
+    pub to: BreakerState,
+}
+
+/// The error of a request sent via `send_guarded()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuardError<E> {
+    /// The [`CircuitBreaker`] of the request type is open.
+    CircuitOpen,
+    /// The bulkhead of the request type has no capacity left.
+    BulkheadFull,
+    /// The handler failed with `E`.
+    Handler(E),
+}
+
+impl<E> GuardError<E> {
+    /// Returns `true` if the request was rejected without calling the handler.
+    pub fn is_rejected(&self) -> bool {
+        !matches!(self, GuardError::Handler(_))
+    }
+}
+
+impl<E> Display for GuardError<E>
+where
+    E: Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GuardError::CircuitOpen => write!(f, "circuit breaker is open"),
+            GuardError::BulkheadFull => write!(f, "bulkhead is full"),
+            GuardError::Handler(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl<E> std::error::Error for GuardError<E> where E: std::error::Error {}
+
+/// The circuit breakers and bulkheads of an async mediator, keyed by the request type.
+pub(crate) struct Guards<Ev> {
+    guards: HashMap<TypeId, Guard>,
+    on_transition: Option<TransitionFn<Ev>>,
+}
+
+impl<Ev> Guards<Ev> {
+    pub(crate) fn new() -> Self {
+        Self {
+            guards: HashMap::new(),
+            on_transition: None,
+        }
+    }
+
+    pub(crate) fn add_circuit_breaker<Req>(&mut self, breaker: CircuitBreaker)
+    where
+        Req: 'static,
+    {
+        self.guard::<Req>().breaker = Some((breaker, Mutex::new(BreakerStatus::default())));
+    }
+
+    pub(crate) fn add_bulkhead<Req>(&mut self, max_in_flight: usize)
+    where
+        Req: 'static,
+    {
+        self.guard::<Req>().bulkhead = Some((max_in_flight.max(1), AtomicUsize::new(0)));
+    }
+
+    pub(crate) fn on_transition<F>(&mut self, f: F)
+    where
+        F: Fn(BreakerTransition) -> Ev + Send + Sync + 'static,
+    {
+        self.on_transition = Some(Box::new(f));
+    }
+
+    pub(crate) fn state<Req>(&self) -> Option<BreakerState>
+    where
+        Req: 'static,
+    {
+        self.guards
+            .get(&TypeId::of::<Req>())
+            .and_then(|guard| guard.breaker.as_ref())
+            .map(|(_, status)| status.lock().unwrap().state)
+    }
+
+    /// Runs `send` unless the guard of `Req` rejects it.
+    /// Returns the result along with the events of all breaker transitions.
+    pub(crate) async fn run<Req, E, F>(
+        &self,
+        clock: &dyn Clock,
+        send: F,
+    ) -> (Result<(), GuardError<E>>, Vec<Ev>)
+    where
+        Req: 'static,
+        F: Future<Output = Result<(), E>>,
+    {
+        let guard = match self.guards.get(&TypeId::of::<Req>()) {
+            Some(guard) => guard,
+            None => return (send.await.map_err(GuardError::Handler), vec![]),
+        };
+
+        let mut transitions = vec![];
+        let result = match guard.enter(clock.now(), &mut transitions) {
+            Ok(permit) => {
+                let result = send.await;
+                guard.record(permit, result.is_ok(), clock.now(), &mut transitions);
+                result.map_err(GuardError::Handler)
+            }
+            Err(err) => Err(err),
+        };
+
+        let events = match &self.on_transition {
+            Some(f) => transitions.into_iter().map(f).collect(),
+            None => vec![],
+        };
+        (result, events)
+    }
+
+    fn guard<Req>(&mut self) -> &mut Guard
+    where
+        Req: 'static,
+    {
+        self.guards.entry(TypeId::of::<Req>()).or_insert(Guard {
+            request: type_name::<Req>(),
+            breaker: None,
+            bulkhead: None,
+        })
+    }
+}
+
+impl<Ev> Debug for Guards<Ev> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Guards")
+            .field(
+                "requests",
+                &self
+                    .guards
+                    .values()
+                    .map(|guard| guard.request)
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// The circuit breaker and bulkhead of a single request type.
+struct Guard {
+    request: &'static str,
+    breaker: Option<(CircuitBreaker, Mutex<BreakerStatus>)>,
+    bulkhead: Option<(usize, AtomicUsize)>,
+}
+
+#[derive(Debug)]
+struct BreakerStatus {
+    state: BreakerState,
+    failures: u32,
+    successes: u32,
+    opened_at: Option<Instant>,
+    trial: bool,
+}
+
+impl Default for BreakerStatus {
+    fn default() -> Self {
+        Self {
+            state: BreakerState::Closed,
+            failures: 0,
+            successes: 0,
+            opened_at: None,
+            trial: false,
+        }
+    }
+}
+
+impl Guard {
+    /// Takes a slot of the bulkhead and asks the breaker for permission.
+    fn enter<E>(
+        &self,
+        now: Instant,
+        transitions: &mut Vec<BreakerTransition>,
+    ) -> Result<Permit<'_>, GuardError<E>> {
+        let mut permit = Permit {
+            guard: self,
+            slot: false,
+            trial: false,
+        };
+        if let Some((max, in_flight)) = &self.bulkhead {
+            if in_flight.fetch_add(1, Ordering::SeqCst) >= *max {
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                return Err(GuardError::BulkheadFull);
+            }
+            permit.slot = true;
+        }
+
+        if let Some((breaker, status)) = &self.breaker {
+            let mut status = status.lock().unwrap();
+            if status.state == BreakerState::Open {
+                let elapsed = status
+                    .opened_at
+                    .map(|opened_at| now.saturating_duration_since(opened_at))
+                    .unwrap_or_default();
+                if elapsed < breaker.open_for {
+                    return Err(GuardError::CircuitOpen);
+                }
+                self.transition(&mut status, BreakerState::HalfOpen, transitions);
+            }
+            if status.state == BreakerState::HalfOpen {
+                if status.trial {
+                    return Err(GuardError::CircuitOpen);
+                }
+                status.trial = true;
+                permit.trial = true;
+            }
+        }
+
+        Ok(permit)
+    }
+
+    /// Records the outcome of a request that was let through.
+    fn record(
+        &self,
+        mut permit: Permit<'_>,
+        success: bool,
+        now: Instant,
+        transitions: &mut Vec<BreakerTransition>,
+    ) {
+        permit.trial = false;
+        let (breaker, status) = match &self.breaker {
+            Some(breaker) => breaker,
+            None => return,
+        };
+        let mut status = status.lock().unwrap();
+
+        match (status.state, success) {
+            (BreakerState::Closed, true) => status.failures = 0,
+            (BreakerState::Closed, false) => {
+                status.failures += 1;
+                if status.failures >= breaker.failure_threshold {
+                    status.opened_at = Some(now);
+                    self.transition(&mut status, BreakerState::Open, transitions);
+                }
+            }
+            (BreakerState::HalfOpen, true) => {
+                status.trial = false;
+                status.successes += 1;
+                if status.successes >= breaker.success_threshold {
+                    self.transition(&mut status, BreakerState::Closed, transitions);
+                }
+            }
+            (BreakerState::HalfOpen, false) => {
+                status.opened_at = Some(now);
+                self.transition(&mut status, BreakerState::Open, transitions);
+            }
+            // Another request opened the breaker while this one was in flight.
+            (BreakerState::Open, _) => (),
+        }
+    }
+
+    fn transition(
+        &self,
+        status: &mut BreakerStatus,
+        to: BreakerState,
+        transitions: &mut Vec<BreakerTransition>,
+    ) {
+        transitions.push(BreakerTransition {
+            request: self.request,
+            from: status.state,
+            to,
+        });
+        status.state = to;
+        status.failures = 0;
+        status.successes = 0;
+        status.trial = false;
+    }
+}
+
+/// A slot of the bulkhead and the trial of the half-open breaker,
+/// which are released on drop, e.g. when the request is interrupted.
+struct Permit<'a> {
+    guard: &'a Guard,
+    slot: bool,
+    trial: bool,
+}
+
+impl<'a> Drop for Permit<'a> {
+    fn drop(&mut self) {
+        if let (true, Some((_, in_flight))) = (self.slot, &self.guard.bulkhead) {
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+        }
+        if let (true, Some((_, status))) = (self.trial, &self.guard.breaker) {
+            status.lock().unwrap().trial = false;
+        }
+    }
+}
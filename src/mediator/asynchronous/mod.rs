@@ -1,2 +1,7 @@
 pub mod basic;
+pub mod cancellation;
 pub mod contextaware;
+pub mod guard;
+pub mod notification;
+pub mod sink;
+pub mod stream;
@@ -0,0 +1,139 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt::Debug,
+    future::Future,
+    marker::PhantomData,
+};
+
+use futures::future::{join_all, BoxFuture};
+
+/// A [`Notification`] is fanned out to every notification handler registered for its type.
+///
+/// Unlike requests, which are processed by exactly one handler,
+/// and events, which are passed to untyped listeners,
+/// a notification may be handled by any number of typed, asynchronous
+/// and fallible notification handlers.
+/// Implemented by the user.
+pub trait Notification: Clone + Send + 'static {
+    /// The error a notification handler may return.
+    type Error: Send + 'static;
+}
+
+/// The strategy used to invoke the notification handlers of a [`Notification`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NotificationStrategy {
+    /// Invokes all handlers one after another in order of registration
+    /// and collects all errors.
+    #[default]
+    Sequential,
+    /// Invokes all handlers concurrently and collects all errors.
+    Parallel,
+    /// Invokes the handlers one after another in order of registration
+    /// and stops at the first error.
+    StopOnFirstError,
+}
+
+type Handler<N, Cx> =
+    Box<dyn Fn(N, Cx) -> BoxFuture<'static, Result<(), <N as Notification>::Error>> + Send + Sync>;
+
+/// Registry of notification handlers, keyed by the type of the [`Notification`].
+///
+/// Handlers receive a context `Cx` along with the notification.
+pub(crate) struct NotificationHandlers<Cx> {
+    handlers: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    pub(crate) strategy: NotificationStrategy,
+    context: PhantomData<fn(Cx)>,
+}
+
+impl<Cx> NotificationHandlers<Cx> {
+    pub(crate) fn new() -> Self {
+        Self {
+            handlers: HashMap::new(),
+            strategy: NotificationStrategy::default(),
+            context: PhantomData,
+        }
+    }
+}
+
+impl<Cx> NotificationHandlers<Cx>
+where
+    Cx: Clone + Send + 'static,
+{
+    pub(crate) fn add<N, F, Fut>(&mut self, f: F)
+    where
+        N: Notification,
+        F: Fn(N, Cx) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), N::Error>> + Send + 'static,
+    {
+        let handler: Handler<N, Cx> = Box::new(move |n, cx| Box::pin(f(n, cx)));
+        self.handlers
+            .entry(TypeId::of::<N>())
+            .or_insert_with(|| Box::new(Vec::<Handler<N, Cx>>::new()))
+            .downcast_mut::<Vec<Handler<N, Cx>>>()
+            .expect("notification handlers are keyed by their notification type")
+            .push(handler);
+    }
+
+    pub(crate) async fn publish<N>(
+        &self,
+        notification: N,
+        cx: Cx,
+        strategy: NotificationStrategy,
+    ) -> Result<(), Vec<N::Error>>
+    where
+        N: Notification,
+    {
+        let handlers = match self
+            .handlers
+            .get(&TypeId::of::<N>())
+            .and_then(|handlers| handlers.downcast_ref::<Vec<Handler<N, Cx>>>())
+        {
+            Some(handlers) => handlers,
+            None => return Ok(()),
+        };
+
+        let mut errors = vec![];
+        match strategy {
+            NotificationStrategy::Sequential => {
+                for handler in handlers.iter() {
+                    if let Err(err) = handler(notification.clone(), cx.clone()).await {
+                        errors.push(err);
+                    }
+                }
+            }
+            NotificationStrategy::Parallel => {
+                let results = join_all(
+                    handlers
+                        .iter()
+                        .map(|handler| handler(notification.clone(), cx.clone())),
+                )
+                .await;
+                errors.extend(results.into_iter().filter_map(Result::err));
+            }
+            NotificationStrategy::StopOnFirstError => {
+                for handler in handlers.iter() {
+                    if let Err(err) = handler(notification.clone(), cx.clone()).await {
+                        errors.push(err);
+                        break;
+                    }
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+impl<Cx> Debug for NotificationHandlers<Cx> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NotificationHandlers")
+            .field("types", &self.handlers.len())
+            .field("strategy", &self.strategy)
+            .finish()
+    }
+}
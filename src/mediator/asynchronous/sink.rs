@@ -0,0 +1,90 @@
+use std::{
+    fmt::Debug,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{future::BoxFuture, stream::FuturesUnordered, Sink, Stream};
+
+type SendFn<'a, Req, E> = Box<dyn Fn(Req) -> BoxFuture<'a, Result<(), E>> + Send + Sync + 'a>;
+
+/// A [`Sink`] of requests `Req` for an async mediator.
+///
+/// Created by [`super::basic::AsyncMediatorInternalSink::request_sink()`] or
+/// [`super::contextaware::CxAwareAsyncMediatorInternalSink::request_sink()`].
+/// Every request fed into the sink is sent to the mediator.
+/// Up to `concurrency` requests are handled at the same time,
+/// further requests wait until a slot becomes available.
+///
+/// If a handler fails, its error is returned by the sink.
+#[must_use = "sinks do nothing unless polled"]
+pub struct RequestSink<'a, Req, E> {
+    send: SendFn<'a, Req, E>,
+    in_flight: FuturesUnordered<BoxFuture<'a, Result<(), E>>>,
+    concurrency: usize,
+}
+
+impl<'a, Req, E> RequestSink<'a, Req, E> {
+    pub(crate) fn new<F>(concurrency: usize, send: F) -> Self
+    where
+        F: Fn(Req) -> BoxFuture<'a, Result<(), E>> + Send + Sync + 'a,
+    {
+        Self {
+            send: Box::new(send),
+            in_flight: FuturesUnordered::new(),
+            concurrency: concurrency.max(1),
+        }
+    }
+
+    /// Returns the number of requests currently being handled.
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    /// Polls the requests in flight until `limit` or less remain.
+    /// Returns the first error of a failed handler.
+    fn poll_until(&mut self, cx: &mut Context<'_>, limit: usize) -> Poll<Result<(), E>> {
+        while self.in_flight.len() > limit {
+            match Pin::new(&mut self.in_flight).poll_next(cx) {
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Err(err)),
+                Poll::Ready(Some(Ok(()))) | Poll::Ready(None) => (),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<'a, Req, E> Sink<Req> for RequestSink<'a, Req, E> {
+    type Error = E;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        let limit = this.concurrency - 1;
+        this.poll_until(cx, limit)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Req) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        let future = (this.send)(item);
+        this.in_flight.push(future);
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().poll_until(cx, 0)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().poll_until(cx, 0)
+    }
+}
+
+impl<'a, Req, E> Debug for RequestSink<'a, Req, E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestSink")
+            .field("in_flight", &self.in_flight.len())
+            .field("concurrency", &self.concurrency)
+            .finish()
+    }
+}
@@ -0,0 +1,239 @@
+use std::{
+    fmt::Debug,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, Weak,
+    },
+    task::{Context, Poll},
+};
+
+use futures::{
+    channel::mpsc::{channel, unbounded, Receiver, Sender, UnboundedReceiver, UnboundedSender},
+    Stream,
+};
+
+use crate::synchronous::basic::BasicMediator;
+
+/// What happens to an event dispatched while the buffer
+/// of a bounded [`EventStream`] is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// The event is dropped for this stream and counted by [`EventStream::lagged()`].
+    DropNewest,
+    /// The stream is closed and ends after the events it buffered.
+    Close,
+}
+
+/// A [`Stream`] of events `Ev` dispatched by an async mediator.
+///
+/// Created by [`super::basic::AsyncMediatorInternalSubscribe::subscribe_stream()`]
+/// or [`super::basic::AsyncMediatorInternalSubscribe::subscribe_stream_bounded()`].
+/// Every [`EventStream`] has its own buffer, which holds the events
+/// dispatched by `next()` until they are consumed.
+/// The stream ends once the mediator is dropped.
+///
+/// Dropping the stream unsubscribes it right away.
+#[derive(Debug)]
+pub struct EventStream<Ev> {
+    id: usize,
+    receiver: Buffer<Ev>,
+    lagged: Arc<AtomicUsize>,
+    list: Weak<Mutex<SubscriberList<Ev>>>,
+}
+
+#[derive(Debug)]
+enum Buffer<Ev> {
+    Unbounded(UnboundedReceiver<Ev>),
+    Bounded(Receiver<Ev>),
+}
+
+impl<Ev> EventStream<Ev> {
+    /// Returns the number of events this stream missed,
+    /// because its buffer was full and they were dropped.
+    pub fn lagged(&self) -> usize {
+        self.lagged.load(Ordering::SeqCst)
+    }
+}
+
+impl<Ev> Stream for EventStream<Ev> {
+    type Item = Ev;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match &mut self.receiver {
+            Buffer::Unbounded(receiver) => Pin::new(receiver).poll_next(cx),
+            Buffer::Bounded(receiver) => Pin::new(receiver).poll_next(cx),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.receiver {
+            Buffer::Unbounded(receiver) => receiver.size_hint(),
+            Buffer::Bounded(receiver) => receiver.size_hint(),
+        }
+    }
+}
+
+impl<Ev> Drop for EventStream<Ev> {
+    fn drop(&mut self) {
+        if let Some(list) = self.list.upgrade() {
+            if let Ok(mut list) = list.lock() {
+                list.subscribers
+                    .retain(|subscriber| subscriber.id != self.id);
+            }
+        }
+    }
+}
+
+/// The sending half of the buffer of a subscriber.
+enum Forward<Ev> {
+    Unbounded(UnboundedSender<Ev>),
+    Bounded(Sender<Ev>, Overflow),
+}
+
+struct Subscriber<Ev> {
+    id: usize,
+    forward: Forward<Ev>,
+    lagged: Arc<AtomicUsize>,
+}
+
+impl<Ev> Subscriber<Ev> {
+    /// Forwards `ev` and returns `false` if the subscriber is gone.
+    fn forward(&mut self, ev: Ev) -> bool {
+        match &mut self.forward {
+            Forward::Unbounded(sender) => sender.unbounded_send(ev).is_ok(),
+            Forward::Bounded(sender, overflow) => match sender.try_send(ev) {
+                Ok(()) => true,
+                Err(e) if e.is_full() => {
+                    self.lagged.fetch_add(1, Ordering::SeqCst);
+                    match overflow {
+                        Overflow::DropNewest => true,
+                        Overflow::Close => {
+                            sender.close_channel();
+                            false
+                        }
+                    }
+                }
+                Err(_) => false,
+            },
+        }
+    }
+}
+
+/// The subscribers of an async mediator.
+///
+/// A single listener is installed into the underlying [`BasicMediator`]
+/// on the first subscription. It forwards every dispatched event to all
+/// subscribers that are still alive.
+/// Subscribers are removed once their [`EventStream`] is dropped,
+/// or once a bounded one is closed by [`Overflow::Close`].
+/// The listener stays installed, but does nothing while there are no subscribers.
+pub(crate) struct Subscribers<Ev> {
+    inner: Arc<Mutex<SubscriberList<Ev>>>,
+}
+
+struct SubscriberList<Ev> {
+    subscribers: Vec<Subscriber<Ev>>,
+    next_id: usize,
+    installed: bool,
+}
+
+impl<Ev> Debug for SubscriberList<Ev> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubscriberList")
+            .field("count", &self.subscribers.len())
+            .finish()
+    }
+}
+
+impl<Ev> Subscribers<Ev> {
+    pub(crate) fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(SubscriberList {
+                subscribers: vec![],
+                next_id: 0,
+                installed: false,
+            })),
+        }
+    }
+}
+
+impl<Ev> Subscribers<Ev>
+where
+    Ev: Debug + Clone + Send + 'static,
+{
+    /// Subscribes with an unbounded buffer.
+    pub(crate) fn subscribe(&self, basic: &mut BasicMediator<Ev>) -> EventStream<Ev> {
+        let (sender, receiver) = unbounded();
+        self.add(
+            basic,
+            Forward::Unbounded(sender),
+            Buffer::Unbounded(receiver),
+        )
+    }
+
+    /// Subscribes with a buffer of `capacity` events.
+    pub(crate) fn subscribe_bounded(
+        &self,
+        basic: &mut BasicMediator<Ev>,
+        capacity: usize,
+        overflow: Overflow,
+    ) -> EventStream<Ev> {
+        // The channel holds one more event than its buffer for every sender.
+        let (sender, receiver) = channel(capacity.max(1) - 1);
+        self.add(
+            basic,
+            Forward::Bounded(sender, overflow),
+            Buffer::Bounded(receiver),
+        )
+    }
+
+    fn add(
+        &self,
+        basic: &mut BasicMediator<Ev>,
+        forward: Forward<Ev>,
+        receiver: Buffer<Ev>,
+    ) -> EventStream<Ev> {
+        let lagged = Arc::new(AtomicUsize::new(0));
+        let mut list = self.inner.lock().unwrap();
+        let id = list.next_id;
+        list.next_id += 1;
+        list.subscribers.push(Subscriber {
+            id,
+            forward,
+            lagged: lagged.clone(),
+        });
+
+        if !list.installed {
+            list.installed = true;
+            let inner = self.inner.clone();
+            basic.listener.push(Box::new(move |ev: Ev| {
+                inner
+                    .lock()
+                    .unwrap()
+                    .subscribers
+                    .retain_mut(|subscriber| subscriber.forward(ev.clone()));
+            }));
+        }
+
+        EventStream {
+            id,
+            receiver,
+            lagged,
+            list: Arc::downgrade(&self.inner),
+        }
+    }
+}
+
+impl<Ev> Debug for Subscribers<Ev> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let count = self
+            .inner
+            .lock()
+            .map(|list| list.subscribers.len())
+            .unwrap_or_default();
+        f.debug_struct("Subscribers")
+            .field("count", &count)
+            .finish()
+    }
+}
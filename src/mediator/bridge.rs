@@ -0,0 +1,152 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use crate::mediator::{
+    hierarchy::{Envelope, Hop, MediatorLink},
+    inbox::Inbox,
+};
+
+type Forward<Ev> = Box<dyn Fn(&Envelope<Ev>) + Send>;
+
+/// The bridges of a mediator, which forward its events to other mediators,
+/// possibly with a different event type and of a different flavor,
+/// e.g. from a [`crate::synchronous::basic::BasicMediator`]
+/// to a [`crate::asynchronous::basic::BasicAsyncMediator`].
+///
+/// Events are forwarded when the mediator receives them on `next()` or `recv()`
+/// by sending them to the channel of the target, which never blocks
+/// and wakes the target if it waits in `recv()`.
+/// Forwarded events remember every mediator they passed through
+/// and are dropped when they come back to one of them,
+/// so bridges, projections and linked hierarchies never bounce events back and forth.
+///
+/// Projections are bridges registered by the derived mediator rather than by the source.
+/// They forward every event the source receives and are unregistered
+/// once the derived mediator is dropped.
+pub(crate) struct Bridges<Ev> {
+    forwards: Vec<Forward<Ev>>,
+    pub(crate) projections: Arc<Mutex<Projections<Ev>>>,
+    sources: Vec<Source>,
+}
+
+/// The projections registered at a mediator.
+pub(crate) struct Projections<Ev> {
+    next_id: u64,
+    forwards: BTreeMap<u64, Forward<Ev>>,
+}
+
+/// The registration of a projection at its source, which unregisters it on drop.
+struct Source {
+    unregister: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl Drop for Source {
+    fn drop(&mut self) {
+        if let Some(unregister) = self.unregister.take() {
+            unregister();
+        }
+    }
+}
+
+impl<Ev> Bridges<Ev> {
+    pub(crate) fn new() -> Self {
+        Self {
+            forwards: vec![],
+            projections: Arc::new(Mutex::new(Projections {
+                next_id: 0,
+                forwards: BTreeMap::new(),
+            })),
+            sources: vec![],
+        }
+    }
+
+    /// Forwards events converted by `map` to the mediator of `target`.
+    /// Events mapped to `None` are not forwarded.
+    pub(crate) fn add<Ev2, F>(&mut self, target: MediatorLink<Ev2>, map: F)
+    where
+        Ev: Clone,
+        Ev2: Send + 'static,
+        F: Fn(Ev) -> Option<Ev2> + Send + 'static,
+    {
+        self.forwards.push(forward(target.sender, map));
+    }
+
+    /// Registers a projection of the mediator of `source` at it,
+    /// which forwards its events converted by `map` to the mediator receiving from `sender`.
+    pub(crate) fn project_from<Src, F>(
+        &mut self,
+        source: MediatorLink<Src>,
+        sender: &Inbox<Ev>,
+        map: F,
+    ) where
+        Src: Clone + 'static,
+        Ev: Send + 'static,
+        F: Fn(Src) -> Option<Ev> + Send + 'static,
+    {
+        let id = {
+            let mut projections = source.projections.lock().unwrap();
+            let id = projections.next_id;
+            projections.next_id += 1;
+            projections
+                .forwards
+                .insert(id, forward(sender.clone(), map));
+            id
+        };
+        let projections = Arc::downgrade(&source.projections);
+        self.sources.push(Source {
+            unregister: Some(Box::new(move || {
+                if let Some(projections) = projections.upgrade() {
+                    projections.lock().unwrap().forwards.remove(&id);
+                }
+            })),
+        });
+    }
+
+    /// Forwards `envelope` over all bridges and all projections.
+    pub(crate) fn forward(&self, envelope: &Envelope<Ev>) {
+        for forward in &self.forwards {
+            forward(envelope);
+        }
+        for forward in self.projections.lock().unwrap().forwards.values() {
+            forward(envelope);
+        }
+    }
+}
+
+/// Returns a [`Forward`] that sends events converted by `map` to `sender`.
+fn forward<Ev, Ev2, F>(sender: Inbox<Ev2>, map: F) -> Forward<Ev>
+where
+    Ev: Clone,
+    Ev2: Send + 'static,
+    F: Fn(Ev) -> Option<Ev2> + Send + 'static,
+{
+    Box::new(move |envelope| {
+        if let Some(ev) = map(envelope.ev.clone()) {
+            sender
+                .send(Envelope {
+                    ev,
+                    topic: None,
+                    priority: None,
+                    hop: Hop::Bridged,
+                    visited: envelope.visited.clone(),
+                })
+                .ok();
+        }
+    })
+}
+
+impl<Ev> Debug for Bridges<Ev> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Bridges")
+            .field("bridges", &self.forwards.len())
+            .field(
+                "projections",
+                &self.projections.lock().unwrap().forwards.len(),
+            )
+            .field("sources", &self.sources.len())
+            .finish()
+    }
+}
@@ -0,0 +1,279 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt::Debug,
+    hash::Hash,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+type Filter<Ev> = Box<dyn Fn(&Ev) -> bool + Send>;
+
+/// A [`QueryCache`] declares how the responses to a query type are cached.
+///
+/// Caches are added per query type on the builders, e.g. via
+/// [`crate::synchronous::basic::BasicBuilder::cache_queries()`],
+/// and consulted by `query()`.
+/// Responses are keyed by the query, which has to be `Hash + Eq`,
+/// and are kept for `ttl` on the [`Clock`](crate::clock::Clock) of the mediator.
+/// Once `capacity` responses are cached, the oldest one is evicted.
+/// All responses are dropped as soon as an event is published
+/// that matches one of the filters added via [`QueryCache::invalidated_by()`].
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mediator_sys::cache::QueryCache;
+/// use std::time::Duration;
+///
+/// #[derive(Debug, Clone)]
+/// enum MyEvent {
+///     Deposited(u32),
+///     LoggedIn,
+/// }
+///
+/// let cache = QueryCache::<MyEvent>::new(Duration::from_secs(60), 100)
+///     .invalidated_by(|ev| matches!(ev, MyEvent::Deposited(_)));
+/// ```
+pub struct QueryCache<Ev> {
+    ttl: Duration,
+    capacity: usize,
+    invalidated_by: Vec<Filter<Ev>>,
+}
+
+impl<Ev> QueryCache<Ev> {
+    /// Creates a [`QueryCache`] that keeps at most `capacity` responses for `ttl` each.
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            invalidated_by: vec![],
+        }
+    }
+
+    /// Drops all cached responses whenever an event matching `f` is published.
+    /// Can be called multiple times to invalidate on several event types.
+    pub fn invalidated_by<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Ev) -> bool + Send + 'static,
+    {
+        self.invalidated_by.push(Box::new(f));
+        self
+    }
+}
+
+impl<Ev> Debug for QueryCache<Ev> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QueryCache")
+            .field("ttl", &self.ttl)
+            .field("capacity", &self.capacity)
+            .field("invalidated_by", &self.invalidated_by.len())
+            .finish()
+    }
+}
+
+/// The cached responses `R` to queries `Q`, with the point in time they expire.
+struct Responses<Q, R> {
+    ttl: Duration,
+    capacity: usize,
+    entries: HashMap<Q, (R, Instant)>,
+}
+
+/// The [`Responses`] of a query type, with the query and response types erased.
+trait Entries: Send {
+    /// Returns a clone of the unexpired response to `query`, if any.
+    fn get(&mut self, query: &dyn Any, now: Instant) -> Option<Box<dyn Any>>;
+
+    /// Returns an owned copy of `query` to store its response under.
+    fn key(&self, query: &dyn Any) -> Option<Box<dyn Any + Send>>;
+
+    fn insert(&mut self, key: Box<dyn Any + Send>, response: &dyn Any, now: Instant);
+
+    fn clear(&mut self);
+}
+
+impl<Q, R> Entries for Responses<Q, R>
+where
+    Q: Hash + Eq + Clone + Send + 'static,
+    R: Clone + Send + 'static,
+{
+    fn get(&mut self, query: &dyn Any, now: Instant) -> Option<Box<dyn Any>> {
+        let query = query.downcast_ref::<Q>()?;
+        match self.entries.get(query) {
+            Some((response, expires)) if now < *expires => Some(Box::new(response.clone())),
+            Some(_) => {
+                self.entries.remove(query);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn key(&self, query: &dyn Any) -> Option<Box<dyn Any + Send>> {
+        let query = query.downcast_ref::<Q>()?;
+        Some(Box::new(query.clone()))
+    }
+
+    fn insert(&mut self, key: Box<dyn Any + Send>, response: &dyn Any, now: Instant) {
+        let (key, response, expires) = match (
+            key.downcast::<Q>(),
+            response.downcast_ref::<R>(),
+            now.checked_add(self.ttl),
+        ) {
+            (Ok(key), Some(response), Some(expires)) if self.capacity > 0 => {
+                (key, response, expires)
+            }
+            _ => return,
+        };
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            self.entries.retain(|_, (_, expires)| now < *expires);
+        }
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            let oldest = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, expires))| *expires)
+                .map(|(query, _)| query.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.entries.insert(*key, (response.clone(), expires));
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// The cache of a query type. The generation is increased on every invalidation,
+/// so responses computed before it are not stored afterwards.
+struct Cache<Ev> {
+    invalidated_by: Vec<Filter<Ev>>,
+    state: Mutex<(u64, Box<dyn Entries>)>,
+}
+
+/// The result of looking up a query in the [`QueryCaches`].
+pub(crate) enum Lookup<R> {
+    /// The query type is not cached.
+    Uncached,
+    /// The cached response.
+    Hit(R),
+    /// No response is cached, the response is to be stored via [`QueryCaches::store()`].
+    Miss(Miss),
+}
+
+/// A query whose response is missing from the [`QueryCaches`].
+pub(crate) struct Miss {
+    query: TypeId,
+    key: Box<dyn Any + Send>,
+    generation: u64,
+}
+
+/// The [`QueryCache`]s of a mediator, per query type.
+pub(crate) struct QueryCaches<Ev> {
+    caches: HashMap<TypeId, Cache<Ev>>,
+}
+
+impl<Ev> QueryCaches<Ev> {
+    pub(crate) fn new() -> Self {
+        Self {
+            caches: HashMap::new(),
+        }
+    }
+
+    /// Caches the responses `R` to queries of type `Q` as declared by `cache`.
+    pub(crate) fn insert<Q, R>(&mut self, cache: QueryCache<Ev>)
+    where
+        Q: Hash + Eq + Clone + Send + 'static,
+        R: Clone + Send + 'static,
+    {
+        let responses = Responses::<Q, R> {
+            ttl: cache.ttl,
+            capacity: cache.capacity,
+            entries: HashMap::new(),
+        };
+        self.caches.insert(
+            TypeId::of::<Q>(),
+            Cache {
+                invalidated_by: cache.invalidated_by,
+                state: Mutex::new((0, Box::new(responses))),
+            },
+        );
+    }
+
+    /// Looks up the response to `query` at `now`.
+    pub(crate) fn lookup<Q, R>(&self, query: &Q, now: Instant) -> Lookup<R>
+    where
+        Q: 'static,
+        R: 'static,
+    {
+        let cache = match self.caches.get(&TypeId::of::<Q>()) {
+            Some(cache) => cache,
+            None => return Lookup::Uncached,
+        };
+        let mut state = cache.state.lock().unwrap();
+        let (generation, entries) = &mut *state;
+        if let Some(response) = entries.get(query, now) {
+            if let Ok(response) = response.downcast::<R>() {
+                return Lookup::Hit(*response);
+            }
+        }
+        match entries.key(query) {
+            Some(key) => Lookup::Miss(Miss {
+                query: TypeId::of::<Q>(),
+                key,
+                generation: *generation,
+            }),
+            None => Lookup::Uncached,
+        }
+    }
+
+    /// Stores the `response` to a missed query at `now`,
+    /// unless the cache was invalidated since it was looked up.
+    pub(crate) fn store<R>(&self, miss: Miss, response: &R, now: Instant)
+    where
+        R: 'static,
+    {
+        if let Some(cache) = self.caches.get(&miss.query) {
+            let mut state = cache.state.lock().unwrap();
+            let (generation, entries) = &mut *state;
+            if *generation == miss.generation {
+                entries.insert(miss.key, response, now);
+            }
+        }
+    }
+
+    /// Drops the responses of every cache that is invalidated by `ev`.
+    pub(crate) fn invalidate(&self, ev: &Ev) {
+        for cache in self.caches.values() {
+            if cache.invalidated_by.iter().any(|f| f(ev)) {
+                Self::clear_cache(cache);
+            }
+        }
+    }
+
+    /// Drops the responses of every cache.
+    #[cfg(feature = "async")]
+    pub(crate) fn clear(&self) {
+        for cache in self.caches.values() {
+            Self::clear_cache(cache);
+        }
+    }
+
+    fn clear_cache(cache: &Cache<Ev>) {
+        let mut state = cache.state.lock().unwrap();
+        state.0 += 1;
+        state.1.clear();
+    }
+}
+
+impl<Ev> Debug for QueryCaches<Ev> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QueryCaches")
+            .field("count", &self.caches.len())
+            .finish()
+    }
+}
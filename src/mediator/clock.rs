@@ -0,0 +1,142 @@
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "async")]
+use futures::future::BoxFuture;
+
+/// A [`Clock`] is the source of time for mediators.
+///
+/// It is used to wait between retries and to schedule delayed work.
+/// The [`SystemClock`] is used by default, while the [`MockClock`]
+/// allows for deterministic tests.
+pub trait Clock: Debug + Send + Sync + 'static {
+    /// Returns the current point in time.
+    fn now(&self) -> Instant;
+
+    /// Blocks the current thread for `duration`.
+    fn sleep(&self, duration: Duration);
+
+    /// Waits asynchronously for `duration`.
+    #[cfg(feature = "async")]
+    fn sleep_async(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        Box::pin(async_std::task::sleep(duration))
+    }
+}
+
+impl<C> Clock for Arc<C>
+where
+    C: Clock + ?Sized,
+{
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        (**self).sleep(duration)
+    }
+
+    #[cfg(feature = "async")]
+    fn sleep_async(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        (**self).sleep_async(duration)
+    }
+}
+
+/// A [`Clock`] based on the system time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration)
+    }
+}
+
+/// A deterministic [`Clock`] for tests.
+///
+/// Time only moves forward through [`MockClock::advance()`] or by sleeping,
+/// which returns immediately and advances the clock instead.
+/// Every sleep is recorded and can be inspected via [`MockClock::sleeps()`].
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mediator_sys::clock::{Clock, MockClock};
+/// use std::time::Duration;
+///
+/// let clock = MockClock::new();
+/// let start = clock.now();
+///
+/// clock.sleep(Duration::from_secs(5));
+/// clock.advance(Duration::from_secs(1));
+///
+/// assert_eq!(clock.now() - start, Duration::from_secs(6));
+/// assert_eq!(clock.sleeps(), vec![Duration::from_secs(5)]);
+/// ```
+#[derive(Debug)]
+pub struct MockClock {
+    start: Instant,
+    state: Mutex<MockState>,
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    elapsed: Duration,
+    sleeps: Vec<Duration>,
+}
+
+impl MockClock {
+    /// Creates a [`MockClock`] starting at the current point in time.
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            state: Mutex::new(MockState::default()),
+        }
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        self.state.lock().unwrap().elapsed += duration;
+    }
+
+    /// Returns all durations slept so far, in order.
+    pub fn sleeps(&self) -> Vec<Duration> {
+        self.state.lock().unwrap().sleeps.clone()
+    }
+
+    fn record(&self, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.elapsed += duration;
+        state.sleeps.push(duration);
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + self.state.lock().unwrap().elapsed
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.record(duration)
+    }
+
+    #[cfg(feature = "async")]
+    fn sleep_async(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        self.record(duration);
+        Box::pin(futures::future::ready(()))
+    }
+}
@@ -0,0 +1,314 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    fmt::Debug,
+    io,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use crate::mediator::storage::{Record, Storage};
+
+type Encoder = Box<dyn Fn(&dyn Any, &dyn Any) -> Option<(String, String)> + Send + Sync>;
+
+/// An event or request that could not be delivered.
+///
+/// Dead letters are collected by the dead-letter queue of a mediator,
+/// which is enabled via `dead_letter_queue()` on the builders.
+/// They can be inspected with `dead_letters()`, re-dispatched
+/// with `redispatch_events()` or `redispatch_requests()` and
+/// removed with `purge_dead_letters()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeadLetter<Ev> {
+    /// The id of the dead letter, unique within its queue.
+    pub id: u64,
+    /// The reason the letter could not be delivered.
+    pub reason: DeadLetterReason,
+    /// The undeliverable event or request.
+    pub letter: Letter<Ev>,
+}
+
+/// The reason a [`DeadLetter`] could not be delivered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeadLetterReason {
+    /// The event was dispatched while the mediator had no listeners.
+    NoListeners,
+    /// A listener panicked while handling the event.
+    ListenerPanicked,
+    /// The request failed with the contained error after all retries.
+    RequestFailed(String),
+}
+
+/// The content of a [`DeadLetter`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Letter<Ev> {
+    /// An undeliverable event.
+    Event(Ev),
+    /// A failed request, encoded via [`Record`].
+    Request {
+        /// The name of the request type.
+        type_name: String,
+        /// The encoded request.
+        payload: String,
+    },
+}
+
+impl<Ev> Record for DeadLetter<Ev>
+where
+    Ev: Record,
+{
+    fn encode(&self) -> String {
+        let id = self.id.to_string();
+        let (reason, error) = match &self.reason {
+            DeadLetterReason::NoListeners => ("no-listeners", ""),
+            DeadLetterReason::ListenerPanicked => ("listener-panicked", ""),
+            DeadLetterReason::RequestFailed(error) => ("request-failed", error.as_str()),
+        };
+        match &self.letter {
+            Letter::Event(ev) => Self::join(&[&id, reason, error, "event", &ev.encode()]),
+            Letter::Request { type_name, payload } => {
+                Self::join(&[&id, reason, error, "request", type_name, payload])
+            }
+        }
+    }
+
+    fn decode(line: &str) -> Option<Self> {
+        let fields = Self::split(line);
+        let (id, reason, error, letter) = match fields.as_slice() {
+            [id, reason, error, letter @ ..] => (id, reason, error, letter),
+            _ => return None,
+        };
+
+        let reason = match reason.as_str() {
+            "no-listeners" => DeadLetterReason::NoListeners,
+            "listener-panicked" => DeadLetterReason::ListenerPanicked,
+            "request-failed" => DeadLetterReason::RequestFailed(error.clone()),
+            _ => return None,
+        };
+        let letter = match letter {
+            [kind, ev] if kind == "event" => Letter::Event(Ev::decode(ev)?),
+            [kind, type_name, payload] if kind == "request" => Letter::Request {
+                type_name: type_name.clone(),
+                payload: payload.clone(),
+            },
+            _ => return None,
+        };
+
+        Some(DeadLetter {
+            id: id.parse().ok()?,
+            reason,
+            letter,
+        })
+    }
+}
+
+/// The dead-letter queue of a mediator.
+///
+/// Without a [`Storage`], which is set by the builders,
+/// nothing is collected.
+/// Letters that could not be stored are kept in memory and stored
+/// before the queue is accessed again, which fails as long as the storage does.
+pub(crate) struct DeadLetters<Ev> {
+    storage: Option<Box<dyn Storage<DeadLetter<Ev>>>>,
+    encoders: HashMap<TypeId, Encoder>,
+    next_id: AtomicU64,
+    unsaved: Mutex<Vec<DeadLetter<Ev>>>,
+}
+
+impl<Ev> DeadLetters<Ev> {
+    pub(crate) fn new() -> Self {
+        Self {
+            storage: None,
+            encoders: HashMap::new(),
+            next_id: AtomicU64::new(0),
+            unsaved: Mutex::new(vec![]),
+        }
+    }
+
+    /// Sets the `storage` and continues numbering after the letters already stored.
+    pub(crate) fn set_storage<S>(&mut self, storage: S)
+    where
+        S: Storage<DeadLetter<Ev>> + 'static,
+    {
+        let next_id = storage
+            .load()
+            .ok()
+            .and_then(|letters| letters.iter().map(|letter| letter.id + 1).max())
+            .unwrap_or_default();
+        self.next_id = AtomicU64::new(next_id);
+        self.storage = Some(Box::new(storage));
+    }
+
+    /// Collects failed requests of type `Req`, encoded via [`Record`].
+    pub(crate) fn add_request<Req, E>(&mut self)
+    where
+        Req: Record + 'static,
+        E: Debug + 'static,
+    {
+        self.encoders.insert(
+            TypeId::of::<Req>(),
+            Box::new(|req, err| {
+                let req = req.downcast_ref::<Req>()?;
+                let err = err.downcast_ref::<E>()?;
+                Some((req.encode(), format!("{:?}", err)))
+            }),
+        );
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.storage.is_some()
+    }
+
+    /// Stores an undeliverable event.
+    pub(crate) fn event(&self, ev: Ev, reason: DeadLetterReason) {
+        self.append(reason, Letter::Event(ev));
+    }
+
+    /// Stores a request that failed with `err`,
+    /// if requests of type `Req` are collected.
+    pub(crate) fn request<Req, E>(&self, req: &Req, err: &E)
+    where
+        Req: 'static,
+        E: 'static,
+    {
+        if !self.is_enabled() {
+            return;
+        }
+        let encoded = self
+            .encoders
+            .get(&TypeId::of::<Req>())
+            .and_then(|encode| encode(req, err));
+        if let Some((payload, error)) = encoded {
+            self.append(
+                DeadLetterReason::RequestFailed(error),
+                Letter::Request {
+                    type_name: type_name::<Req>().to_owned(),
+                    payload,
+                },
+            );
+        }
+    }
+
+    pub(crate) fn load(&self) -> io::Result<Vec<DeadLetter<Ev>>> {
+        match &self.storage {
+            Some(storage) => {
+                let _unsaved = self.flush(storage.as_ref())?;
+                storage.load()
+            }
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Removes all dead letters and returns how many were removed.
+    pub(crate) fn purge(&self) -> io::Result<usize> {
+        match &self.storage {
+            Some(storage) => {
+                let _unsaved = self.flush(storage.as_ref())?;
+                let count = storage.load()?.len();
+                storage.replace(vec![])?;
+                Ok(count)
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Removes and returns all dead events.
+    pub(crate) fn take_events(&self) -> io::Result<Vec<Ev>> {
+        self.take(|letter| match letter {
+            Letter::Event(ev) => Ok(ev),
+            letter => Err(letter),
+        })
+    }
+
+    /// Removes and returns all failed requests of type `Req`.
+    /// Requests that cannot be decoded are kept.
+    pub(crate) fn take_requests<Req>(&self) -> io::Result<Vec<Req>>
+    where
+        Req: Record,
+    {
+        self.take(|letter| match letter {
+            Letter::Request {
+                type_name: name,
+                payload,
+            } if name == type_name::<Req>() => match Req::decode(&payload) {
+                Some(req) => Ok(req),
+                None => Err(Letter::Request {
+                    type_name: name,
+                    payload,
+                }),
+            },
+            letter => Err(letter),
+        })
+    }
+
+    fn take<T, F>(&self, mut f: F) -> io::Result<Vec<T>>
+    where
+        F: FnMut(Letter<Ev>) -> Result<T, Letter<Ev>>,
+    {
+        let storage = match &self.storage {
+            Some(storage) => storage,
+            None => return Ok(vec![]),
+        };
+
+        // Letters appended in the meantime would be lost by `replace()`.
+        let _unsaved = self.flush(storage.as_ref())?;
+        let mut taken = vec![];
+        let mut kept = vec![];
+        for DeadLetter { id, reason, letter } in storage.load()? {
+            match f(letter) {
+                Ok(item) => taken.push(item),
+                Err(letter) => kept.push(DeadLetter { id, reason, letter }),
+            }
+        }
+        if !taken.is_empty() {
+            storage.replace(kept)?;
+        }
+        Ok(taken)
+    }
+
+    /// Stores the letter, or keeps it in memory if the storage fails.
+    /// The error is returned once the queue is accessed again and the storage still fails.
+    fn append(&self, reason: DeadLetterReason, letter: Letter<Ev>) {
+        if let Some(storage) = &self.storage {
+            let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+            let mut unsaved = self.unsaved.lock().unwrap();
+            unsaved.push(DeadLetter { id, reason, letter });
+            Self::store(storage.as_ref(), &mut unsaved).ok();
+        }
+    }
+
+    /// Stores the letters kept in memory and returns the locked, now empty, list.
+    ///
+    /// Holding the lock keeps other letters from being appended
+    /// while the storage is read and replaced.
+    fn flush(
+        &self,
+        storage: &dyn Storage<DeadLetter<Ev>>,
+    ) -> io::Result<std::sync::MutexGuard<'_, Vec<DeadLetter<Ev>>>> {
+        let mut unsaved = self.unsaved.lock().unwrap();
+        Self::store(storage, &mut unsaved)?;
+        Ok(unsaved)
+    }
+
+    fn store(
+        storage: &dyn Storage<DeadLetter<Ev>>,
+        unsaved: &mut Vec<DeadLetter<Ev>>,
+    ) -> io::Result<()> {
+        while let Some(letter) = unsaved.first() {
+            storage.append(letter)?;
+            unsaved.remove(0);
+        }
+        Ok(())
+    }
+}
+
+impl<Ev> Debug for DeadLetters<Ev> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeadLetters")
+            .field("storage", &self.storage)
+            .field("requests", &self.encoders.len())
+            .finish()
+    }
+}
@@ -0,0 +1,310 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, Weak,
+    },
+};
+
+use crate::mediator::{
+    bridge::Projections, inbox::Inbox, priority::Priority,
+    synchronous::basic::SyncMediatorInternalHierarchy,
+};
+
+type Filter<Ev> = Box<dyn Fn(&Ev) -> bool + Send>;
+type Child<Ev> = (Inbox<Ev>, Option<Filter<Ev>>);
+
+/// The id of the next mediator, which tells mediators apart across event types.
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// The rules by which events propagate between a child mediator and its parent.
+///
+/// Events published on the child bubble up to the parent if they pass the `up` filter,
+/// events published on the parent flow down to the child if they pass the `down` filter.
+/// Events only ever travel in one direction: an event that bubbled up
+/// continues to bubble up to the grandparent, but never flows back down,
+/// and an event that flowed down never bubbles back up.
+/// This way, events cannot loop between linked mediators.
+/// A mediator also drops events that come back to it, e.g. over a bridge.
+///
+/// A child is linked to its parent on its builder, e.g. via
+/// [`crate::synchronous::basic::BasicBuilder::parent()`].
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mediator_sys::hierarchy::Propagation;
+///
+/// #[derive(Debug, Clone)]
+/// enum MyEvent {
+///     PluginLoaded,
+///     ConfigChanged,
+///     Shutdown,
+/// }
+///
+/// // Bubbles every event up, lets only shutdowns flow down.
+/// let propagation = Propagation::<MyEvent>::both()
+///     .down_if(|ev| matches!(ev, MyEvent::Shutdown));
+/// ```
+pub struct Propagation<Ev> {
+    up: Option<Filter<Ev>>,
+    down: Option<Filter<Ev>>,
+}
+
+impl<Ev> Propagation<Ev> {
+    /// Events propagate in neither direction.
+    pub fn none() -> Self {
+        Self {
+            up: None,
+            down: None,
+        }
+    }
+
+    /// Events bubble up from the child to the parent.
+    pub fn up() -> Self {
+        Self::none().up_if(|_| true)
+    }
+
+    /// Events flow down from the parent to the child.
+    pub fn down() -> Self {
+        Self::none().down_if(|_| true)
+    }
+
+    /// Events propagate in both directions.
+    pub fn both() -> Self {
+        Self::up().down_if(|_| true)
+    }
+
+    /// Events bubble up from the child to the parent if they pass `f`.
+    pub fn up_if<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Ev) -> bool + Send + 'static,
+    {
+        self.up = Some(Box::new(f));
+        self
+    }
+
+    /// Events flow down from the parent to the child if they pass `f`.
+    pub fn down_if<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Ev) -> bool + Send + 'static,
+    {
+        self.down = Some(Box::new(f));
+        self
+    }
+}
+
+impl<Ev> Debug for Propagation<Ev> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Propagation")
+            .field("up", &self.up.is_some())
+            .field("down", &self.down.is_some())
+            .finish()
+    }
+}
+
+/// A handle of a mediator to link child mediators to it.
+///
+/// It is returned by `link()` on the mediators
+/// and passed to `parent()` on the builders of the children.
+pub struct MediatorLink<Ev> {
+    pub(crate) sender: Inbox<Ev>,
+    pub(crate) children: Arc<Mutex<Children<Ev>>>,
+    pub(crate) projections: Arc<Mutex<Projections<Ev>>>,
+}
+
+impl<Ev> SyncMediatorInternalHierarchy<Ev> for MediatorLink<Ev>
+where
+    Ev: Debug,
+{
+    fn link(&self) -> MediatorLink<Ev> {
+        self.clone()
+    }
+
+    fn child_count(&self) -> usize {
+        self.children.lock().unwrap().links.len()
+    }
+}
+
+impl<Ev> Clone for MediatorLink<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            children: self.children.clone(),
+            projections: self.projections.clone(),
+        }
+    }
+}
+
+impl<Ev> Debug for MediatorLink<Ev> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MediatorLink")
+            .field("children", &self.children.lock().unwrap().links.len())
+            .finish()
+    }
+}
+
+/// An event in the channel of a mediator along with its routing information.
+pub(crate) struct Envelope<Ev> {
+    pub(crate) ev: Ev,
+    pub(crate) topic: Option<String>,
+    pub(crate) priority: Option<Priority>,
+    pub(crate) hop: Hop,
+    /// The ids of the mediators that received the event so far.
+    pub(crate) visited: Vec<u64>,
+}
+
+impl<Ev> Envelope<Ev> {
+    pub(crate) fn local(ev: Ev, topic: Option<String>, priority: Option<Priority>) -> Self {
+        Self {
+            ev,
+            topic,
+            priority,
+            hop: Hop::Local,
+            visited: vec![],
+        }
+    }
+
+    /// Records that the mediator `id` received the envelope,
+    /// returns `false` if it did so before.
+    pub(crate) fn visit(&mut self, id: u64) -> bool {
+        if self.visited.contains(&id) {
+            return false;
+        }
+        self.visited.push(id);
+        true
+    }
+}
+
+/// Where an [`Envelope`] came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Hop {
+    /// Published on the mediator itself.
+    Local,
+    /// Bubbled up from a child.
+    Up,
+    /// Flowed down from the parent.
+    Down,
+    /// Forwarded by a bridge from another mediator.
+    Bridged,
+}
+
+pub(crate) struct Children<Ev> {
+    next_id: u64,
+    links: BTreeMap<u64, Child<Ev>>,
+}
+
+/// The link of a child mediator to its parent, which unlinks the child on drop.
+struct Parent<Ev> {
+    id: u64,
+    sender: Inbox<Ev>,
+    up: Option<Filter<Ev>>,
+    siblings: Weak<Mutex<Children<Ev>>>,
+}
+
+impl<Ev> Drop for Parent<Ev> {
+    fn drop(&mut self) {
+        if let Some(siblings) = self.siblings.upgrade() {
+            siblings.lock().unwrap().links.remove(&self.id);
+        }
+    }
+}
+
+/// The parent and the children of a mediator.
+pub(crate) struct Hierarchy<Ev> {
+    /// The id of the mediator, unique within the process.
+    pub(crate) id: u64,
+    parent: Option<Parent<Ev>>,
+    pub(crate) children: Arc<Mutex<Children<Ev>>>,
+}
+
+impl<Ev> Hierarchy<Ev> {
+    pub(crate) fn new() -> Self {
+        Self {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            parent: None,
+            children: Arc::new(Mutex::new(Children {
+                next_id: 0,
+                links: BTreeMap::new(),
+            })),
+        }
+    }
+
+    /// Links the mediator that receives from `sender` as a child to `parent`,
+    /// replacing a previous parent.
+    pub(crate) fn set_parent(
+        &mut self,
+        parent: MediatorLink<Ev>,
+        sender: &Inbox<Ev>,
+        propagation: Propagation<Ev>,
+    ) {
+        let id = {
+            let mut siblings = parent.children.lock().unwrap();
+            let id = siblings.next_id;
+            siblings.next_id += 1;
+            siblings
+                .links
+                .insert(id, (sender.clone(), propagation.down));
+            id
+        };
+        self.parent = Some(Parent {
+            id,
+            sender: parent.sender,
+            up: propagation.up,
+            siblings: Arc::downgrade(&parent.children),
+        });
+    }
+
+    pub(crate) fn child_count(&self) -> usize {
+        self.children.lock().unwrap().links.len()
+    }
+}
+
+impl<Ev> Hierarchy<Ev>
+where
+    Ev: Clone,
+{
+    /// Forwards `envelope` to the parent and the children according to their
+    /// [`Propagation`], without sending it back in the direction it came from.
+    pub(crate) fn propagate(&self, envelope: &Envelope<Ev>) {
+        let forward = |hop| Envelope {
+            ev: envelope.ev.clone(),
+            topic: envelope.topic.clone(),
+            priority: envelope.priority,
+            hop,
+            visited: envelope.visited.clone(),
+        };
+
+        if envelope.hop != Hop::Down {
+            if let Some(Parent {
+                sender,
+                up: Some(up),
+                ..
+            }) = &self.parent
+            {
+                if up(&envelope.ev) {
+                    sender.send(forward(Hop::Up)).ok();
+                }
+            }
+        }
+        if envelope.hop != Hop::Up {
+            for (sender, down) in self.children.lock().unwrap().links.values() {
+                if matches!(down, Some(down) if down(&envelope.ev)) {
+                    sender.send(forward(Hop::Down)).ok();
+                }
+            }
+        }
+    }
+}
+
+impl<Ev> Debug for Hierarchy<Ev> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Hierarchy")
+            .field("linked", &self.parent.is_some())
+            .field("children", &self.child_count())
+            .finish()
+    }
+}
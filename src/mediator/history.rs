@@ -0,0 +1,301 @@
+use std::{any::type_name, collections::VecDeque, fmt::Debug, sync::Mutex};
+
+#[cfg(feature = "async")]
+use futures::future::BoxFuture;
+
+/// A request that can be undone by sending its inverse request.
+///
+/// Undoable requests are sent via `send_undoable()` on the mediators,
+/// which records them in the command history if it was enabled
+/// via `command_history()` on the builders.
+/// Undoing a request sends the request returned by [`Undoable::inverse()`],
+/// redoing it sends a clone of the request again.
+/// Both must be handled by the mediator.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mediator_sys::history::Undoable;
+///
+/// #[derive(Debug, Clone)]
+/// enum Edit {
+///     Insert(usize, String),
+///     Delete(usize, usize),
+/// }
+///
+/// impl Undoable for Edit {
+///     type Inverse = Edit;
+///
+///     fn inverse(&self) -> Edit {
+///         match self {
+///             Edit::Insert(at, text) => Edit::Delete(*at, text.len()),
+///             Edit::Delete(at, len) => Edit::Insert(*at, " ".repeat(*len)),
+///         }
+///     }
+///
+///     fn label(&self) -> String {
+///         String::from("edit")
+///     }
+/// }
+///
+/// assert!(matches!(Edit::Insert(0, String::from("abc")).inverse(), Edit::Delete(0, 3)));
+/// ```
+pub trait Undoable: Clone + Send + Sync + 'static {
+    /// The request that undoes this request.
+    type Inverse: Send + 'static;
+
+    /// Returns the request that undoes this request.
+    fn inverse(&self) -> Self::Inverse;
+
+    /// Returns the label of this request in the [`CommandHistory`],
+    /// the name of its type by default.
+    fn label(&self) -> String {
+        type_name::<Self>().to_owned()
+    }
+}
+
+/// A step of the [`CommandHistory`]: a single request or a group of requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryStep {
+    /// The label of the request or of the group.
+    pub label: String,
+    /// The labels of the requests of the step, in the order they were sent.
+    pub requests: Vec<String>,
+}
+
+/// A snapshot of the command history of a mediator, as returned by `history()`.
+///
+/// Both stacks end with the step that is undone or redone next.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandHistory {
+    /// The steps that can be undone.
+    pub undo: Vec<HistoryStep>,
+    /// The steps that can be redone.
+    pub redo: Vec<HistoryStep>,
+}
+
+/// A recorded request as a pair of actions `A` that send its inverse and itself again.
+pub(crate) struct Command<A> {
+    label: String,
+    pub(crate) undo: A,
+    pub(crate) redo: A,
+}
+
+/// A single request or a group of requests, which are undone and redone together.
+pub(crate) struct Step<A> {
+    label: String,
+    pub(crate) commands: Vec<Command<A>>,
+}
+
+impl<A> Step<A> {
+    fn describe(&self) -> HistoryStep {
+        HistoryStep {
+            label: self.label.clone(),
+            requests: self.commands.iter().map(|c| c.label.clone()).collect(),
+        }
+    }
+}
+
+/// The undo and redo stacks of a mediator.
+pub(crate) struct History<A> {
+    capacity: Option<usize>,
+    undo: VecDeque<Step<A>>,
+    redo: Vec<Step<A>>,
+    group: Option<(usize, Step<A>)>,
+    /// The number of steps recorded so far, which tells a [`Replay`]
+    /// whether a step was recorded while it was running.
+    recorded: u64,
+}
+
+impl<A> History<A> {
+    /// Creates a disabled history, which records nothing.
+    pub(crate) fn new() -> Self {
+        Self {
+            capacity: None,
+            undo: VecDeque::new(),
+            redo: vec![],
+            group: None,
+            recorded: 0,
+        }
+    }
+
+    /// Enables the history, keeping at most `capacity` steps to undo.
+    pub(crate) fn enable(&mut self, capacity: usize) {
+        self.capacity = Some(capacity.max(1));
+    }
+
+    /// Records the request `label` to the open group or as a new step,
+    /// unless the history is disabled.
+    ///
+    /// Recording a new step discards the steps to redo.
+    pub(crate) fn record(&mut self, label: String, undo: A, redo: A) {
+        if self.capacity.is_none() {
+            return;
+        }
+        let command = Command {
+            label: label.clone(),
+            undo,
+            redo,
+        };
+        match &mut self.group {
+            Some((_, step)) => step.commands.push(command),
+            None => self.push(Step {
+                label,
+                commands: vec![command],
+            }),
+        }
+    }
+
+    fn push(&mut self, step: Step<A>) {
+        self.recorded += 1;
+        self.redo.clear();
+        self.undo.push_back(step);
+        if let Some(capacity) = self.capacity {
+            while self.undo.len() > capacity {
+                self.undo.pop_front();
+            }
+        }
+    }
+
+    /// Opens a group `label`, or nests into the open group.
+    pub(crate) fn begin_group(&mut self, label: String) {
+        match &mut self.group {
+            Some((depth, _)) => *depth += 1,
+            None => {
+                self.group = Some((
+                    1,
+                    Step {
+                        label,
+                        commands: vec![],
+                    },
+                ))
+            }
+        }
+    }
+
+    /// Closes the innermost group and records the outermost one
+    /// as a single step once it is closed, if it contains any request.
+    pub(crate) fn end_group(&mut self) {
+        match self.group.take() {
+            Some((depth, step)) if depth > 1 => self.group = Some((depth - 1, step)),
+            Some((_, step)) if !step.commands.is_empty() => self.push(step),
+            _ => {}
+        }
+    }
+
+    /// Discards all steps.
+    pub(crate) fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    pub(crate) fn snapshot(&self) -> CommandHistory {
+        CommandHistory {
+            undo: self.undo.iter().map(Step::describe).collect(),
+            redo: self.redo.iter().map(Step::describe).collect(),
+        }
+    }
+}
+
+impl<A> Debug for History<A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("History")
+            .field("capacity", &self.capacity)
+            .field("undo", &self.undo.len())
+            .field("redo", &self.redo.len())
+            .finish()
+    }
+}
+
+/// A step of a [`History`] that is being undone or redone.
+///
+/// The step is taken off its stack while its requests are sent
+/// and moved onto the other stack by [`Replay::complete()`].
+/// If the replay is dropped before, e.g. because the future undoing the step
+/// was dropped or a request panicked, the step is put back, so it can be replayed again.
+/// Requests of the step are sent via `send()`, so replaying never records them,
+/// whereas [`Undoable`] requests sent concurrently are recorded as usual.
+pub(crate) struct Replay<'a, A> {
+    history: &'a Mutex<History<A>>,
+    step: Option<Step<A>>,
+    undo: bool,
+    recorded: u64,
+}
+
+impl<'a, A> Replay<'a, A> {
+    /// Takes the step to undo next, if any.
+    pub(crate) fn undo(history: &'a Mutex<History<A>>) -> Option<Self> {
+        let mut locked = history.lock().unwrap();
+        let step = locked.undo.pop_back()?;
+        Some(Self {
+            step: Some(step),
+            undo: true,
+            recorded: locked.recorded,
+            history,
+        })
+    }
+
+    /// Takes the step to redo next, if any.
+    pub(crate) fn redo(history: &'a Mutex<History<A>>) -> Option<Self> {
+        let mut locked = history.lock().unwrap();
+        let step = locked.redo.pop()?;
+        Some(Self {
+            step: Some(step),
+            undo: false,
+            recorded: locked.recorded,
+            history,
+        })
+    }
+
+    pub(crate) fn step(&self) -> &Step<A> {
+        self.step.as_ref().unwrap()
+    }
+
+    /// Moves an undone step onto the redo stack and a redone step onto the undo stack.
+    ///
+    /// An undone step is discarded instead, if a new step was recorded meanwhile,
+    /// just like recording discards the steps to redo.
+    pub(crate) fn complete(mut self) {
+        let step = self.step.take().unwrap();
+        let mut history = self.history.lock().unwrap();
+        match self.undo {
+            true if history.recorded != self.recorded => {}
+            true => history.redo.push(step),
+            false => history.undo.push_back(step),
+        }
+    }
+}
+
+impl<A> Drop for Replay<'_, A> {
+    /// Puts the step back below the steps recorded meanwhile,
+    /// or discards a step to redo if a recording discarded the others.
+    fn drop(&mut self) {
+        if let Some(step) = self.step.take() {
+            if let Ok(mut history) = self.history.lock() {
+                let newer = (history.recorded - self.recorded) as usize;
+                match self.undo {
+                    true => {
+                        let at = history.undo.len().saturating_sub(newer);
+                        history.undo.insert(at, step);
+                        if let Some(capacity) = history.capacity {
+                            while history.undo.len() > capacity {
+                                history.undo.pop_front();
+                            }
+                        }
+                    }
+                    false if newer > 0 => {}
+                    false => history.redo.push(step),
+                }
+            }
+        }
+    }
+}
+
+/// An action of the history of a mediator `M`, which sends a request.
+pub(crate) type Action<M> = Box<dyn Fn(&M) + Send>;
+
+/// An action of the history of an async mediator `M`, which sends a request.
+#[cfg(feature = "async")]
+pub(crate) type AsyncAction<M> = Box<dyn for<'a> Fn(&'a M) -> BoxFuture<'a, ()> + Send + Sync>;
@@ -0,0 +1,386 @@
+use std::{
+    any::{type_name, Any},
+    collections::{HashMap, VecDeque},
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+#[cfg(feature = "async")]
+use futures::{
+    channel::oneshot::{channel, Receiver, Sender},
+    future::{FutureExt, Shared},
+};
+#[cfg(feature = "async")]
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// The outcome of a request, as cached by a [`DedupStore`].
+///
+/// It holds `()` for requests sent via `send_idempotent()` and
+/// the `Result` of the handler for requests sent via `try_send_idempotent()`,
+/// which is only cached if the request succeeded.
+pub type CachedOutcome = Arc<dyn Any + Send + Sync>;
+
+/// A request or an event that may carry an idempotency key.
+///
+/// Requests sent via `send_idempotent()` or `try_send_idempotent()` with a key
+/// that was already handled are not handled again, if the mediator deduplicates requests.
+/// Events with a key that was already received are not delivered again,
+/// if the mediator deduplicates events.
+/// Requests and events without a key are never deduplicated.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mediator_sys::idempotency::Idempotent;
+///
+/// struct Charge {
+///     order: u32,
+///     amount: u32,
+/// }
+///
+/// impl Idempotent for Charge {
+///     fn idempotency_key(&self) -> Option<String> {
+///         Some(format!("charge-{}", self.order))
+///     }
+/// }
+///
+/// let charge = Charge { order: 7, amount: 20 };
+/// assert_eq!(charge.idempotency_key(), Some(String::from("charge-7")));
+/// ```
+pub trait Idempotent {
+    /// Returns the idempotency key, if any.
+    fn idempotency_key(&self) -> Option<String>;
+}
+
+/// A [`DedupStore`] remembers the keys of handled requests and received events
+/// along with a value of type `V`, e.g. the [`CachedOutcome`] of a request.
+///
+/// It is implemented by the [`MemoryDedupStore`],
+/// but can be implemented for any other backend as well,
+/// e.g. one shared by several mediators.
+pub trait DedupStore<V>: Debug + Send + Sync {
+    /// Returns the value recorded for `key`, if it is still remembered.
+    fn get(&self, key: &str) -> Option<V>;
+
+    /// Records `value` for `key`.
+    fn insert(&self, key: String, value: V);
+}
+
+/// A [`DedupStore`] that remembers the last `capacity` keys in memory.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mediator_sys::idempotency::{DedupStore, MemoryDedupStore};
+///
+/// let store = MemoryDedupStore::new(2);
+/// store.insert(String::from("a"), 1);
+/// store.insert(String::from("b"), 2);
+/// store.insert(String::from("c"), 3);
+///
+/// assert_eq!(store.get("a"), None);
+/// assert_eq!(store.get("c"), Some(3));
+/// ```
+pub struct MemoryDedupStore<V> {
+    capacity: usize,
+    entries: Mutex<(HashMap<String, V>, VecDeque<String>)>,
+}
+
+impl<V> MemoryDedupStore<V> {
+    /// Creates an empty [`MemoryDedupStore`] remembering at most `capacity` keys.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            entries: Mutex::new((HashMap::new(), VecDeque::new())),
+        }
+    }
+}
+
+impl<V> DedupStore<V> for MemoryDedupStore<V>
+where
+    V: Clone + Send,
+{
+    fn get(&self, key: &str) -> Option<V> {
+        self.entries.lock().unwrap().0.get(key).cloned()
+    }
+
+    fn insert(&self, key: String, value: V) {
+        let (values, order) = &mut *self.entries.lock().unwrap();
+        if values.insert(key.clone(), value).is_none() {
+            order.push_back(key);
+        }
+        while order.len() > self.capacity {
+            if let Some(oldest) = order.pop_front() {
+                values.remove(&oldest);
+            }
+        }
+    }
+}
+
+impl<V> Debug for MemoryDedupStore<V> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoryDedupStore")
+            .field("capacity", &self.capacity)
+            .field("keys", &self.entries.lock().unwrap().1.len())
+            .finish()
+    }
+}
+
+type EventKey<Ev> = Box<dyn Fn(&Ev) -> Option<String> + Send>;
+
+/// The dedup windows of the requests and events of a mediator.
+pub(crate) struct Deduplication<Ev> {
+    requests: Option<Box<dyn DedupStore<CachedOutcome>>>,
+    events: Option<(EventKey<Ev>, Box<dyn DedupStore<()>>)>,
+}
+
+impl<Ev> Deduplication<Ev> {
+    pub(crate) fn new() -> Self {
+        Self {
+            requests: None,
+            events: None,
+        }
+    }
+
+    pub(crate) fn set_requests<St>(&mut self, store: St)
+    where
+        St: DedupStore<CachedOutcome> + 'static,
+    {
+        self.requests = Some(Box::new(store));
+    }
+
+    pub(crate) fn set_events<St>(&mut self, store: St)
+    where
+        St: DedupStore<()> + 'static,
+        Ev: Idempotent,
+    {
+        self.events = Some((Box::new(|ev: &Ev| ev.idempotency_key()), Box::new(store)));
+    }
+
+    /// Returns `false` if `ev` is a duplicate, otherwise records its key.
+    pub(crate) fn admit(&self, ev: &Ev) -> bool {
+        let (key, store) = match &self.events {
+            Some(events) => events,
+            None => return true,
+        };
+        match key(ev) {
+            Some(key) if store.get(&key).is_some() => false,
+            Some(key) => {
+                store.insert(key, ());
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// Returns the key of `req` within the namespace of its type.
+    ///
+    /// Returns `None` if `req` has no key or requests are not deduplicated.
+    pub(crate) fn key<Req>(&self, req: &Req) -> Option<String>
+    where
+        Req: Idempotent,
+    {
+        self.requests.as_ref()?;
+        Some(format!("{}:{}", type_name::<Req>(), req.idempotency_key()?))
+    }
+
+    /// Returns the cached outcome of type `T` of the request `key`, if any.
+    pub(crate) fn cached<T>(&self, key: &str) -> Option<T>
+    where
+        T: Clone + 'static,
+    {
+        self.requests
+            .as_ref()?
+            .get(key)
+            .and_then(|outcome| outcome.downcast_ref::<T>().cloned())
+    }
+
+    /// Caches the outcome of the request `key`.
+    pub(crate) fn record<T>(&self, key: String, outcome: T)
+    where
+        T: Send + Sync + 'static,
+    {
+        if let Some(store) = &self.requests {
+            store.insert(key, Arc::new(outcome));
+        }
+    }
+}
+
+impl<Ev> Debug for Deduplication<Ev> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Deduplication")
+            .field("requests", &self.requests)
+            .field("events", &self.events.as_ref().map(|(_, store)| store))
+            .finish()
+    }
+}
+
+/// The outcome of a request that is being handled, as awaited by its duplicates.
+#[cfg(feature = "async")]
+type Outcome = Shared<Receiver<CachedOutcome>>;
+
+/// The keys of the requests an async mediator is handling, so that duplicates
+/// sent meanwhile wait for their outcome instead of being handled as well.
+#[cfg(feature = "async")]
+#[derive(Debug, Default)]
+pub(crate) struct InFlight {
+    keys: Mutex<HashMap<String, Outcome>>,
+}
+
+#[cfg(feature = "async")]
+async_std::task_local! {
+    /// The request keys whose handler the current task is polling, per [`InFlight`]
+    /// by its address, so that a duplicate sent by the handler does not wait for itself.
+    static HELD: RefCell<HashSet<(usize, String)>> = RefCell::new(HashSet::new());
+}
+
+/// The result of [`InFlight::reserve()`].
+#[cfg(feature = "async")]
+pub(crate) enum Reservation<'a> {
+    /// The caller handles the request and completes the [`Claim`].
+    Claimed(Claim<'a>),
+    /// A duplicate is being handled, whose outcome is awaited via [`wait()`].
+    Waiting(Outcome),
+    /// A duplicate is being handled by the caller itself, so its outcome cannot be awaited.
+    Reentered,
+}
+
+#[cfg(feature = "async")]
+impl InFlight {
+    /// Reserves the request `key` for the caller, unless a duplicate reserved it before.
+    pub(crate) fn reserve(&self, key: &str) -> Reservation<'_> {
+        let held = (self as *const Self as usize, key.to_owned());
+        let mut keys = self.keys.lock().unwrap();
+        if let Some(outcome) = keys.get(key) {
+            let reentered = HELD
+                .try_with(|keys| keys.borrow().contains(&held))
+                .unwrap_or(false);
+            if reentered {
+                return Reservation::Reentered;
+            }
+            return Reservation::Waiting(outcome.clone());
+        }
+        let (sender, receiver) = channel();
+        keys.insert(key.to_owned(), receiver.shared());
+        Reservation::Claimed(Claim {
+            in_flight: self,
+            key: key.to_owned(),
+            sender: Some(sender),
+        })
+    }
+}
+
+/// The reservation of a request key, which is released on drop.
+///
+/// If it is dropped before [`Claim::complete()`], e.g. because the future handling
+/// the request was dropped, the duplicates waiting for it try again.
+#[cfg(feature = "async")]
+pub(crate) struct Claim<'a> {
+    in_flight: &'a InFlight,
+    key: String,
+    sender: Option<Sender<CachedOutcome>>,
+}
+
+#[cfg(feature = "async")]
+impl Claim<'_> {
+    /// Releases the key and passes `outcome` to the duplicates waiting for it.
+    ///
+    /// The outcome must have been cached before, if at all,
+    /// so that duplicates sent afterwards find it.
+    pub(crate) fn complete<T>(mut self, outcome: T)
+    where
+        T: Send + Sync + 'static,
+    {
+        self.release();
+        if let Some(sender) = self.sender.take() {
+            sender.send(Arc::new(outcome)).ok();
+        }
+    }
+
+    fn release(&self) {
+        if let Ok(mut keys) = self.in_flight.keys.lock() {
+            keys.remove(&self.key);
+        }
+    }
+
+    /// Wraps `handler`, the future handling the request,
+    /// so that duplicates sent while it is polled are [`Reservation::Reentered`].
+    pub(crate) fn hold<F>(&self, handler: F) -> Held<F>
+    where
+        F: Future + Unpin,
+    {
+        Held {
+            key: (self.in_flight as *const InFlight as usize, self.key.clone()),
+            handler,
+        }
+    }
+}
+
+/// The future handling a claimed request, see [`Claim::hold()`].
+#[cfg(feature = "async")]
+pub(crate) struct Held<F> {
+    key: (usize, String),
+    handler: F,
+}
+
+#[cfg(feature = "async")]
+impl<F> Future for Held<F>
+where
+    F: Future + Unpin,
+{
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let this = &mut *self;
+        let inserted = HELD
+            .try_with(|keys| keys.borrow_mut().insert(this.key.clone()))
+            .unwrap_or(false);
+        let _held = Holding(inserted.then_some(&this.key));
+        Pin::new(&mut this.handler).poll(cx)
+    }
+}
+
+/// Removes the key from [`HELD`] once the handler was polled, even if it panicked.
+#[cfg(feature = "async")]
+struct Holding<'a>(Option<&'a (usize, String)>);
+
+#[cfg(feature = "async")]
+impl Drop for Holding<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.0 {
+            HELD.try_with(|keys| keys.borrow_mut().remove(key)).ok();
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl Drop for Claim<'_> {
+    fn drop(&mut self) {
+        if self.sender.is_some() {
+            self.release();
+        }
+    }
+}
+
+/// Waits for the outcome of type `T` of a duplicate,
+/// returns `None` if it was not handled to completion.
+#[cfg(feature = "async")]
+pub(crate) async fn wait<T>(outcome: Outcome) -> Option<T>
+where
+    T: Clone + 'static,
+{
+    outcome
+        .await
+        .ok()
+        .and_then(|outcome| outcome.downcast_ref::<T>().cloned())
+}
@@ -0,0 +1,112 @@
+use std::{
+    fmt::Debug,
+    sync::{
+        mpsc::{SendError, Sender},
+        Arc, Mutex,
+    },
+    task::Waker,
+};
+
+#[cfg(feature = "async")]
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::mediator::hierarchy::Envelope;
+
+/// The sending half of the channel of a mediator.
+///
+/// Every event sent to the mediator, whether published on it,
+/// propagated by a linked mediator or forwarded by a bridge,
+/// wakes the tasks waiting for it in `recv()`.
+pub(crate) struct Inbox<Ev> {
+    sender: Sender<Envelope<Ev>>,
+    pub(crate) arrivals: Arc<Arrivals>,
+}
+
+impl<Ev> Inbox<Ev> {
+    pub(crate) fn new(sender: Sender<Envelope<Ev>>) -> Self {
+        Self {
+            sender,
+            arrivals: Arc::new(Arrivals {
+                state: Mutex::new((0, vec![])),
+            }),
+        }
+    }
+
+    /// Sends `envelope` to the mediator and wakes the tasks waiting for it.
+    pub(crate) fn send(&self, envelope: Envelope<Ev>) -> Result<(), SendError<Envelope<Ev>>> {
+        self.sender.send(envelope)?;
+        self.arrivals.notify();
+        Ok(())
+    }
+}
+
+impl<Ev> Clone for Inbox<Ev> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            arrivals: self.arrivals.clone(),
+        }
+    }
+}
+
+impl<Ev> Debug for Inbox<Ev> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Inbox")
+            .field("arrivals", &self.arrivals.state.lock().unwrap().0)
+            .finish()
+    }
+}
+
+/// Counts the events sent to a mediator and wakes the tasks waiting for the next one.
+pub(crate) struct Arrivals {
+    state: Mutex<(u64, Vec<Waker>)>,
+}
+
+impl Arrivals {
+    fn notify(&self) {
+        let wakers = {
+            let mut state = self.state.lock().unwrap();
+            state.0 += 1;
+            std::mem::take(&mut state.1)
+        };
+        for waker in wakers {
+            waker.wake();
+        }
+    }
+
+    /// Returns an [`Arrival`] that resolves once an event is sent after this call.
+    #[cfg(feature = "async")]
+    pub(crate) fn next(self: &Arc<Self>) -> Arrival {
+        Arrival {
+            seen: self.state.lock().unwrap().0,
+            arrivals: self.clone(),
+        }
+    }
+}
+
+/// Resolves once an event was sent to a mediator after it was created.
+#[cfg(feature = "async")]
+pub(crate) struct Arrival {
+    seen: u64,
+    arrivals: Arc<Arrivals>,
+}
+
+#[cfg(feature = "async")]
+impl Future for Arrival {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.arrivals.state.lock().unwrap();
+        if state.0 != self.seen {
+            return Poll::Ready(());
+        }
+        if !state.1.iter().any(|waker| waker.will_wake(cx.waker())) {
+            state.1.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
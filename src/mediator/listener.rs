@@ -1,7 +1,23 @@
 use core::fmt::Debug;
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap, VecDeque},
+    hash::{Hash, Hasher},
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        mpsc::{channel, Sender},
+        Arc, Condvar, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::mediator::topic::Routed;
+
+type KeyHash<Ev> = Box<dyn Fn(&Ev) -> u64 + Send>;
 
 /// A [`Listener`] is a user-defined closure that is generic over its received event `Ev`.
 /// The closure handles the event and may act upon an event.
+#[allow(clippy::unused_unit)]
 pub trait Listener<Ev: Debug>: Fn(Ev) -> () + Send + 'static {}
 
 impl<Ev> Debug for dyn Listener<Ev>
@@ -13,9 +29,443 @@ where
     }
 }
 
+#[allow(clippy::unused_unit)]
 impl<Ev, F> Listener<Ev> for F
 where
     F: Fn(Ev) -> () + Send + 'static,
     Ev: Debug + Clone,
 {
 }
+
+/// A listener that depends on time, such as a debounced, throttled or batch [`Listener`].
+///
+/// It receives every dispatched event along with the current point in time
+/// of the mediator clock and is ticked on every `next()`.
+pub(crate) trait TimedListener<Ev>: Send {
+    /// Receives a dispatched event.
+    fn on_event(&self, ev: Ev, now: Instant);
+
+    /// Emits pending events that are due, returns `true` if any was emitted.
+    fn tick(&self, now: Instant) -> bool;
+
+    /// Returns the point in time the next pending event is due, if any.
+    fn next_due(&self) -> Option<Instant>;
+
+    /// Emits all pending events regardless of their due time,
+    /// returns `true` if any was emitted.
+    fn flush(&self) -> bool;
+
+    /// Returns an [`Idle`] to wait for the events that are processed
+    /// in the background, if any.
+    fn idle(&self) -> Option<Idle>;
+}
+
+impl<Ev> Debug for dyn TimedListener<Ev> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TimedListener")
+    }
+}
+
+/// Calls the inner [`Listener`] with the last event of a burst per key,
+/// once no further event with the same key arrived for the `quiet` period.
+pub(crate) struct Debounce<Ev, K, KF, F> {
+    quiet: Duration,
+    key: KF,
+    pending: Mutex<Pending<K, Ev>>,
+    f: F,
+}
+
+/// The pending event of every key, with the time it is due and
+/// its arrival, which orders keys that are due at the same time.
+struct Pending<K, Ev> {
+    events: HashMap<K, (Ev, Instant, usize)>,
+    arrived: usize,
+}
+
+impl<Ev, K, KF, F> Debounce<Ev, K, KF, F> {
+    pub(crate) fn new(quiet: Duration, key: KF, f: F) -> Self {
+        Self {
+            quiet,
+            key,
+            pending: Mutex::new(Pending {
+                events: HashMap::new(),
+                arrived: 0,
+            }),
+            f,
+        }
+    }
+}
+
+impl<Ev, K, KF, F> Debounce<Ev, K, KF, F>
+where
+    Ev: Debug,
+    K: Hash + Eq,
+    F: Listener<Ev>,
+{
+    /// Emits the pending events that are `due` in the order they are due,
+    /// returns `true` if any was emitted.
+    fn emit<D>(&self, due: D) -> bool
+    where
+        D: Fn(Instant) -> bool,
+    {
+        let mut emitted = vec![];
+        {
+            let pending = &mut self.pending.lock().unwrap().events;
+            for (key, (ev, at, seq)) in std::mem::take(pending) {
+                match due(at) {
+                    true => emitted.push((ev, at, seq)),
+                    false => {
+                        pending.insert(key, (ev, at, seq));
+                    }
+                }
+            }
+        }
+        emitted.sort_by_key(|(_, at, seq)| (*at, *seq));
+        let any = !emitted.is_empty();
+        for (ev, _, _) in emitted {
+            (self.f)(ev);
+        }
+        any
+    }
+}
+
+impl<Ev, K, KF, F> TimedListener<Ev> for Debounce<Ev, K, KF, F>
+where
+    Ev: Debug + Send,
+    K: Hash + Eq + Send,
+    KF: Fn(&Ev) -> K + Send,
+    F: Listener<Ev>,
+{
+    fn on_event(&self, ev: Ev, now: Instant) {
+        let key = (self.key)(&ev);
+        let mut pending = self.pending.lock().unwrap();
+        pending.arrived += 1;
+        let arrived = pending.arrived;
+        pending.events.insert(key, (ev, now + self.quiet, arrived));
+    }
+
+    fn tick(&self, now: Instant) -> bool {
+        self.emit(|due| due <= now)
+    }
+
+    fn next_due(&self) -> Option<Instant> {
+        self.pending
+            .lock()
+            .unwrap()
+            .events
+            .values()
+            .map(|(_, due, _)| *due)
+            .min()
+    }
+
+    fn flush(&self) -> bool {
+        self.emit(|_| true)
+    }
+
+    fn idle(&self) -> Option<Idle> {
+        None
+    }
+}
+
+/// Calls the inner [`Listener`] for at most `max` events per `interval` and key
+/// and drops the others.
+pub(crate) struct Throttle<K, KF, F> {
+    max: usize,
+    interval: Duration,
+    key: KF,
+    windows: Mutex<HashMap<K, VecDeque<Instant>>>,
+    f: F,
+}
+
+impl<K, KF, F> Throttle<K, KF, F> {
+    pub(crate) fn new(max: usize, interval: Duration, key: KF, f: F) -> Self {
+        Self {
+            max: max.max(1),
+            interval,
+            key,
+            windows: Mutex::new(HashMap::new()),
+            f,
+        }
+    }
+}
+
+impl<Ev, K, KF, F> TimedListener<Ev> for Throttle<K, KF, F>
+where
+    Ev: Debug,
+    K: Hash + Eq + Send,
+    KF: Fn(&Ev) -> K + Send,
+    F: Listener<Ev>,
+{
+    fn on_event(&self, ev: Ev, now: Instant) {
+        {
+            let mut windows = self.windows.lock().unwrap();
+            // Forget the keys whose window passed, so they do not pile up.
+            windows.retain(|_, window| {
+                while matches!(window.front(), Some(at) if *at + self.interval <= now) {
+                    window.pop_front();
+                }
+                !window.is_empty()
+            });
+            let window = windows.entry((self.key)(&ev)).or_default();
+            if window.len() >= self.max {
+                return;
+            }
+            window.push_back(now);
+        }
+        (self.f)(ev)
+    }
+
+    fn tick(&self, _now: Instant) -> bool {
+        false
+    }
+
+    fn next_due(&self) -> Option<Instant> {
+        None
+    }
+
+    fn flush(&self) -> bool {
+        false
+    }
+
+    fn idle(&self) -> Option<Idle> {
+        None
+    }
+}
+
+/// Calls the inner [`Listener`] with batches of events.
+///
+/// A batch is emitted once it holds `max_size` events,
+/// once its first event waited for `max_wait` or when it is flushed.
+pub(crate) struct Batch<Ev, F> {
+    max_size: usize,
+    max_wait: Duration,
+    pending: Mutex<(Vec<Ev>, Option<Instant>)>,
+    f: F,
+}
+
+impl<Ev, F> Batch<Ev, F> {
+    pub(crate) fn new(max_size: usize, max_wait: Duration, f: F) -> Self {
+        Self {
+            max_size: max_size.max(1),
+            max_wait,
+            pending: Mutex::new((vec![], None)),
+            f,
+        }
+    }
+}
+
+impl<Ev, F> Batch<Ev, F>
+where
+    Ev: Debug + Clone,
+    F: Listener<Vec<Ev>>,
+{
+    /// Emits the pending batch if `due` says so, returns `true` if it was emitted.
+    fn emit_if<D>(&self, due: D) -> bool
+    where
+        D: FnOnce(&Vec<Ev>, Option<Instant>) -> bool,
+    {
+        let batch = {
+            let mut pending = self.pending.lock().unwrap();
+            if pending.0.is_empty() || !due(&pending.0, pending.1) {
+                return false;
+            }
+            pending.1 = None;
+            std::mem::take(&mut pending.0)
+        };
+        (self.f)(batch);
+        true
+    }
+}
+
+impl<Ev, F> TimedListener<Ev> for Batch<Ev, F>
+where
+    Ev: Debug + Clone + Send,
+    F: Listener<Vec<Ev>>,
+{
+    fn on_event(&self, ev: Ev, now: Instant) {
+        {
+            let mut pending = self.pending.lock().unwrap();
+            pending.0.push(ev);
+            pending.1.get_or_insert(now + self.max_wait);
+        }
+        self.emit_if(|batch, _| batch.len() >= self.max_size);
+    }
+
+    fn tick(&self, now: Instant) -> bool {
+        self.emit_if(|_, due| matches!(due, Some(due) if due <= now))
+    }
+
+    fn next_due(&self) -> Option<Instant> {
+        self.pending.lock().unwrap().1
+    }
+
+    fn flush(&self) -> bool {
+        self.emit_if(|_, _| true)
+    }
+
+    fn idle(&self) -> Option<Idle> {
+        None
+    }
+}
+
+/// Calls the inner listener on a pool of worker threads.
+///
+/// Events are routed to a worker by the hash of their key,
+/// so events with the same key are processed strictly in order,
+/// while events with different keys may be processed in parallel.
+pub(crate) struct Parallel<Ev> {
+    key: KeyHash<Ev>,
+    workers: Vec<(Sender<Ev>, JoinHandle<()>)>,
+    pending: Arc<(Mutex<usize>, Condvar)>,
+}
+
+impl<Ev> Parallel<Ev>
+where
+    Ev: Send + 'static,
+{
+    pub(crate) fn new<K, KF, F>(workers: usize, key: KF, f: F) -> Self
+    where
+        K: Hash,
+        KF: Fn(&Ev) -> K + Send + 'static,
+        F: Fn(Ev) + Send + Sync + 'static,
+    {
+        let f = Arc::new(f);
+        let pending = Arc::new((Mutex::new(0), Condvar::new()));
+        let workers = (0..workers.max(1))
+            .map(|_| {
+                let (tx, rx) = channel::<Ev>();
+                let f = f.clone();
+                let pending = pending.clone();
+                let handle = thread::spawn(move || {
+                    for ev in rx {
+                        // A panicking listener must not stop the worker,
+                        // or the events of its keys would be lost.
+                        catch_unwind(AssertUnwindSafe(|| f(ev))).ok();
+                        let (count, idle) = &*pending;
+                        *count.lock().unwrap() -= 1;
+                        idle.notify_all();
+                    }
+                });
+                (tx, handle)
+            })
+            .collect();
+
+        Self {
+            key: Box::new(move |ev| {
+                let mut hasher = DefaultHasher::new();
+                key(ev).hash(&mut hasher);
+                hasher.finish()
+            }),
+            workers,
+            pending,
+        }
+    }
+}
+
+impl<Ev> TimedListener<Ev> for Parallel<Ev>
+where
+    Ev: Send,
+{
+    fn on_event(&self, ev: Ev, _now: Instant) {
+        let worker = (self.key)(&ev) as usize % self.workers.len();
+        *self.pending.0.lock().unwrap() += 1;
+        if self.workers[worker].0.send(ev).is_err() {
+            *self.pending.0.lock().unwrap() -= 1;
+        }
+    }
+
+    fn tick(&self, _now: Instant) -> bool {
+        false
+    }
+
+    fn next_due(&self) -> Option<Instant> {
+        None
+    }
+
+    fn flush(&self) -> bool {
+        false
+    }
+
+    fn idle(&self) -> Option<Idle> {
+        Some(Idle(self.pending.clone()))
+    }
+}
+
+/// Waits until the workers of a [`Parallel`] listener processed
+/// all events routed to them so far, without holding the mediator.
+pub(crate) struct Idle(Arc<(Mutex<usize>, Condvar)>);
+
+impl Idle {
+    /// Blocks until no routed event is pending.
+    pub(crate) fn wait(&self) {
+        let (count, idle) = &*self.0;
+        let mut count = count.lock().unwrap();
+        while *count > 0 {
+            count = idle.wait(count).unwrap();
+        }
+    }
+}
+
+impl<Ev> Drop for Parallel<Ev> {
+    /// Lets the workers process the remaining events and joins them.
+    fn drop(&mut self) {
+        let handles: Vec<_> = self
+            .workers
+            .drain(..)
+            .map(|(tx, handle)| {
+                drop(tx);
+                handle
+            })
+            .collect();
+        for handle in handles {
+            handle.join().ok();
+        }
+    }
+}
+
+/// Merges queued events with the same key before they are dispatched.
+pub(crate) trait Coalesce<Ev>: Send {
+    /// Appends `ev` to `queue` or merges it into a queued event
+    /// with the same key that was published to the same topic.
+    fn push(&self, queue: &mut VecDeque<Routed<Ev>>, ev: Routed<Ev>);
+}
+
+impl<Ev> Debug for dyn Coalesce<Ev> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Coalesce")
+    }
+}
+
+/// A [`Coalesce`] based on a key function and a merge function.
+pub(crate) struct Coalescer<KF, MF> {
+    key: KF,
+    merge: MF,
+}
+
+impl<KF, MF> Coalescer<KF, MF> {
+    pub(crate) fn new(key: KF, merge: MF) -> Self {
+        Self { key, merge }
+    }
+}
+
+impl<Ev, K, KF, MF> Coalesce<Ev> for Coalescer<KF, MF>
+where
+    K: Eq,
+    KF: Fn(&Ev) -> Option<K> + Send,
+    MF: Fn(Ev, Ev) -> Ev + Send,
+{
+    fn push(&self, queue: &mut VecDeque<Routed<Ev>>, (ev, topic): Routed<Ev>) {
+        let position = (self.key)(&ev).and_then(|key| {
+            queue.iter().position(|(queued, queued_topic)| {
+                *queued_topic == topic && (self.key)(queued).as_ref() == Some(&key)
+            })
+        });
+        match position {
+            Some(position) => {
+                let (queued, _) = queue.remove(position).unwrap();
+                queue.insert(position, ((self.merge)(queued, ev), topic));
+            }
+            None => queue.push_back((ev, topic)),
+        }
+    }
+}
@@ -1,5 +1,20 @@
 #[cfg(feature = "async")]
 pub mod asynchronous;
+pub(crate) mod bridge;
 pub mod builder;
+pub mod cache;
+pub mod clock;
+pub mod dead_letter;
+pub mod hierarchy;
+pub mod history;
+pub mod idempotency;
+pub(crate) mod inbox;
 pub mod listener;
+pub(crate) mod outbox;
+pub mod priority;
+pub mod retry;
+pub mod saga;
+pub mod schedule;
+pub mod storage;
 pub mod synchronous;
+pub(crate) mod topic;
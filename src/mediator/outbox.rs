@@ -0,0 +1,201 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fmt::Debug,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+#[cfg(feature = "async")]
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::mediator::hierarchy::Envelope;
+
+static NEXT_MEDIATOR: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// The transactions that are currently running, innermost last,
+    /// as pairs of the mediator they belong to and their id.
+    static CURRENT: RefCell<Vec<(u64, u64)>> = const { RefCell::new(vec![]) };
+}
+
+/// The outbox of a mediator, which buffers the events published
+/// while a transactional request is handled.
+///
+/// Each request gets its own [`Transaction`]. While its handler runs,
+/// the transaction is the ambient transaction of the mediator,
+/// so events published via the mediator end up in the outbox of the request
+/// instead of the channel. They are committed to the channel once the handler
+/// succeeded and discarded once it failed.
+/// Requests sent from within a transactional handler are transactions of their own,
+/// which commit into the outbox of the enclosing request.
+pub(crate) struct Outbox<Ev> {
+    mediator: u64,
+    enabled: bool,
+    pending: Arc<Mutex<Pending<Ev>>>,
+}
+
+struct Pending<Ev> {
+    next_id: u64,
+    outboxes: BTreeMap<u64, Vec<Envelope<Ev>>>,
+}
+
+impl<Ev> Outbox<Ev> {
+    pub(crate) fn new() -> Self {
+        Self {
+            mediator: NEXT_MEDIATOR.fetch_add(1, Ordering::Relaxed),
+            enabled: false,
+            pending: Arc::new(Mutex::new(Pending {
+                next_id: 0,
+                outboxes: BTreeMap::new(),
+            })),
+        }
+    }
+
+    /// Makes requests handled by the mediator transactional.
+    pub(crate) fn enable(&mut self) {
+        self.enabled = true;
+    }
+
+    /// Begins a [`Transaction`] for a request, if requests are transactional.
+    pub(crate) fn begin(&self) -> Option<Transaction<Ev>> {
+        if !self.enabled {
+            return None;
+        }
+        let mut pending = self.pending.lock().unwrap();
+        let id = pending.next_id;
+        pending.next_id += 1;
+        pending.outboxes.insert(id, vec![]);
+        Some(Transaction {
+            mediator: self.mediator,
+            id,
+            pending: self.pending.clone(),
+        })
+    }
+
+    /// Buffers `envelope` in the outbox of the ambient transaction of the mediator.
+    ///
+    /// Returns `envelope` back if there is none, so it can be sent right away.
+    pub(crate) fn stash(&self, envelope: Envelope<Ev>) -> Option<Envelope<Ev>> {
+        let current = CURRENT.with(|current| {
+            current
+                .borrow()
+                .iter()
+                .rev()
+                .find(|(mediator, _)| *mediator == self.mediator)
+                .map(|(_, id)| *id)
+        });
+        match current {
+            Some(id) => match self.pending.lock().unwrap().outboxes.get_mut(&id) {
+                Some(outbox) => {
+                    outbox.push(envelope);
+                    None
+                }
+                None => Some(envelope),
+            },
+            None => Some(envelope),
+        }
+    }
+}
+
+impl<Ev> Debug for Outbox<Ev> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Outbox")
+            .field("enabled", &self.enabled)
+            .field("transactions", &self.pending.lock().unwrap().outboxes.len())
+            .finish()
+    }
+}
+
+/// The outbox of a single request, which is discarded on drop unless committed.
+pub(crate) struct Transaction<Ev> {
+    mediator: u64,
+    id: u64,
+    pending: Arc<Mutex<Pending<Ev>>>,
+}
+
+impl<Ev> Transaction<Ev> {
+    /// Runs `f` with the transaction as the ambient transaction of its mediator.
+    pub(crate) fn run<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        let _ambient = Ambient::enter(self.mediator, self.id);
+        f()
+    }
+
+    /// Returns a `Future` that polls `future` with the transaction
+    /// as the ambient transaction of its mediator.
+    #[cfg(feature = "async")]
+    pub(crate) fn scope<F>(&self, future: F) -> Scoped<F>
+    where
+        F: Future,
+    {
+        Scoped {
+            mediator: self.mediator,
+            id: self.id,
+            future: Box::pin(future),
+        }
+    }
+
+    /// Ends the transaction and returns the events published within it.
+    pub(crate) fn commit(self) -> Vec<Envelope<Ev>> {
+        self.pending
+            .lock()
+            .unwrap()
+            .outboxes
+            .remove(&self.id)
+            .unwrap_or_default()
+    }
+}
+
+impl<Ev> Drop for Transaction<Ev> {
+    /// Discards the events of the transaction, if it was not committed.
+    fn drop(&mut self) {
+        self.pending.lock().unwrap().outboxes.remove(&self.id);
+    }
+}
+
+/// A `Future` that is polled within a [`Transaction`].
+///
+/// Created by [`Transaction::scope()`].
+#[cfg(feature = "async")]
+pub(crate) struct Scoped<F> {
+    mediator: u64,
+    id: u64,
+    future: Pin<Box<F>>,
+}
+
+#[cfg(feature = "async")]
+impl<F> Future for Scoped<F>
+where
+    F: Future,
+{
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let _ambient = Ambient::enter(self.mediator, self.id);
+        self.future.as_mut().poll(cx)
+    }
+}
+
+/// Marks a transaction as ambient until it is dropped, even if the handler panicked.
+struct Ambient;
+
+impl Ambient {
+    fn enter(mediator: u64, id: u64) -> Self {
+        CURRENT.with(|current| current.borrow_mut().push((mediator, id)));
+        Ambient
+    }
+}
+
+impl Drop for Ambient {
+    fn drop(&mut self) {
+        CURRENT.with(|current| current.borrow_mut().pop());
+    }
+}
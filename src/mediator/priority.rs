@@ -0,0 +1,105 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, VecDeque},
+    fmt::Debug,
+};
+
+use crate::mediator::{listener::Coalesce, topic::Routed};
+
+type Classifier<Ev> = Box<dyn Fn(&Ev) -> Priority + Send>;
+
+/// The [`Priority`] of an event.
+///
+/// `next()` always dispatches the pending event with the highest priority,
+/// events of the same priority are dispatched in the order they were published.
+/// Events are published with [`Priority::NORMAL`], unless they are classified
+/// by the function given to `prioritize()` on the builders
+/// or published via `publish_with_priority()`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mediator_sys::priority::Priority;
+///
+/// assert!(Priority::HIGH > Priority::NORMAL);
+/// assert!(Priority(-10) < Priority::LOW);
+/// assert_eq!(Priority::default(), Priority::NORMAL);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Priority(pub i32);
+
+impl Priority {
+    /// The priority of routine events.
+    pub const LOW: Priority = Priority(-1);
+    /// The priority of events that are not classified.
+    pub const NORMAL: Priority = Priority(0);
+    /// The priority of urgent events, such as shutdowns or alerts.
+    pub const HIGH: Priority = Priority(1);
+}
+
+/// The pending events of a mediator, ordered by [`Priority`].
+pub(crate) struct EventQueue<Ev> {
+    levels: BTreeMap<Reverse<Priority>, VecDeque<Routed<Ev>>>,
+    classify: Option<Classifier<Ev>>,
+}
+
+impl<Ev> EventQueue<Ev> {
+    pub(crate) fn new() -> Self {
+        Self {
+            levels: BTreeMap::new(),
+            classify: None,
+        }
+    }
+
+    pub(crate) fn set_classifier<F>(&mut self, f: F)
+    where
+        F: Fn(&Ev) -> Priority + Send + 'static,
+    {
+        self.classify = Some(Box::new(f));
+    }
+
+    /// Appends `ev` to the level of its `priority`,
+    /// or of its classified priority if it has none.
+    ///
+    /// If events are coalesced, `ev` may be merged
+    /// into a queued event of the same level instead.
+    pub(crate) fn push(
+        &mut self,
+        ev: Routed<Ev>,
+        priority: Option<Priority>,
+        coalesce: Option<&dyn Coalesce<Ev>>,
+    ) {
+        let priority = priority
+            .or_else(|| self.classify.as_ref().map(|classify| classify(&ev.0)))
+            .unwrap_or_default();
+        let level = self.levels.entry(Reverse(priority)).or_default();
+        match coalesce {
+            Some(coalesce) => coalesce.push(level, ev),
+            None => level.push_back(ev),
+        }
+    }
+
+    /// Removes the oldest event of the highest priority.
+    pub(crate) fn pop(&mut self) -> Option<Routed<Ev>> {
+        let mut level = self.levels.first_entry()?;
+        let ev = level.get_mut().pop_front();
+        if level.get().is_empty() {
+            level.remove();
+        }
+        ev
+    }
+}
+
+impl<Ev> Debug for EventQueue<Ev> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventQueue")
+            .field(
+                "pending",
+                &self.levels.values().map(VecDeque::len).sum::<usize>(),
+            )
+            .field("classified", &self.classify.is_some())
+            .finish()
+    }
+}
@@ -0,0 +1,272 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{hash_map::RandomState, HashMap},
+    fmt::Debug,
+    hash::{BuildHasher, Hasher},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+#[cfg(feature = "async")]
+use std::future::Future;
+
+use crate::mediator::clock::Clock;
+
+type Predicate<E> = Arc<dyn Fn(&E) -> bool + Send + Sync>;
+
+/// The delay between two attempts of a [`RetryPolicy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backoff {
+    /// Waits the same duration before every retry.
+    Fixed(Duration),
+    /// Waits `initial` before the first retry and doubles the delay
+    /// for every further retry, up to `max`.
+    Exponential { initial: Duration, max: Duration },
+}
+
+impl Backoff {
+    fn delay(&self, retry: u32) -> Duration {
+        match *self {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential { initial, max } => initial
+                .checked_mul(2u32.saturating_pow(retry.saturating_sub(1)))
+                .map(|delay| delay.min(max))
+                .unwrap_or(max),
+        }
+    }
+}
+
+/// A [`RetryPolicy`] declares how failed requests are retried.
+///
+/// Policies are added per request type on the builders, e.g. via
+/// [`crate::synchronous::basic::BasicBuilder::add_retry_policy()`],
+/// and applied by `send_with_retry()`.
+/// The type `E` is the error of the fallible handler of that request type.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mediator_sys::retry::RetryPolicy;
+/// use std::time::Duration;
+///
+/// #[derive(Debug)]
+/// enum DbError {
+///     Timeout,
+///     Constraint,
+/// }
+///
+/// let policy = RetryPolicy::<DbError>::new(5)
+///     .exponential(Duration::from_millis(10), Duration::from_secs(1))
+///     .with_jitter(0.2)
+///     .retry_if(|err| matches!(err, DbError::Timeout));
+/// ```
+pub struct RetryPolicy<E> {
+    max_attempts: u32,
+    backoff: Backoff,
+    jitter: f64,
+    retry_if: Option<Predicate<E>>,
+    rng: Arc<Mutex<u64>>,
+}
+
+impl<E> RetryPolicy<E> {
+    /// Creates a [`RetryPolicy`] that makes at most `max_attempts` attempts,
+    /// including the first one, without waiting in between.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            backoff: Backoff::Fixed(Duration::ZERO),
+            jitter: 0.0,
+            retry_if: None,
+            rng: Arc::new(Mutex::new(RandomState::new().build_hasher().finish() | 1)),
+        }
+    }
+
+    /// Waits `delay` before every retry.
+    pub fn fixed(mut self, delay: Duration) -> Self {
+        self.backoff = Backoff::Fixed(delay);
+        self
+    }
+
+    /// Waits `initial` before the first retry and doubles
+    /// the delay for every further retry, up to `max`.
+    pub fn exponential(mut self, initial: Duration, max: Duration) -> Self {
+        self.backoff = Backoff::Exponential { initial, max };
+        self
+    }
+
+    /// Shortens every delay by a random fraction of up to `jitter`,
+    /// which is clamped to `0.0..=1.0`.
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Seeds the random number generator used for the jitter,
+    /// which makes the delays reproducible.
+    pub fn with_seed(self, seed: u64) -> Self {
+        *self.rng.lock().unwrap() = seed | 1;
+        self
+    }
+
+    /// Only retries if `predicate` returns `true` for the error of the handler.
+    /// Without a predicate, every error is retried.
+    pub fn retry_if<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&E) -> bool + Send + Sync + 'static,
+    {
+        self.retry_if = Some(Arc::new(predicate));
+        self
+    }
+
+    /// Returns the maximum number of attempts, including the first one.
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns the [`Backoff`] of the policy.
+    pub fn backoff(&self) -> Backoff {
+        self.backoff
+    }
+
+    /// Returns the delay before the next attempt if the `attempt`
+    /// that failed with `err` should be retried.
+    pub(crate) fn next_delay(&self, attempt: u32, err: &E) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        if let Some(retry_if) = &self.retry_if {
+            if !retry_if(err) {
+                return None;
+            }
+        }
+
+        let delay = self.backoff.delay(attempt);
+        if self.jitter > 0.0 {
+            Some(delay.mul_f64(1.0 - self.jitter * self.random()))
+        } else {
+            Some(delay)
+        }
+    }
+
+    /// Returns a random number in `0.0..1.0` (xorshift64).
+    fn random(&self) -> f64 {
+        let mut state = self.rng.lock().unwrap();
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        (*state >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl<E> Clone for RetryPolicy<E> {
+    fn clone(&self) -> Self {
+        Self {
+            max_attempts: self.max_attempts,
+            backoff: self.backoff,
+            jitter: self.jitter,
+            retry_if: self.retry_if.clone(),
+            rng: self.rng.clone(),
+        }
+    }
+}
+
+impl<E> Debug for RetryPolicy<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("backoff", &self.backoff)
+            .field("jitter", &self.jitter)
+            .field("retry_if", &self.retry_if.is_some())
+            .finish()
+    }
+}
+
+/// Sends `req` until it succeeds or `policy` gives up,
+/// sleeping on `clock` in between.
+pub(crate) fn retry<Req, E, F>(
+    policy: &RetryPolicy<E>,
+    clock: &dyn Clock,
+    req: Req,
+    mut send: F,
+) -> Result<(), E>
+where
+    Req: Clone,
+    F: FnMut(Req) -> Result<(), E>,
+{
+    let mut attempt = 1;
+    loop {
+        let err = match send(req.clone()) {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+        match policy.next_delay(attempt, &err) {
+            Some(delay) => clock.sleep(delay),
+            None => return Err(err),
+        }
+        attempt += 1;
+    }
+}
+
+/// Sends `req` asynchronously until it succeeds or `policy` gives up,
+/// waiting on `clock` in between.
+#[cfg(feature = "async")]
+pub(crate) async fn retry_async<Req, E, F, Fut>(
+    policy: &RetryPolicy<E>,
+    clock: &dyn Clock,
+    req: Req,
+    mut send: F,
+) -> Result<(), E>
+where
+    Req: Clone,
+    F: FnMut(Req) -> Fut,
+    Fut: Future<Output = Result<(), E>>,
+{
+    let mut attempt = 1;
+    loop {
+        let err = match send(req.clone()).await {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+        match policy.next_delay(attempt, &err) {
+            Some(delay) => clock.sleep_async(delay).await,
+            None => return Err(err),
+        }
+        attempt += 1;
+    }
+}
+
+/// The [`RetryPolicy`] of every request type, keyed by the request type.
+#[derive(Default)]
+pub(crate) struct RetryPolicies {
+    policies: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+}
+
+impl RetryPolicies {
+    pub(crate) fn insert<Req, E>(&mut self, policy: RetryPolicy<E>)
+    where
+        Req: 'static,
+        E: 'static,
+    {
+        self.policies.insert(TypeId::of::<Req>(), Box::new(policy));
+    }
+
+    pub(crate) fn get<Req, E>(&self) -> Option<&RetryPolicy<E>>
+    where
+        Req: 'static,
+        E: 'static,
+    {
+        self.policies
+            .get(&TypeId::of::<Req>())
+            .and_then(|policy| policy.downcast_ref::<RetryPolicy<E>>())
+    }
+}
+
+impl Debug for RetryPolicies {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryPolicies")
+            .field("count", &self.policies.len())
+            .finish()
+    }
+}
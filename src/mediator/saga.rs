@@ -0,0 +1,500 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::Debug,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::mediator::{
+    listener::{Idle, TimedListener},
+    storage::{Record, Storage},
+};
+
+#[cfg(feature = "async")]
+use futures::future::BoxFuture;
+
+/// A [`Saga`] coordinates a long-running workflow that spans
+/// multiple requests and events, also known as process manager.
+///
+/// A saga reacts to the events of its mediator. Each event is correlated
+/// with an instance of the saga via [`Saga::correlate()`]. An instance is started
+/// by an event [`Saga::start()`] returns a state for and ends once
+/// [`Saga::react()`] completes or compensates it. In each [`Step`], the saga may send
+/// follow-up requests of type [`Saga::Request`] through the mediator.
+/// If one of them fails or the instance times out, the requests returned by
+/// [`Saga::compensate()`] are sent to undo the steps that already succeeded.
+///
+/// Sagas are added to a mediator via `add_saga()` or `add_saga_with_storage()`
+/// on its builder, which require the mediator to handle [`Saga::Request`]s
+/// as fallible requests. The follow-up requests are sent on `next()`.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mediator_sys::saga::{Saga, Step};
+///
+/// #[derive(Debug, Clone)]
+/// enum OrderEvent {
+///     Placed(u32),
+///     PaymentReceived(u32),
+///     Shipped(u32),
+/// }
+///
+/// enum OrderRequest {
+///     ReserveStock(u32),
+///     ReleaseStock(u32),
+///     Ship(u32),
+/// }
+///
+/// #[derive(Debug, Clone)]
+/// enum OrderState {
+///     AwaitingPayment,
+///     AwaitingShipment,
+/// }
+///
+/// struct Fulfillment;
+///
+/// impl Saga<OrderEvent> for Fulfillment {
+///     type Id = u32;
+///     type State = OrderState;
+///     type Request = OrderRequest;
+///
+///     fn correlate(&self, ev: &OrderEvent) -> Option<u32> {
+///         match ev {
+///             OrderEvent::Placed(id)
+///             | OrderEvent::PaymentReceived(id)
+///             | OrderEvent::Shipped(id) => Some(*id),
+///         }
+///     }
+///
+///     fn start(&self, _id: &u32, ev: &OrderEvent) -> Option<OrderState> {
+///         matches!(ev, OrderEvent::Placed(_)).then_some(OrderState::AwaitingPayment)
+///     }
+///
+///     fn react(&self, state: &mut OrderState, ev: &OrderEvent) -> Step<OrderRequest> {
+///         match (&state, ev) {
+///             (OrderState::AwaitingPayment, OrderEvent::Placed(id)) => {
+///                 Step::Continue(vec![OrderRequest::ReserveStock(*id)])
+///             }
+///             (OrderState::AwaitingPayment, OrderEvent::PaymentReceived(id)) => {
+///                 *state = OrderState::AwaitingShipment;
+///                 Step::Continue(vec![OrderRequest::Ship(*id)])
+///             }
+///             (OrderState::AwaitingShipment, OrderEvent::Shipped(_)) => Step::Complete(vec![]),
+///             _ => Step::Continue(vec![]),
+///         }
+///     }
+///
+///     fn compensate(&self, id: &u32, _state: &OrderState) -> Vec<OrderRequest> {
+///         vec![OrderRequest::ReleaseStock(*id)]
+///     }
+/// }
+/// ```
+pub trait Saga<Ev>: Send + Sync + 'static {
+    /// The correlation id of an instance.
+    type Id: Debug + Clone + Ord + Send + 'static;
+    /// The state of an instance.
+    type State: Debug + Clone + Send + 'static;
+    /// The follow-up requests sent through the mediator.
+    type Request: Send + 'static;
+
+    /// Returns the correlation id of the instance `ev` belongs to,
+    /// or `None` if the saga is not interested in `ev`.
+    fn correlate(&self, ev: &Ev) -> Option<Self::Id>;
+
+    /// Returns the initial state of the instance `id`, if `ev` starts one.
+    ///
+    /// It is only called for events without a running instance.
+    /// The starting event is passed to [`Saga::react()`] afterwards.
+    fn start(&self, id: &Self::Id, ev: &Ev) -> Option<Self::State>;
+
+    /// Advances the instance with `state` by `ev`.
+    fn react(&self, state: &mut Self::State, ev: &Ev) -> Step<Self::Request>;
+
+    /// Returns the requests undoing the steps of the instance `id`,
+    /// once a follow-up request failed, the instance timed out or
+    /// [`Saga::react()`] returned [`Step::Compensate`].
+    fn compensate(&self, id: &Self::Id, state: &Self::State) -> Vec<Self::Request>;
+
+    /// Returns the time after which a running instance times out
+    /// and is compensated, `None` by default.
+    ///
+    /// The time is measured on the clock of the mediator from the start of the instance.
+    /// Instances restored from a storage keep their deadline,
+    /// so the time the mediator was not running counts as well.
+    fn timeout(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// The outcome of [`Saga::react()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step<Req> {
+    /// Sends the requests and keeps the instance running.
+    Continue(Vec<Req>),
+    /// Sends the requests and ends the instance right away.
+    ///
+    /// Failures of these requests are not compensated, as there is no instance left
+    /// to compensate. Return [`Step::Continue`] and complete the instance
+    /// on a later event instead, if they need to be.
+    Complete(Vec<Req>),
+    /// Ends the instance and sends the requests returned by [`Saga::compensate()`].
+    Compensate,
+}
+
+/// A running instance of a [`Saga`], as persisted to the storage
+/// given to `add_saga_with_storage()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SagaInstance<Id, State> {
+    /// The correlation id of the instance.
+    pub id: Id,
+    /// The state of the instance.
+    pub state: State,
+    /// The point in time the instance times out, if the [`Saga`] has a timeout.
+    pub deadline: Option<SystemTime>,
+}
+
+impl<Id, State> Record for SagaInstance<Id, State>
+where
+    Id: Record,
+    State: Record,
+{
+    fn encode(&self) -> String {
+        let deadline = self
+            .deadline
+            .and_then(|deadline| deadline.duration_since(UNIX_EPOCH).ok())
+            .map(|deadline| deadline.as_millis().to_string())
+            .unwrap_or_default();
+        Self::join(&[&self.id.encode(), &self.state.encode(), &deadline])
+    }
+
+    fn decode(line: &str) -> Option<Self> {
+        match Self::split(line).as_slice() {
+            // Written before deadlines were persisted.
+            [id, state] => Some(SagaInstance {
+                id: Id::decode(id)?,
+                state: State::decode(state)?,
+                deadline: None,
+            }),
+            [id, state, deadline] => Some(SagaInstance {
+                id: Id::decode(id)?,
+                state: State::decode(state)?,
+                deadline: match deadline.as_str() {
+                    "" => None,
+                    millis => Some(UNIX_EPOCH + Duration::from_millis(millis.parse().ok()?)),
+                },
+            }),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) type InstanceStorage<S, Ev> =
+    Box<dyn Storage<SagaInstance<<S as Saga<Ev>>::Id, <S as Saga<Ev>>::State>>>;
+
+/// A follow-up request along with the instance to compensate if it fails,
+/// which is `None` for compensating requests.
+pub(crate) type Pending<S, Ev> = (Option<<S as Saga<Ev>>::Id>, <S as Saga<Ev>>::Request);
+
+/// The states of the running instances along with their deadlines.
+type Instances<S, Ev> = BTreeMap<<S as Saga<Ev>>::Id, (<S as Saga<Ev>>::State, Option<Deadline>)>;
+
+/// The deadline of an instance on the clock of the mediator,
+/// along with the wall-clock time it is persisted as.
+#[derive(Debug, Clone, Copy)]
+struct Deadline {
+    at: Instant,
+    wall: SystemTime,
+}
+
+impl Deadline {
+    fn after(now: Instant, timeout: Duration) -> Self {
+        Self {
+            at: now + timeout,
+            wall: SystemTime::now() + timeout,
+        }
+    }
+
+    /// Maps the persisted `wall` deadline onto the clock of the mediator.
+    fn restore(now: Instant, wall: SystemTime) -> Self {
+        let left = wall
+            .duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO);
+        Self {
+            at: now + left,
+            wall,
+        }
+    }
+}
+
+/// The instances of a [`Saga`] and the requests they are about to send.
+///
+/// It receives the events of the mediator as a [`TimedListener`],
+/// which also compensates timed out instances.
+/// The pending requests are sent by the mediator after every `next()`.
+pub(crate) struct SagaRunner<S, Ev>
+where
+    S: Saga<Ev>,
+{
+    saga: S,
+    instances: Mutex<Instances<S, Ev>>,
+    pending: Mutex<VecDeque<Pending<S, Ev>>>,
+    storage: Option<InstanceStorage<S, Ev>>,
+    restored: Mutex<bool>,
+}
+
+impl<S, Ev> SagaRunner<S, Ev>
+where
+    S: Saga<Ev>,
+{
+    /// Creates a runner, which restores the instances of `storage`
+    /// on the first event or tick.
+    pub(crate) fn new(saga: S, storage: Option<InstanceStorage<S, Ev>>) -> Self {
+        Self {
+            saga,
+            instances: Mutex::new(BTreeMap::new()),
+            pending: Mutex::new(VecDeque::new()),
+            storage,
+            restored: Mutex::new(false),
+        }
+    }
+
+    /// Removes the next pending request.
+    pub(crate) fn pop(&self) -> Option<Pending<S, Ev>> {
+        self.pending.lock().unwrap().pop_front()
+    }
+
+    /// Compensates the instance `id`, as one of its follow-up requests failed,
+    /// and drops its other pending follow-up requests.
+    ///
+    /// Does nothing if the instance already ended, e.g. by [`Step::Complete`],
+    /// whose requests are documented to not be compensated.
+    pub(crate) fn fail(&self, id: S::Id) {
+        let mut instances = self.instances.lock().unwrap();
+        if let Some((state, _)) = instances.remove(&id) {
+            self.pending
+                .lock()
+                .unwrap()
+                .retain(|(pending, _)| pending.as_ref() != Some(&id));
+            self.compensate(&id, &state);
+            self.persist(&instances);
+        }
+    }
+
+    /// Sends all pending requests via `send`, which returns `false` if a request failed.
+    pub(crate) fn drain<F>(&self, send: F)
+    where
+        F: Fn(S::Request) -> bool,
+    {
+        while let Some((id, req)) = self.pop() {
+            if !send(req) {
+                if let Some(id) = id {
+                    self.fail(id);
+                }
+            }
+        }
+    }
+
+    fn compensate(&self, id: &S::Id, state: &S::State) {
+        self.pending.lock().unwrap().extend(
+            self.saga
+                .compensate(id, state)
+                .into_iter()
+                .map(|req| (None, req)),
+        );
+    }
+
+    /// Loads the instances of the storage once.
+    ///
+    /// Instances keep their persisted deadline, those without one,
+    /// e.g. written before deadlines were persisted, time out `timeout` after `now`.
+    fn restore(&self, now: Instant) {
+        let mut restored = self.restored.lock().unwrap();
+        if *restored {
+            return;
+        }
+        *restored = true;
+
+        let loaded = self
+            .storage
+            .as_ref()
+            .and_then(|storage| storage.load().ok())
+            .unwrap_or_default();
+        let timeout = self.saga.timeout();
+        let mut instances = self.instances.lock().unwrap();
+        for SagaInstance {
+            id,
+            state,
+            deadline,
+        } in loaded
+        {
+            let deadline = match (timeout, deadline) {
+                (None, _) => None,
+                (Some(_), Some(wall)) => Some(Deadline::restore(now, wall)),
+                (Some(timeout), None) => Some(Deadline::after(now, timeout)),
+            };
+            instances.insert(id, (state, deadline));
+        }
+    }
+
+    /// Replaces the instances in the storage.
+    ///
+    /// Errors of the storage are ignored, the instances keep running in memory.
+    fn persist(&self, instances: &Instances<S, Ev>) {
+        if let Some(storage) = &self.storage {
+            storage
+                .replace(
+                    instances
+                        .iter()
+                        .map(|(id, (state, deadline))| SagaInstance {
+                            id: id.clone(),
+                            state: state.clone(),
+                            deadline: deadline.map(|deadline| deadline.wall),
+                        })
+                        .collect(),
+                )
+                .ok();
+        }
+    }
+}
+
+impl<S, Ev> TimedListener<Ev> for Arc<SagaRunner<S, Ev>>
+where
+    S: Saga<Ev>,
+{
+    fn on_event(&self, ev: Ev, now: Instant) {
+        self.restore(now);
+        let id = match self.saga.correlate(&ev) {
+            Some(id) => id,
+            None => return,
+        };
+
+        let mut instances = self.instances.lock().unwrap();
+        if !instances.contains_key(&id) {
+            match self.saga.start(&id, &ev) {
+                Some(state) => {
+                    let deadline = self
+                        .saga
+                        .timeout()
+                        .map(|timeout| Deadline::after(now, timeout));
+                    instances.insert(id.clone(), (state, deadline));
+                }
+                None => return,
+            }
+        }
+
+        let (state, _) = instances.get_mut(&id).unwrap();
+        let (requests, ended) = match self.saga.react(state, &ev) {
+            Step::Continue(requests) => (requests, false),
+            Step::Complete(requests) => (requests, true),
+            Step::Compensate => {
+                self.compensate(&id, state);
+                (vec![], true)
+            }
+        };
+        self.pending
+            .lock()
+            .unwrap()
+            .extend(requests.into_iter().map(|req| (Some(id.clone()), req)));
+        if ended {
+            instances.remove(&id);
+        }
+        self.persist(&instances);
+    }
+
+    fn tick(&self, now: Instant) -> bool {
+        self.restore(now);
+        let mut instances = self.instances.lock().unwrap();
+        let timed_out: Vec<S::Id> = instances
+            .iter()
+            .filter(|(_, (_, deadline))| matches!(deadline, Some(deadline) if deadline.at <= now))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &timed_out {
+            if let Some((state, _)) = instances.remove(id) {
+                self.compensate(id, &state);
+            }
+        }
+        if !timed_out.is_empty() {
+            self.persist(&instances);
+        }
+        !timed_out.is_empty()
+    }
+
+    fn next_due(&self) -> Option<Instant> {
+        self.instances
+            .lock()
+            .unwrap()
+            .values()
+            .filter_map(|(_, deadline)| deadline.map(|deadline| deadline.at))
+            .min()
+    }
+
+    fn flush(&self) -> bool {
+        false
+    }
+
+    fn idle(&self) -> Option<Idle> {
+        None
+    }
+}
+
+pub(crate) type Driver<M> = Box<dyn Fn(&M) + Send>;
+
+/// Sends the pending requests of the sagas of a mediator `M` after every `next()`.
+pub(crate) struct Sagas<M> {
+    pub(crate) drivers: Vec<Driver<M>>,
+}
+
+impl<M> Sagas<M> {
+    pub(crate) fn new() -> Self {
+        Self { drivers: vec![] }
+    }
+
+    pub(crate) fn run(&self, mediator: &M) {
+        for driver in &self.drivers {
+            driver(mediator);
+        }
+    }
+}
+
+impl<M> Debug for Sagas<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sagas")
+            .field("sagas", &self.drivers.len())
+            .finish()
+    }
+}
+
+/// Sends the pending requests of the sagas of an async mediator `M` after every `next()`.
+#[cfg(feature = "async")]
+pub(crate) struct AsyncSagas<M> {
+    pub(crate) drivers: Vec<AsyncDriver<M>>,
+}
+
+#[cfg(feature = "async")]
+pub(crate) type AsyncDriver<M> = Box<dyn for<'a> Fn(&'a M) -> BoxFuture<'a, ()> + Send + Sync>;
+
+#[cfg(feature = "async")]
+impl<M> AsyncSagas<M> {
+    pub(crate) fn new() -> Self {
+        Self { drivers: vec![] }
+    }
+
+    pub(crate) async fn run(&self, mediator: &M) {
+        for driver in &self.drivers {
+            driver(mediator).await;
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<M> Debug for AsyncSagas<M> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AsyncSagas")
+            .field("sagas", &self.drivers.len())
+            .finish()
+    }
+}
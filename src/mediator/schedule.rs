@@ -0,0 +1,195 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Debug,
+    time::{Duration, Instant},
+};
+
+type Factory<Ev> = Box<dyn FnMut() -> Ev + Send>;
+
+/// The id of an event scheduled via `publish_after()`,
+/// `publish_at()` or `publish_every()`.
+///
+/// It is used to cancel the event via `cancel_scheduled()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ScheduledId(u64);
+
+/// The events scheduled for publishing, ordered by due time.
+///
+/// Due events are published by the mediator on `next()`,
+/// based on the [`crate::clock::Clock`] of the mediator.
+pub(crate) struct Schedule<Ev> {
+    entries: BTreeMap<(Due, ScheduledId), Entry<Ev>>,
+    next_id: u64,
+    /// Recurring events taken by [`Schedule::take_due()`] and not rescheduled yet.
+    taken: HashSet<ScheduledId>,
+    /// Taken recurring events that were cancelled meanwhile.
+    cancelled: HashSet<ScheduledId>,
+}
+
+/// The point in time a scheduled event is due.
+///
+/// Events due later than an [`Instant`] can represent are never due,
+/// but stay scheduled until they are cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Due {
+    At(Instant),
+    Never,
+}
+
+impl Due {
+    fn after(at: Instant, delay: Duration) -> Self {
+        at.checked_add(delay).map_or(Due::Never, Due::At)
+    }
+}
+
+enum Entry<Ev> {
+    Once(Ev),
+    Every {
+        factory: Factory<Ev>,
+        interval: Duration,
+    },
+}
+
+/// An event taken from the [`Schedule`] because it is due.
+pub(crate) enum Taken<Ev> {
+    Once(Ev),
+    Every(Recurring<Ev>),
+}
+
+/// A recurring event taken from the [`Schedule`], to be passed back via
+/// [`Schedule::reschedule()`] once its event was created via [`Recurring::fire()`].
+pub(crate) struct Recurring<Ev> {
+    at: Due,
+    id: ScheduledId,
+    factory: Factory<Ev>,
+    interval: Duration,
+}
+
+impl<Ev> Recurring<Ev> {
+    /// Creates the event of this tick.
+    pub(crate) fn fire(&mut self) -> Ev {
+        (self.factory)()
+    }
+}
+
+impl<Ev> Schedule<Ev> {
+    pub(crate) fn new() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+            next_id: 0,
+            taken: HashSet::new(),
+            cancelled: HashSet::new(),
+        }
+    }
+
+    /// Schedules `ev` to be published `delay` after `now`.
+    pub(crate) fn after(&mut self, now: Instant, delay: Duration, ev: Ev) -> ScheduledId {
+        self.insert(Due::after(now, delay), Entry::Once(ev))
+    }
+
+    /// Schedules `ev` to be published at `due`.
+    pub(crate) fn once(&mut self, due: Instant, ev: Ev) -> ScheduledId {
+        self.insert(Due::At(due), Entry::Once(ev))
+    }
+
+    /// Schedules an event created by `factory` to be published
+    /// every `interval`, first one `interval` after `now`.
+    pub(crate) fn every<F>(&mut self, now: Instant, interval: Duration, factory: F) -> ScheduledId
+    where
+        F: FnMut() -> Ev + Send + 'static,
+    {
+        let interval = interval.max(Duration::from_nanos(1));
+        self.insert(
+            Due::after(now, interval),
+            Entry::Every {
+                factory: Box::new(factory),
+                interval,
+            },
+        )
+    }
+
+    /// Removes the event with `id`, returns `false` if it is not scheduled.
+    pub(crate) fn cancel(&mut self, id: ScheduledId) -> bool {
+        if self.taken.contains(&id) {
+            return self.cancelled.insert(id);
+        }
+        let key = self.entries.keys().find(|(_, other)| *other == id).copied();
+        key.and_then(|key| self.entries.remove(&key)).is_some()
+    }
+
+    /// Returns the point in time the next event is due, if any.
+    pub(crate) fn next_due(&self) -> Option<Instant> {
+        match self.entries.keys().next() {
+            Some((Due::At(due), _)) => Some(*due),
+            _ => None,
+        }
+    }
+
+    /// Removes all events due at `now` and returns them in order.
+    ///
+    /// Recurring events are returned as [`Taken::Every`], so that the caller creates
+    /// their events without holding the schedule, and passes them back via [`Schedule::reschedule()`].
+    pub(crate) fn take_due(&mut self, now: Instant) -> Vec<Taken<Ev>> {
+        let mut due = vec![];
+        while let Some(entry) = self.entries.first_entry() {
+            if entry.key().0 > Due::At(now) {
+                break;
+            }
+            let ((at, id), entry) = entry.remove_entry();
+            due.push(match entry {
+                Entry::Once(ev) => Taken::Once(ev),
+                Entry::Every { factory, interval } => {
+                    self.taken.insert(id);
+                    Taken::Every(Recurring {
+                        at,
+                        id,
+                        factory,
+                        interval,
+                    })
+                }
+            });
+        }
+        due
+    }
+
+    /// Reschedules the recurring event taken by [`Schedule::take_due()`] after its interval.
+    /// If it fell behind by more than an interval, missed ticks are skipped.
+    /// Events cancelled in the meantime are not rescheduled.
+    pub(crate) fn reschedule(&mut self, recurring: Recurring<Ev>, now: Instant) {
+        let Recurring {
+            at,
+            id,
+            factory,
+            interval,
+        } = recurring;
+        self.taken.remove(&id);
+        if self.cancelled.remove(&id) {
+            return;
+        }
+        let mut next = match at {
+            Due::At(at) => Due::after(at, interval),
+            Due::Never => Due::Never,
+        };
+        if next <= Due::At(now) {
+            next = Due::after(now, interval);
+        }
+        self.entries
+            .insert((next, id), Entry::Every { factory, interval });
+    }
+
+    fn insert(&mut self, due: Due, entry: Entry<Ev>) -> ScheduledId {
+        let id = ScheduledId(self.next_id);
+        self.next_id += 1;
+        self.entries.insert((due, id), entry);
+        id
+    }
+}
+
+impl<Ev> Debug for Schedule<Ev> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Schedule")
+            .field("scheduled", &self.entries.len())
+            .field("next_due", &self.next_due())
+            .finish()
+    }
+}
@@ -0,0 +1,280 @@
+use std::{
+    fmt::Debug,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    marker::PhantomData,
+    path::PathBuf,
+    process,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+/// A [`Storage`] persists items of type `T` for mediators,
+/// e.g. the dead letters of a [`crate::dead_letter::DeadLetter`] queue.
+///
+/// It is implemented by the [`MemoryStorage`] and the [`FileStorage`],
+/// but can be implemented for any other backend as well.
+pub trait Storage<T>: Debug + Send + Sync {
+    /// Appends `item` to the storage.
+    ///
+    /// It is passed by reference, so the caller keeps it if appending fails.
+    fn append(&self, item: &T) -> io::Result<()>;
+
+    /// Loads all items in the order they were appended.
+    fn load(&self) -> io::Result<Vec<T>>;
+
+    /// Replaces all items with `items`.
+    fn replace(&self, items: Vec<T>) -> io::Result<()>;
+}
+
+/// A [`Record`] can be written to and read from a single line of text.
+///
+/// It is required by the [`FileStorage`].
+/// Use [`Record::join()`] and [`Record::split()`] to encode multiple fields.
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mediator_sys::storage::Record;
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Order {
+///     id: u32,
+///     item: String,
+/// }
+///
+/// impl Record for Order {
+///     fn encode(&self) -> String {
+///         Self::join(&[&self.id.to_string(), &self.item])
+///     }
+///
+///     fn decode(line: &str) -> Option<Self> {
+///         match Self::split(line).as_slice() {
+///             [id, item] => Some(Order { id: id.parse().ok()?, item: item.clone() }),
+///             _ => None,
+///         }
+///     }
+/// }
+///
+/// let order = Order { id: 1, item: String::from("tea\tcups") };
+/// assert_eq!(Order::decode(&order.encode()), Some(order));
+/// ```
+pub trait Record: Sized {
+    /// Encodes the record as text.
+    fn encode(&self) -> String;
+
+    /// Decodes a record from text, returns `None` if it is malformed.
+    fn decode(line: &str) -> Option<Self>;
+
+    /// Joins `fields` with tabs, escaping tabs, newlines and backslashes.
+    fn join(fields: &[&str]) -> String {
+        fields
+            .iter()
+            .map(|field| escape(field, '\t'))
+            .collect::<Vec<_>>()
+            .join("\t")
+    }
+
+    /// Splits a line that was joined by [`Record::join()`] into its fields.
+    fn split(line: &str) -> Vec<String> {
+        split_escaped(line, '\t')
+    }
+}
+
+impl Record for String {
+    fn encode(&self) -> String {
+        self.clone()
+    }
+
+    fn decode(line: &str) -> Option<Self> {
+        Some(line.to_owned())
+    }
+}
+
+/// A [`Storage`] that keeps its items in memory.
+#[derive(Debug)]
+pub struct MemoryStorage<T> {
+    items: Mutex<Vec<T>>,
+}
+
+impl<T> MemoryStorage<T> {
+    /// Creates an empty [`MemoryStorage`].
+    pub fn new() -> Self {
+        Self {
+            items: Mutex::new(vec![]),
+        }
+    }
+}
+
+impl<T> Default for MemoryStorage<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Storage<T> for MemoryStorage<T>
+where
+    T: Debug + Clone + Send,
+{
+    fn append(&self, item: &T) -> io::Result<()> {
+        self.items.lock().unwrap().push(item.clone());
+        Ok(())
+    }
+
+    fn load(&self) -> io::Result<Vec<T>> {
+        Ok(self.items.lock().unwrap().clone())
+    }
+
+    fn replace(&self, items: Vec<T>) -> io::Result<()> {
+        *self.items.lock().unwrap() = items;
+        Ok(())
+    }
+}
+
+/// A [`Storage`] that keeps every item as a line in a file.
+///
+/// The file is created on the first write.
+/// Items must implement [`Record`].
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```
+/// use mediator_sys::storage::{FileStorage, Storage};
+///
+/// let path = std::env::temp_dir().join("mediator-sys-file-storage-doc.log");
+/// let storage = FileStorage::<String>::new(&path);
+///
+/// storage.replace(vec![]).unwrap();
+/// storage.append(&String::from("first\nline")).unwrap();
+/// assert_eq!(storage.load().unwrap(), vec![String::from("first\nline")]);
+///
+/// std::fs::remove_file(path).ok();
+/// ```
+pub struct FileStorage<T> {
+    path: PathBuf,
+    lock: Mutex<()>,
+    _items: PhantomData<fn() -> T>,
+}
+
+impl<T> FileStorage<T> {
+    /// Creates a [`FileStorage`] writing to the file at `path`.
+    pub fn new<P>(path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+            _items: PhantomData,
+        }
+    }
+
+    /// Returns the path of the file.
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// Returns a path next to the file that no other [`FileStorage`] writes to.
+    fn temp_path(&self) -> PathBuf {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let mut name = self.path.file_name().unwrap_or_default().to_os_string();
+        name.push(format!(
+            ".{}.{}.tmp",
+            process::id(),
+            NEXT.fetch_add(1, Ordering::SeqCst)
+        ));
+        self.path.with_file_name(name)
+    }
+}
+
+impl<T> Storage<T> for FileStorage<T>
+where
+    T: Record,
+{
+    fn append(&self, item: &T) -> io::Result<()> {
+        let _lock = self.lock.lock().unwrap();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", escape(&item.encode(), '\n'))
+    }
+
+    fn load(&self) -> io::Result<Vec<T>> {
+        let _lock = self.lock.lock().unwrap();
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err),
+        };
+
+        BufReader::new(file)
+            .lines()
+            .map(|line| {
+                let line = split_escaped(&line?, '\n').concat();
+                T::decode(&line)
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "malformed record"))
+            })
+            .collect()
+    }
+
+    fn replace(&self, items: Vec<T>) -> io::Result<()> {
+        let _lock = self.lock.lock().unwrap();
+        let tmp = self.temp_path();
+        {
+            let mut file = File::create(&tmp)?;
+            for item in items {
+                writeln!(file, "{}", escape(&item.encode(), '\n'))?;
+            }
+            file.sync_all()?;
+        }
+        fs::rename(tmp, &self.path)
+    }
+}
+
+impl<T> Debug for FileStorage<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileStorage")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+/// Escapes backslashes, newlines and `separator` in `field`.
+fn escape(field: &str, separator: char) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' if separator == '\t' => escaped.push_str("\\t"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Splits `line` at unescaped occurrences of `separator` and unescapes the fields.
+fn split_escaped(line: &str, separator: char) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => fields.last_mut().unwrap().push('\n'),
+                Some('t') => fields.last_mut().unwrap().push('\t'),
+                Some(c) => fields.last_mut().unwrap().push(c),
+                None => fields.last_mut().unwrap().push('\\'),
+            },
+            c if c == separator => fields.push(String::new()),
+            c => fields.last_mut().unwrap().push(c),
+        }
+    }
+    fields
+}
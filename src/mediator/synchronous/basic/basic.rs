@@ -1,8 +1,34 @@
-use std::sync::mpsc::{Receiver, Sender, TryRecvError};
+use std::{
+    io,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        mpsc::{channel, Receiver, RecvError, TryRecvError},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
 
 use core::fmt::Debug;
 
 use super::*;
+use crate::mediator::{
+    bridge::Bridges,
+    cache::{Lookup, QueryCaches},
+    clock::{Clock, SystemClock},
+    dead_letter::{DeadLetter, DeadLetterReason, DeadLetters},
+    hierarchy::{Envelope, Hierarchy, Hop, MediatorLink},
+    history::{Action, CommandHistory, History, Replay, Undoable},
+    idempotency::{Deduplication, Idempotent},
+    inbox::Inbox,
+    listener::{Coalesce, Idle, TimedListener},
+    outbox::{Outbox, Transaction},
+    priority::{EventQueue, Priority},
+    retry::{self, RetryPolicies},
+    saga::Sagas,
+    schedule::{Schedule, ScheduledId, Taken},
+    storage::Record,
+    topic::{Routed, TopicTrie},
+};
 
 /// Basic mediator for synchronous environments with events of type `Ev`.
 ///
@@ -56,8 +82,191 @@ pub struct BasicMediator<Ev>
 where
     Ev: Debug,
 {
-    pub(crate) channel: (Sender<Ev>, Receiver<Ev>),
+    pub(crate) channel: (Inbox<Ev>, Receiver<Envelope<Ev>>),
     pub(crate) listener: Vec<Box<dyn Listener<Ev>>>,
+    pub(crate) topics: TopicTrie<Box<dyn Listener<Ev>>>,
+    pub(crate) timed: Vec<Box<dyn TimedListener<Ev>>>,
+    pub(crate) coalesce: Option<Box<dyn Coalesce<Ev>>>,
+    pub(crate) queue: Mutex<EventQueue<Ev>>,
+    pub(crate) retry: RetryPolicies,
+    pub(crate) clock: Arc<dyn Clock>,
+    pub(crate) dead_letters: DeadLetters<Ev>,
+    pub(crate) schedule: Mutex<Schedule<Ev>>,
+    pub(crate) hierarchy: Hierarchy<Ev>,
+    pub(crate) bridges: Bridges<Ev>,
+    pub(crate) outbox: Outbox<Ev>,
+    pub(crate) sagas: Sagas<BasicMediator<Ev>>,
+    pub(crate) history: Mutex<History<Action<BasicMediator<Ev>>>>,
+    pub(crate) dedup: Deduplication<Ev>,
+    pub(crate) caches: QueryCaches<Ev>,
+}
+
+impl<Ev> BasicMediator<Ev>
+where
+    Ev: Debug,
+{
+    /// Creates an empty [`BasicMediator`], which is filled by its builder.
+    pub(crate) fn new() -> Self {
+        Self {
+            channel: {
+                let (sender, receiver) = channel();
+                (Inbox::new(sender), receiver)
+            },
+            listener: vec![],
+            topics: TopicTrie::new(),
+            timed: vec![],
+            coalesce: None,
+            queue: Mutex::new(EventQueue::new()),
+            retry: RetryPolicies::default(),
+            clock: Arc::new(SystemClock),
+            dead_letters: DeadLetters::new(),
+            schedule: Mutex::new(Schedule::new()),
+            hierarchy: Hierarchy::new(),
+            bridges: Bridges::new(),
+            outbox: Outbox::new(),
+            sagas: Sagas::new(),
+            history: Mutex::new(History::new()),
+            dedup: Deduplication::new(),
+            caches: QueryCaches::new(),
+        }
+    }
+
+    /// Sends `envelope` to the channel, or to the outbox
+    /// of the request that is currently being handled, if it is transactional.
+    /// Cached query responses are invalidated once the event is sent to the channel.
+    pub(crate) fn send_envelope(&self, envelope: Envelope<Ev>) {
+        if let Some(envelope) = self.outbox.stash(envelope) {
+            self.caches.invalidate(&envelope.ev);
+            self.channel.0.send(envelope).ok();
+        }
+    }
+
+    /// Commits the events of `transaction` if the request succeeded.
+    pub(crate) fn commit(&self, transaction: Transaction<Ev>, succeeded: bool) {
+        if succeeded {
+            for envelope in transaction.commit() {
+                self.send_envelope(envelope);
+            }
+        }
+    }
+
+    /// Handles a request via `f` within a transaction, if requests are transactional.
+    fn transact<E, F>(&self, f: F) -> Result<(), E>
+    where
+        F: FnOnce() -> Result<(), E>,
+    {
+        match self.outbox.begin() {
+            Some(transaction) => {
+                let result = transaction.run(f);
+                self.commit(transaction, result.is_ok());
+                result
+            }
+            None => f(),
+        }
+    }
+}
+
+impl<Ev> BasicMediator<Ev>
+where
+    Ev: Debug + Clone,
+{
+    /// Calls every listener, and every topic listener subscribed to `topic`,
+    /// with a clone of `ev`.
+    ///
+    /// If the dead-letter queue is enabled, the event is stored in it
+    /// when there are no listeners, and so is every clone whose listener panicked.
+    pub(crate) fn dispatch(&self, ev: Ev, topic: Option<&str>) {
+        let now = self.clock.now();
+        let guarded = self.dead_letters.is_enabled();
+        let subscribed = topic
+            .map(|topic| self.topics.matches(topic))
+            .unwrap_or_default();
+        if guarded && self.listener.is_empty() && self.timed.is_empty() && subscribed.is_empty() {
+            self.dead_letters.event(ev, DeadLetterReason::NoListeners);
+            return;
+        }
+
+        for listener in self.listener.iter().chain(subscribed) {
+            self.call(guarded, &ev, listener);
+        }
+        for timed in self.timed.iter() {
+            self.call(guarded, &ev, |ev| timed.on_event(ev, now));
+        }
+    }
+
+    /// Calls `f` with a clone of `ev` and stores the event
+    /// in the dead-letter queue if `f` panics while `guarded`.
+    fn call<F>(&self, guarded: bool, ev: &Ev, f: F)
+    where
+        F: FnOnce(Ev),
+    {
+        if !guarded {
+            return f(ev.clone());
+        }
+        let cloned = ev.clone();
+        if catch_unwind(AssertUnwindSafe(|| f(cloned))).is_err() {
+            self.dead_letters
+                .event(ev.clone(), DeadLetterReason::ListenerPanicked);
+        }
+    }
+
+    /// Receives the next event from the channel.
+    ///
+    /// All queued events are drained from the channel into the [`EventQueue`] first,
+    /// so the pending event with the highest [`Priority`] is returned.
+    fn receive(&self) -> Result<Routed<Ev>, TryRecvError> {
+        let mut queue = self.queue.lock().unwrap();
+        while let Ok(envelope) = self.channel.1.try_recv() {
+            self.enqueue(&mut queue, envelope);
+        }
+        queue.pop().ok_or(TryRecvError::Empty)
+    }
+
+    /// Pushes an event received from the channel to the [`EventQueue`].
+    ///
+    /// If events are coalesced, they are merged with the already buffered ones.
+    /// If events are deduplicated, duplicates are dropped right away,
+    /// and so are events that came back to the mediator over bridges or links.
+    /// Events from other mediators invalidate cached query responses once received here.
+    fn enqueue(&self, queue: &mut EventQueue<Ev>, mut envelope: Envelope<Ev>) {
+        if !envelope.visit(self.hierarchy.id) || !self.dedup.admit(&envelope.ev) {
+            return;
+        }
+        if envelope.hop != Hop::Local {
+            self.caches.invalidate(&envelope.ev);
+        }
+        self.hierarchy.propagate(&envelope);
+        self.bridges.forward(&envelope);
+        let Envelope {
+            ev,
+            topic,
+            priority,
+            ..
+        } = envelope;
+        queue.push((ev, topic), priority, self.coalesce.as_deref());
+    }
+
+    /// Returns the point in time the next scheduled or debounced event is due, if any.
+    pub(crate) fn next_due(&self) -> Option<Instant> {
+        let scheduled = self.schedule.lock().unwrap().next_due();
+        self.timed
+            .iter()
+            .filter_map(|timed| timed.next_due())
+            .chain(scheduled)
+            .min()
+    }
+
+    /// Emits the events buffered by timed listeners and returns the [`Idle`]s
+    /// to wait for the events that are processed in the background.
+    pub(crate) fn flush_timed(&self) -> Vec<Idle> {
+        self.timed
+            .iter()
+            .filter_map(|timed| {
+                timed.flush();
+                timed.idle()
+            })
+            .collect()
+    }
 }
 
 impl<Ev> SyncMediatorInternal<Ev> for BasicMediator<Ev>
@@ -95,7 +304,163 @@ where
     /// }
     ///
     fn publish(&self, event: Ev) {
-        self.channel.0.send(event).ok();
+        self.send_envelope(Envelope::local(event, None, None));
+    }
+}
+
+impl<Ev> SyncMediatorInternalPriority<Ev> for BasicMediator<Ev>
+where
+    Ev: Debug,
+{
+    /// Publishes an event `Ev` with a [`Priority`].
+    ///
+    /// The event is dispatched by [`BasicMediator::next()`] before all pending events
+    /// of a lower priority, and after the pending events of the same or a higher priority.
+    /// The given priority overrides the one assigned by
+    /// [`super::BasicBuilder::prioritize()`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone, PartialEq)]
+    /// enum MyEvent {
+    ///     Routine,
+    ///     Shutdown,
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .add_listener(|ev| {
+    ///         /* Your listening logic */
+    ///     })
+    ///     .build();
+    ///
+    /// mediator.publish(MyEvent::Routine);
+    /// mediator.publish_with_priority(MyEvent::Shutdown, Priority::HIGH);
+    ///
+    /// // Dispatches `MyEvent::Shutdown` first.
+    /// mediator.next().ok();
+    ///
+    fn publish_with_priority(&self, event: Ev, priority: Priority) {
+        self.send_envelope(Envelope::local(event, None, Some(priority)));
+    }
+}
+
+impl<Ev> SyncMediatorInternalTopic<Ev> for BasicMediator<Ev>
+where
+    Ev: Debug,
+{
+    /// Publishes an event `Ev` to a `topic`.
+    ///
+    /// Topics consist of segments separated by dots, e.g. `orders.eu.created`.
+    /// Besides all listeners, the event is dispatched to every listener subscribed
+    /// to a matching pattern via [`super::BasicBuilder::add_topic_listener()`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     OrderCreated(u32),
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .add_topic_listener("orders.*.created", |ev| {
+    ///         /* Your listening logic */
+    ///     })
+    ///     .build();
+    ///
+    /// mediator.publish_to("orders.eu.created", MyEvent::OrderCreated(1));
+    /// mediator.next().ok();
+    ///
+    fn publish_to(&self, topic: &str, event: Ev) {
+        self.send_envelope(Envelope::local(event, Some(topic.to_owned()), None));
+    }
+}
+
+impl<Ev> SyncMediatorInternalOutbox<Ev> for BasicMediator<Ev>
+where
+    Ev: Debug,
+{
+    /// Publishes an event `Ev` right away, even from within a transactional handler.
+    ///
+    /// Requests sent via [`SyncMediatorInternalTryHandle::try_send()`] to a mediator
+    /// built with [`super::BasicBuilder::transactional()`] buffer the events
+    /// published by their handler in an outbox, until the handler succeeded.
+    /// Events published via this method bypass the outbox,
+    /// so they are delivered even if the handler fails, e.g. progress or audit events.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Attempted,
+    ///     Transferred,
+    /// }
+    ///
+    /// struct Transfer;
+    ///
+    /// impl TryRequestHandler<Transfer, MyEvent> for BasicMediator<MyEvent> {
+    ///     type Error = String;
+    ///
+    ///     fn try_handle(&self, _req: Transfer) -> Result<(), String> {
+    ///         self.publish_immediately(MyEvent::Attempted);
+    ///         self.publish(MyEvent::Transferred);
+    ///         Err(String::from("insufficient funds"))
+    ///     }
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .transactional()
+    ///     .build();
+    ///
+    /// assert!(mediator.try_send(Transfer).is_err());
+    ///
+    /// // Only `MyEvent::Attempted` is dispatched.
+    /// mediator.next().ok();
+    /// assert!(mediator.next().is_err());
+    ///
+    fn publish_immediately(&self, event: Ev) {
+        self.caches.invalidate(&event);
+        self.channel.0.send(Envelope::local(event, None, None)).ok();
+    }
+}
+
+impl<Ev> SyncMediatorInternalHierarchy<Ev> for BasicMediator<Ev>
+where
+    Ev: Debug,
+{
+    /// Returns a [`MediatorLink`] to link child mediators to the [`BasicMediator`].
+    ///
+    /// Children are linked via `parent()` on their builders, e.g.
+    /// [`super::BasicBuilder::parent()`], which takes the parent mediator itself.
+    ///
+    fn link(&self) -> MediatorLink<Ev> {
+        MediatorLink {
+            sender: self.channel.0.clone(),
+            children: self.hierarchy.children.clone(),
+            projections: self.bridges.projections.clone(),
+        }
+    }
+
+    /// Returns the number of child mediators linked to the [`BasicMediator`].
+    ///
+    /// Children are unlinked when they are dropped.
+    ///
+    fn child_count(&self) -> usize {
+        self.hierarchy.child_count()
     }
 }
 
@@ -116,6 +481,565 @@ where
     }
 }
 
+impl<Ev> SyncMediatorInternalCqrs<Ev> for BasicMediator<Ev>
+where
+    Ev: Debug,
+{
+    /// Execute a command of type `Cmd` with the mediator.
+    ///
+    /// The command will be processed internally by [`CommandHandler::handle()`].
+    /// This is why it is required to implement [`CommandHandler`] for [`BasicMediator`].
+    ///
+    fn execute<Cmd>(&self, cmd: Cmd)
+    where
+        Self: CommandHandler<Cmd, Ev>,
+    {
+        <Self as CommandHandler<Cmd, Ev>>::handle(self, cmd);
+    }
+
+    /// Send a query of type `Q` to the mediator and return its response.
+    ///
+    /// The query will be answered by [`QueryHandler::handle()`].
+    /// This is why it is required to implement [`QueryHandler`] for [`BasicMediator`].
+    /// The query handler is given a [`QueryContext`] instead of the mediator,
+    /// so it can answer other queries, but cannot publish events.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    /// use std::sync::atomic::{AtomicU32, Ordering};
+    ///
+    /// static BALANCE: AtomicU32 = AtomicU32::new(0);
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Deposited(u32),
+    /// }
+    ///
+    /// struct Deposit(u32);
+    ///
+    /// struct Balance;
+    ///
+    /// struct Doubled;
+    ///
+    /// impl CommandHandler<Deposit, MyEvent> for BasicMediator<MyEvent> {
+    ///     fn handle(&self, cmd: Deposit) {
+    ///         BALANCE.fetch_add(cmd.0, Ordering::SeqCst);
+    ///         self.publish(MyEvent::Deposited(cmd.0));
+    ///     }
+    /// }
+    ///
+    /// impl QueryHandler<Balance, MyEvent> for BasicMediator<MyEvent> {
+    ///     type Response = u32;
+    ///
+    ///     fn handle(_: Balance, _: &QueryContext<'_, Self>) -> u32 {
+    ///         BALANCE.load(Ordering::SeqCst)
+    ///     }
+    /// }
+    ///
+    /// impl QueryHandler<Doubled, MyEvent> for BasicMediator<MyEvent> {
+    ///     type Response = u32;
+    ///
+    ///     fn handle(_: Doubled, cx: &QueryContext<'_, Self>) -> u32 {
+    ///         cx.query(Balance) * 2
+    ///     }
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder().build();
+    ///
+    /// mediator.execute(Deposit(20));
+    /// mediator.execute(Deposit(22));
+    ///
+    /// assert_eq!(mediator.query(Balance), 42);
+    /// assert_eq!(mediator.query(Doubled), 84);
+    ///
+    fn query<Q>(&self, query: Q) -> <Self as QueryHandler<Q, Ev>>::Response
+    where
+        Self: QueryHandler<Q, Ev>,
+        Q: 'static,
+    {
+        match self.caches.lookup(&query, self.clock.now()) {
+            Lookup::Hit(response) => response,
+            Lookup::Miss(miss) => {
+                let response =
+                    <Self as QueryHandler<Q, Ev>>::handle(query, &QueryContext::new(self));
+                self.caches.store(miss, &response, self.clock.now());
+                response
+            }
+            Lookup::Uncached => {
+                <Self as QueryHandler<Q, Ev>>::handle(query, &QueryContext::new(self))
+            }
+        }
+    }
+}
+
+impl<'a, Ev> QueryContext<'a, BasicMediator<Ev>>
+where
+    Ev: Debug,
+{
+    /// Answer another query of type `Q` while answering a query.
+    ///
+    /// See [`SyncMediatorInternalCqrs::query()`].
+    ///
+    pub fn query<Q>(&self, query: Q) -> <BasicMediator<Ev> as QueryHandler<Q, Ev>>::Response
+    where
+        BasicMediator<Ev>: QueryHandler<Q, Ev>,
+        Q: 'static,
+    {
+        self.mediator.query(query)
+    }
+}
+
+impl<Ev> SyncMediatorInternalTryHandle<Ev> for BasicMediator<Ev>
+where
+    Ev: Debug,
+{
+    /// Send a request of type `Req` to the mediator.
+    ///
+    /// The request will be processed internally by [`TryRequestHandler::try_handle()`].
+    /// This is why it is required to implement [`TryRequestHandler`] for [`BasicMediator`].
+    /// The result of the handler is returned.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     One,
+    ///     Two
+    /// }
+    ///
+    /// struct Request(u32);
+    ///
+    /// impl TryRequestHandler<Request, MyEvent> for BasicMediator<MyEvent> {
+    ///     type Error = String;
+    ///
+    ///     fn try_handle(&self, req: Request) -> Result<(), String> {
+    ///         match req.0 {
+    ///             1 => self.publish(MyEvent::One),
+    ///             2 => self.publish(MyEvent::Two),
+    ///             n => return Err(format!("unknown request {}", n)),
+    ///         };
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder().build();
+    ///
+    /// assert!(mediator.try_send(Request(1)).is_ok());
+    /// assert!(mediator.try_send(Request(3)).is_err());
+    ///
+    fn try_send<Req>(&self, req: Req) -> Result<(), <Self as TryRequestHandler<Req, Ev>>::Error>
+    where
+        Self: TryRequestHandler<Req, Ev>,
+    {
+        self.transact(|| <Self as TryRequestHandler<Req, Ev>>::try_handle(self, req))
+    }
+}
+
+impl<Ev> SyncMediatorInternalIdempotency<Ev> for BasicMediator<Ev>
+where
+    Ev: Debug,
+{
+    /// Send an [`Idempotent`] request of type `Req` to the mediator,
+    /// unless a request of the same type with the same key was already handled.
+    ///
+    /// The request will be processed internally by [`RequestHandler::handle()`].
+    /// Requests are only deduplicated if enabled via
+    /// [`super::BasicBuilder::deduplicate_requests()`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Paid(u32),
+    /// }
+    ///
+    /// struct Pay(u32);
+    ///
+    /// impl Idempotent for Pay {
+    ///     fn idempotency_key(&self) -> Option<String> {
+    ///         Some(self.0.to_string())
+    ///     }
+    /// }
+    ///
+    /// impl RequestHandler<Pay, MyEvent> for BasicMediator<MyEvent> {
+    ///     fn handle(&self, req: Pay) {
+    ///         self.publish(MyEvent::Paid(req.0));
+    ///     }
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .deduplicate_requests(1000)
+    ///     .build();
+    ///
+    /// mediator.send_idempotent(Pay(1));
+    /// // The duplicate is not handled.
+    /// mediator.send_idempotent(Pay(1));
+    ///
+    /// assert!(mediator.next().is_ok());
+    /// assert!(mediator.next().is_err());
+    ///
+    fn send_idempotent<Req>(&self, req: Req)
+    where
+        Req: Idempotent,
+        Self: RequestHandler<Req, Ev>,
+    {
+        match self.dedup.key(&req) {
+            Some(key) if self.dedup.cached::<()>(&key).is_some() => {}
+            Some(key) => {
+                self.send(req);
+                self.dedup.record(key, ());
+            }
+            None => self.send(req),
+        }
+    }
+
+    /// Send an [`Idempotent`] request of type `Req` to the mediator,
+    /// unless a request of the same type with the same key was already handled.
+    ///
+    /// The request will be processed internally by [`TryRequestHandler::try_handle()`].
+    /// A successful result is cached and returned for duplicates,
+    /// whereas a failed request is handled again when it is sent again with the same key.
+    /// Requests are only deduplicated if enabled via
+    /// [`super::BasicBuilder::deduplicate_requests()`].
+    ///
+    fn try_send_idempotent<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), <Self as TryRequestHandler<Req, Ev>>::Error>
+    where
+        Req: Idempotent,
+        Self: TryRequestHandler<Req, Ev>,
+        <Self as TryRequestHandler<Req, Ev>>::Error: Clone + Send + Sync + 'static,
+    {
+        let key = match self.dedup.key(&req) {
+            Some(key) => key,
+            None => return self.try_send(req),
+        };
+        if let Some(outcome) = self.dedup.cached(&key) {
+            return outcome;
+        }
+        let outcome = self.try_send(req);
+        if outcome.is_ok() {
+            self.dedup.record(key, outcome.clone());
+        }
+        outcome
+    }
+}
+
+impl<Ev> SyncMediatorInternalHistory<Ev> for BasicMediator<Ev>
+where
+    Ev: Debug + 'static,
+{
+    /// Send an [`Undoable`] request of type `Req` to the mediator
+    /// and record it in the command history.
+    ///
+    /// The request will be processed internally by [`RequestHandler::handle()`].
+    /// It is only recorded if the history was enabled via
+    /// [`super::BasicBuilder::command_history()`].
+    /// Undoing it sends its [`Undoable::inverse()`], redoing it sends it again.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Moved(i32),
+    /// }
+    ///
+    /// #[derive(Clone)]
+    /// struct Move(i32);
+    ///
+    /// impl Undoable for Move {
+    ///     type Inverse = Move;
+    ///
+    ///     fn inverse(&self) -> Move {
+    ///         Move(-self.0)
+    ///     }
+    /// }
+    ///
+    /// impl RequestHandler<Move, MyEvent> for BasicMediator<MyEvent> {
+    ///     fn handle(&self, req: Move) {
+    ///         self.publish(MyEvent::Moved(req.0));
+    ///     }
+    /// }
+    ///
+    /// let position = Arc::new(Mutex::new(0));
+    /// let cloned = position.clone();
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .add_listener(move |MyEvent::Moved(by)| *cloned.lock().unwrap() += by)
+    ///     .command_history(100)
+    ///     .build();
+    ///
+    /// mediator.send_undoable(Move(3));
+    /// mediator.begin_group("double step");
+    /// mediator.send_undoable(Move(1));
+    /// mediator.send_undoable(Move(1));
+    /// mediator.end_group();
+    ///
+    /// // Undoes both moves of the group.
+    /// assert!(mediator.undo());
+    /// while mediator.next().is_ok() {}
+    /// assert_eq!(*position.lock().unwrap(), 3);
+    /// assert_eq!(mediator.history().redo[0].label, "double step");
+    ///
+    fn send_undoable<Req>(&self, req: Req)
+    where
+        Req: Undoable,
+        Self: RequestHandler<Req, Ev> + RequestHandler<Req::Inverse, Ev>,
+    {
+        let (undo, redo) = (req.clone(), req.clone());
+        self.history.lock().unwrap().record(
+            req.label(),
+            Box::new(move |m: &Self| m.send(undo.inverse())),
+            Box::new(move |m: &Self| m.send(redo.clone())),
+        );
+        self.send(req)
+    }
+
+    /// Undoes the last step of the command history by sending the inverses
+    /// of its requests in reverse order.
+    ///
+    /// Returns `false` if there is nothing to undo.
+    /// The inverses are sent as plain requests, so they are not recorded.
+    /// If one of them panics, the step stays in the history to be undone again.
+    ///
+    fn undo(&self) -> bool {
+        match Replay::undo(&self.history) {
+            Some(replay) => {
+                for command in replay.step().commands.iter().rev() {
+                    (command.undo)(self);
+                }
+                replay.complete();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Redoes the last undone step of the command history by sending its requests again.
+    ///
+    /// Returns `false` if there is nothing to redo.
+    /// Sending a new [`Undoable`] request discards the steps to redo.
+    ///
+    fn redo(&self) -> bool {
+        match Replay::redo(&self.history) {
+            Some(replay) => {
+                for command in &replay.step().commands {
+                    (command.redo)(self);
+                }
+                replay.complete();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Groups the [`Undoable`] requests sent until [`SyncMediatorInternalHistory::end_group()`]
+    /// into a single step named `label`.
+    ///
+    /// Groups may be nested, the outermost one forms the step.
+    ///
+    fn begin_group(&self, label: &str) {
+        self.history.lock().unwrap().begin_group(label.to_owned());
+    }
+
+    /// Closes the group opened by [`SyncMediatorInternalHistory::begin_group()`].
+    ///
+    fn end_group(&self) {
+        self.history.lock().unwrap().end_group();
+    }
+
+    /// Returns a snapshot of the steps that can be undone and redone.
+    ///
+    fn history(&self) -> CommandHistory {
+        self.history.lock().unwrap().snapshot()
+    }
+
+    /// Discards all steps of the command history.
+    ///
+    fn clear_history(&self) {
+        self.history.lock().unwrap().clear();
+    }
+}
+
+impl<Ev> SyncMediatorInternalRetry<Ev> for BasicMediator<Ev>
+where
+    Ev: Debug,
+{
+    /// Send a request of type `Req` to the mediator and retry it if the handler fails.
+    ///
+    /// The request will be processed internally by [`TryRequestHandler::try_handle()`].
+    /// If a [`crate::retry::RetryPolicy`] was added for `Req` via
+    /// [`super::BasicBuilder::add_retry_policy()`], failed attempts are retried
+    /// according to it, sleeping on the [`Clock`] of the mediator in between.
+    /// Otherwise, the request is sent exactly once.
+    /// The error of the last attempt is returned.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    /// use std::{sync::atomic::{AtomicU32, Ordering}, time::Duration};
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Stored,
+    /// }
+    ///
+    /// static ATTEMPTS: AtomicU32 = AtomicU32::new(0);
+    ///
+    /// #[derive(Clone)]
+    /// struct Request;
+    ///
+    /// impl TryRequestHandler<Request, MyEvent> for BasicMediator<MyEvent> {
+    ///     type Error = String;
+    ///
+    ///     fn try_handle(&self, _req: Request) -> Result<(), String> {
+    ///         if ATTEMPTS.fetch_add(1, Ordering::SeqCst) < 2 {
+    ///             return Err(String::from("database unavailable"));
+    ///         }
+    ///         self.publish(MyEvent::Stored);
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .add_retry_policy::<Request>(RetryPolicy::new(3).fixed(Duration::from_millis(1)))
+    ///     .build();
+    ///
+    /// assert!(mediator.send_with_retry(Request).is_ok());
+    /// assert_eq!(ATTEMPTS.load(Ordering::SeqCst), 3);
+    ///
+    fn send_with_retry<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), <Self as TryRequestHandler<Req, Ev>>::Error>
+    where
+        Req: Clone + 'static,
+        Self: TryRequestHandler<Req, Ev>,
+        <Self as TryRequestHandler<Req, Ev>>::Error: 'static,
+    {
+        let dead = self.dead_letters.is_enabled().then(|| req.clone());
+        let result = match self
+            .retry
+            .get::<Req, <Self as TryRequestHandler<Req, Ev>>::Error>()
+        {
+            Some(policy) => retry::retry(policy, &*self.clock, req, |req| self.try_send(req)),
+            None => self.try_send(req),
+        };
+
+        if let (Some(req), Err(err)) = (dead, &result) {
+            self.dead_letters.request(&req, err);
+        }
+        result
+    }
+}
+
+impl<Ev> SyncMediatorInternalDeadLetter<Ev> for BasicMediator<Ev>
+where
+    Ev: Debug,
+{
+    /// Returns all [`DeadLetter`]s in the order they were collected.
+    ///
+    /// The dead-letter queue is enabled via [`super::BasicBuilder::dead_letter_queue()`].
+    /// It collects events dispatched while there are no listeners,
+    /// events whose listener panicked and requests of the types added via
+    /// [`super::BasicBuilder::dead_letter_requests()`] that failed in
+    /// [`SyncMediatorInternalRetry::send_with_retry()`].
+    /// Without a dead-letter queue, no letters are returned.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone, PartialEq)]
+    /// enum MyEvent {
+    ///     One,
+    ///     Two
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .dead_letter_queue(MemoryStorage::new())
+    ///     .build();
+    ///
+    /// mediator.publish(MyEvent::One);
+    /// mediator.next().ok();
+    ///
+    /// let letters = mediator.dead_letters().unwrap();
+    /// assert_eq!(letters[0].reason, DeadLetterReason::NoListeners);
+    /// assert_eq!(letters[0].letter, Letter::Event(MyEvent::One));
+    ///
+    fn dead_letters(&self) -> io::Result<Vec<DeadLetter<Ev>>> {
+        self.dead_letters.load()
+    }
+
+    /// Removes all dead events from the queue and publishes them again.
+    ///
+    /// The events are dispatched to the listeners on the following calls of
+    /// [`BasicMediator::next()`]. Returns the number of re-published events.
+    ///
+    fn redispatch_events(&self) -> io::Result<usize> {
+        let events = self.dead_letters.take_events()?;
+        let count = events.len();
+        for ev in events {
+            self.publish(ev);
+        }
+        Ok(count)
+    }
+
+    /// Removes all failed requests of type `Req` from the queue
+    /// and sends them again via [`SyncMediatorInternalRetry::send_with_retry()`].
+    ///
+    /// Returns the result of every request. Requests that fail again
+    /// are collected again with a new id.
+    ///
+    fn redispatch_requests<Req>(
+        &self,
+    ) -> io::Result<Vec<Result<(), <Self as TryRequestHandler<Req, Ev>>::Error>>>
+    where
+        Req: Record + Clone + 'static,
+        Self: TryRequestHandler<Req, Ev>,
+        <Self as TryRequestHandler<Req, Ev>>::Error: 'static,
+    {
+        Ok(self
+            .dead_letters
+            .take_requests::<Req>()?
+            .into_iter()
+            .map(|req| self.send_with_retry(req))
+            .collect())
+    }
+
+    /// Removes all [`DeadLetter`]s and returns how many were removed.
+    ///
+    fn purge_dead_letters(&self) -> io::Result<usize> {
+        self.dead_letters.purge()
+    }
+}
+
 impl<Ev> SyncMediatorInternalNext for BasicMediator<Ev>
 where
     Ev: Debug + Clone,
@@ -125,16 +1049,267 @@ where
     /// [`SyncMediatorInternalNext::next()`] invokes
     /// registered listeners with a cloned value
     /// of the published event.
+    /// Afterwards, the follow-up requests of sagas are sent.
     ///
     fn next(&self) -> Result<(), TryRecvError> {
-        match self.channel.1.try_recv() {
-            Ok(ev) => {
-                for listener in self.listener.iter() {
-                    listener(ev.clone())
+        let now = self.clock.now();
+        let due = self.schedule.lock().unwrap().take_due(now);
+        for taken in due {
+            match taken {
+                Taken::Once(ev) => self.publish(ev),
+                Taken::Every(mut recurring) => {
+                    let ev = recurring.fire();
+                    self.schedule.lock().unwrap().reschedule(recurring, now);
+                    self.publish(ev);
                 }
+            }
+        }
+        let flushed = self.timed.iter().filter(|timed| timed.tick(now)).count() > 0;
+
+        let result = match self.receive() {
+            Ok((ev, topic)) => {
+                self.dispatch(ev, topic.as_deref());
                 Ok(())
             }
+            Err(TryRecvError::Empty) if flushed => Ok(()),
             Err(err) => Err(err),
+        };
+        self.sagas.run(self);
+        result
+    }
+
+    /// Process the next event, blocking until one arrives if necessary.
+    ///
+    /// Behaves like [`BasicMediator::next()`], but if no event is ready,
+    /// it blocks until an event is published on the mediator, propagated to it
+    /// by a linked mediator or forwarded to it by a bridge.
+    /// This way, a blocking worker can drive the mediator without polling `next()`.
+    /// Scheduled events are only published once another event arrives,
+    /// use [`BasicMediator::next_scheduled()`] to wait for them.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    /// use std::thread;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Started,
+    /// }
+    ///
+    /// let parent = BasicMediator::<MyEvent>::builder().build();
+    /// let link = parent.link();
+    ///
+    /// let worker = thread::spawn(move || {
+    ///     let child = BasicMediator::<MyEvent>::builder()
+    ///         .add_listener(|ev| {
+    ///             /* Your listening logic */
+    ///         })
+    ///         .parent(&link, Propagation::up())
+    ///         .build();
+    ///     child.publish(MyEvent::Started);
+    ///     child.recv().unwrap();
+    /// });
+    /// worker.join().unwrap();
+    ///
+    /// // Blocks until the event bubbled up from the worker.
+    /// parent.recv().unwrap();
+    ///
+    fn recv(&self) -> Result<(), RecvError> {
+        loop {
+            match self.next() {
+                Err(TryRecvError::Empty) => (),
+                result => return result.map_err(|_| RecvError),
+            }
+            let envelope = self.channel.1.recv()?;
+            self.enqueue(&mut self.queue.lock().unwrap(), envelope);
+        }
+    }
+}
+
+impl<Ev> SyncMediatorInternalSchedule<Ev> for BasicMediator<Ev>
+where
+    Ev: Debug + Clone,
+{
+    /// Schedules the event `Ev` to be published after `delay`.
+    ///
+    /// The event is published by the first call of [`BasicMediator::next()`]
+    /// once `delay` passed on the [`Clock`] of the mediator.
+    /// Returns a [`ScheduledId`] to cancel the event.
+    ///
+    /// A `delay` beyond the range of [`Instant`] is never due,
+    /// but the event can still be cancelled.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    /// use std::{sync::Arc, time::Duration};
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     TimedOut,
+    /// }
+    ///
+    /// let clock = Arc::new(MockClock::new());
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .add_listener(|ev| {
+    ///         /* Your listening logic */
+    ///     })
+    ///     .clock(clock.clone())
+    ///     .build();
+    ///
+    /// mediator.publish_after(MyEvent::TimedOut, Duration::from_secs(30));
+    /// assert!(mediator.next().is_err());
+    ///
+    /// clock.advance(Duration::from_secs(30));
+    /// assert!(mediator.next().is_ok());
+    ///
+    fn publish_after(&self, event: Ev, delay: Duration) -> ScheduledId {
+        self.schedule
+            .lock()
+            .unwrap()
+            .after(self.clock.now(), delay, event)
+    }
+
+    /// Schedules the event `Ev` to be published at `at`.
+    ///
+    /// See [`BasicMediator::publish_after()`] for more info.
+    ///
+    fn publish_at(&self, event: Ev, at: Instant) -> ScheduledId {
+        self.schedule.lock().unwrap().once(at, event)
+    }
+
+    /// Schedules an event created by `f` to be published every `interval`,
+    /// starting one `interval` from now.
+    ///
+    /// If the mediator falls behind by more than an interval,
+    /// the missed ticks are skipped. `f` is called by [`BasicMediator::next()`]
+    /// without holding the schedule, so it may schedule or cancel events itself.
+    /// Ticks beyond the range of [`Instant`] are never due.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    /// use std::{sync::Arc, time::Duration};
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Tick(u32),
+    /// }
+    ///
+    /// let clock = Arc::new(MockClock::new());
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .add_listener(|ev| {
+    ///         /* Your listening logic */
+    ///     })
+    ///     .clock(clock.clone())
+    ///     .build();
+    ///
+    /// let mut ticks = 0;
+    /// let id = mediator.publish_every(move || {
+    ///     ticks += 1;
+    ///     MyEvent::Tick(ticks)
+    /// }, Duration::from_secs(1));
+    ///
+    /// mediator.next_scheduled().ok();
+    /// mediator.next_scheduled().ok();
+    /// assert_eq!(clock.sleeps(), vec![Duration::from_secs(1); 2]);
+    ///
+    /// assert!(mediator.cancel_scheduled(id));
+    ///
+    fn publish_every<F>(&self, f: F, interval: Duration) -> ScheduledId
+    where
+        F: FnMut() -> Ev + Send + 'static,
+    {
+        self.schedule
+            .lock()
+            .unwrap()
+            .every(self.clock.now(), interval, f)
+    }
+
+    /// Cancels the scheduled event with `id`.
+    ///
+    /// Returns `false` if the event was already published
+    /// or cancelled before. Recurring events can always be cancelled.
+    ///
+    fn cancel_scheduled(&self, id: ScheduledId) -> bool {
+        self.schedule.lock().unwrap().cancel(id)
+    }
+
+    /// Process the next event, waiting for scheduled events if necessary.
+    ///
+    /// Behaves like [`BasicMediator::next()`], but if no event is ready,
+    /// it sleeps on the [`Clock`] of the mediator until the next scheduled
+    /// or debounced event is due. Only returns [`TryRecvError::Empty`] if nothing is scheduled.
+    ///
+    fn next_scheduled(&self) -> Result<(), TryRecvError> {
+        loop {
+            match self.next() {
+                Err(TryRecvError::Empty) => {
+                    let due = self.next_due();
+                    match due {
+                        Some(due) => self
+                            .clock
+                            .sleep(due.saturating_duration_since(self.clock.now())),
+                        None => return Err(TryRecvError::Empty),
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl<Ev> SyncMediatorInternalFlush for BasicMediator<Ev>
+where
+    Ev: Debug + Clone,
+{
+    /// Flushes the events buffered by batch and debounced listeners.
+    ///
+    /// Every batch listener is called with its pending batch
+    /// and every debounced listener with its pending event,
+    /// regardless of whether they are due.
+    /// It also waits until parallel listeners processed all dispatched events.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Indexed(u32),
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .add_batch_listener(100, Duration::from_secs(60), |batch: Vec<MyEvent>| {
+    ///         assert_eq!(batch.len(), 2);
+    ///     })
+    ///     .build();
+    ///
+    /// mediator.publish(MyEvent::Indexed(1));
+    /// mediator.publish(MyEvent::Indexed(2));
+    /// mediator.next().ok();
+    /// mediator.next().ok();
+    ///
+    /// // Called with both events, long before the batch is due.
+    /// mediator.flush();
+    ///
+    fn flush(&self) {
+        for idle in self.flush_timed() {
+            idle.wait();
         }
     }
 }
@@ -1,17 +1,49 @@
-use super::{basic::BasicMediator, interface::BasicMediatorBuilderInterface};
+use super::{
+    basic::BasicMediator,
+    interface::{
+        BasicMediatorBuilderInterface, BridgeBuilderInterface, DeadLetterBuilderInterface,
+        DeduplicationBuilderInterface, HierarchyBuilderInterface, HistoryBuilderInterface,
+        ListenerOperatorBuilderInterface, OutboxBuilderInterface, PriorityBuilderInterface,
+        ProjectionBuilderInterface, QueryCacheBuilderInterface, QueryHandler,
+        RetryBuilderInterface, SagaBuilderInterface, SyncMediatorInternalHierarchy,
+        SyncMediatorInternalTryHandle, TopicBuilderInterface, TryRequestHandler,
+    },
+};
 use crate::mediator::{
     builder::{BuilderFlow, BuilderInternal},
-    listener::Listener,
+    cache::QueryCache,
+    clock::Clock,
+    dead_letter::DeadLetter,
+    hierarchy::{MediatorLink, Propagation},
+    idempotency::{CachedOutcome, DedupStore, Idempotent, MemoryDedupStore},
+    listener::{Batch, Coalescer, Debounce, Listener, Parallel, Throttle},
+    priority::Priority,
+    retry::RetryPolicy,
+    saga::{InstanceStorage, Saga, SagaInstance, SagaRunner},
+    storage::{Record, Storage},
 };
-use std::{fmt::Debug, sync::mpsc::channel};
+use std::{fmt::Debug, hash::Hash, sync::Arc, time::Duration};
 
 /// The [`BasicBuilder`] helps you to create a [`BasicMediator`].
 ///
 /// The [`BasicBuilder`] is part of the builder pattern.
-/// It has only two functionalities. The first one is adding a [`Listener`] via
-/// [`BasicBuilder::add_listener()`].
-/// The second one is the mandatory [`BuilderFlow::build()`], which returns
-/// a [`BasicMediator`].
+/// Its main functionality is adding a [`Listener`] via [`BasicBuilder::add_listener()`],
+/// or a topic, debounced, throttled, batch or parallel one via
+/// [`BasicBuilder::add_topic_listener()`], [`BasicBuilder::add_debounced_listener()`],
+/// [`BasicBuilder::add_throttled_listener()`], [`BasicBuilder::add_batch_listener()`]
+/// and [`BasicBuilder::add_parallel_listener()`].
+///
+/// The other methods configure the [`BasicMediator`], among others:
+/// - how events are queued via [`BasicBuilder::prioritize()`] and [`BasicBuilder::coalesce()`],
+/// - its parent and bridges via [`BasicBuilder::parent()`], [`BasicBuilder::add_bridge()`]
+///   and [`BasicBuilder::project_from()`],
+/// - retries and the clock via [`BasicBuilder::add_retry_policy()`] and [`BasicBuilder::clock()`],
+/// - a dead-letter queue via [`BasicBuilder::dead_letter_queue()`],
+/// - transactions, undo and deduplication via [`BasicBuilder::transactional()`],
+///   [`BasicBuilder::command_history()`] and [`BasicBuilder::deduplicate_requests()`],
+/// - sagas and query caches via [`BasicBuilder::add_saga()`] and [`BasicBuilder::cache_queries()`].
+///
+/// Finally, the mandatory [`BuilderFlow::build()`] returns a [`BasicMediator`].
 ///
 pub struct BasicBuilder<Ev>
 where
@@ -28,10 +60,7 @@ where
     ///
     fn builder() -> BasicBuilder<Ev> {
         BasicBuilder::<Ev> {
-            mediator: BasicMediator::<Ev> {
-                channel: channel(),
-                listener: vec![],
-            },
+            mediator: BasicMediator::<Ev>::new(),
         }
     }
 }
@@ -58,6 +87,399 @@ where
     }
 }
 
+impl<M, Ev> RetryBuilderInterface<M, Ev> for BasicBuilder<Ev>
+where
+    Ev: Debug,
+{
+    /// Adds a [`RetryPolicy`] for the request type `Req` to the [`BasicBuilder`].
+    ///
+    /// A policy that was added before for the same request type is replaced.
+    ///
+    fn add_retry_policy<Req, E>(mut self, policy: RetryPolicy<E>) -> Self
+    where
+        Req: 'static,
+        E: 'static,
+    {
+        self.mediator.retry.insert::<Req, E>(policy);
+        self
+    }
+
+    /// Replaces the [`Clock`] of the [`BasicBuilder`].
+    ///
+    fn clock<C>(mut self, clock: C) -> Self
+    where
+        C: Clock,
+    {
+        self.mediator.clock = Arc::new(clock);
+        self
+    }
+}
+
+impl<M, Ev> DeadLetterBuilderInterface<M, Ev> for BasicBuilder<Ev>
+where
+    Ev: Debug,
+{
+    /// Enables the dead-letter queue of the [`BasicBuilder`], backed by `storage`.
+    ///
+    fn dead_letter_queue<S>(mut self, storage: S) -> Self
+    where
+        S: Storage<DeadLetter<Ev>> + 'static,
+    {
+        self.mediator.dead_letters.set_storage(storage);
+        self
+    }
+
+    /// Collects failed requests of type `Req` in the dead-letter queue.
+    ///
+    fn dead_letter_requests<Req, E>(mut self) -> Self
+    where
+        Req: Record + 'static,
+        E: Debug + 'static,
+    {
+        self.mediator.dead_letters.add_request::<Req, E>();
+        self
+    }
+}
+
+impl<M, Ev> ListenerOperatorBuilderInterface<M, Ev> for BasicBuilder<Ev>
+where
+    Ev: Debug,
+{
+    /// Adds a debounced listener to the [`BasicBuilder`].
+    ///
+    fn add_debounced_listener<K, KF, F>(mut self, quiet: Duration, key: KF, f: F) -> Self
+    where
+        K: Hash + Eq + Send + 'static,
+        KF: Fn(&Ev) -> K + Send + 'static,
+        F: Listener<Ev>,
+        Ev: Debug + Send + 'static,
+    {
+        self.mediator
+            .timed
+            .push(Box::new(Debounce::new(quiet, key, f)));
+        self
+    }
+
+    /// Adds a throttled listener to the [`BasicBuilder`].
+    ///
+    fn add_throttled_listener<K, KF, F>(
+        mut self,
+        max: usize,
+        interval: Duration,
+        key: KF,
+        f: F,
+    ) -> Self
+    where
+        K: Hash + Eq + Send + 'static,
+        KF: Fn(&Ev) -> K + Send + 'static,
+        F: Listener<Ev>,
+        Ev: Debug + 'static,
+    {
+        self.mediator
+            .timed
+            .push(Box::new(Throttle::new(max, interval, key, f)));
+        self
+    }
+
+    /// Adds a batch listener to the [`BasicBuilder`].
+    ///
+    fn add_batch_listener<F>(mut self, max_size: usize, max_wait: Duration, f: F) -> Self
+    where
+        F: Listener<Vec<Ev>>,
+        Ev: Debug + Clone + Send + 'static,
+    {
+        self.mediator
+            .timed
+            .push(Box::new(Batch::new(max_size, max_wait, f)));
+        self
+    }
+
+    /// Adds a parallel listener to the [`BasicBuilder`].
+    ///
+    fn add_parallel_listener<K, KF, F>(mut self, workers: usize, key: KF, f: F) -> Self
+    where
+        K: Hash,
+        KF: Fn(&Ev) -> K + Send + 'static,
+        F: Listener<Ev> + Sync,
+        Ev: Debug + Send + 'static,
+    {
+        self.mediator
+            .timed
+            .push(Box::new(Parallel::new(workers, key, f)));
+        self
+    }
+
+    /// Coalesces queued events with the same key.
+    ///
+    fn coalesce<K, KF, MF>(mut self, key: KF, merge: MF) -> Self
+    where
+        K: Eq + 'static,
+        KF: Fn(&Ev) -> Option<K> + Send + 'static,
+        MF: Fn(Ev, Ev) -> Ev + Send + 'static,
+        Ev: 'static,
+    {
+        self.mediator.coalesce = Some(Box::new(Coalescer::new(key, merge)));
+        self
+    }
+}
+
+impl<M, Ev> PriorityBuilderInterface<M, Ev> for BasicBuilder<Ev>
+where
+    Ev: Debug,
+{
+    /// Classifies the events of the [`BasicBuilder`] by their [`Priority`].
+    ///
+    fn prioritize<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&Ev) -> Priority + Send + 'static,
+    {
+        self.mediator.queue.get_mut().unwrap().set_classifier(f);
+        self
+    }
+}
+
+impl<M, Ev> TopicBuilderInterface<M, Ev> for BasicBuilder<Ev>
+where
+    Ev: Debug,
+{
+    /// Adds a user-defined listener subscribed to `pattern` to the [`BasicBuilder`].
+    ///
+    fn add_topic_listener<F>(mut self, pattern: &str, f: F) -> Self
+    where
+        F: Listener<Ev>,
+        Ev: Debug,
+    {
+        self.mediator.topics.insert(pattern, Box::new(f));
+        self
+    }
+}
+
+impl<M, Ev> HierarchyBuilderInterface<M, Ev> for BasicBuilder<Ev>
+where
+    Ev: Debug,
+{
+    /// Links the mediator built by the [`BasicBuilder`] as a child to `parent`.
+    ///
+    fn parent<P>(mut self, parent: &P, propagation: Propagation<Ev>) -> Self
+    where
+        P: SyncMediatorInternalHierarchy<Ev>,
+        Ev: Debug,
+    {
+        self.mediator
+            .hierarchy
+            .set_parent(parent.link(), &self.mediator.channel.0, propagation);
+        self
+    }
+}
+
+impl<M, Ev> BridgeBuilderInterface<M, Ev> for BasicBuilder<Ev>
+where
+    Ev: Debug,
+{
+    /// Adds a bridge forwarding events converted by `map` to `target` to the [`BasicBuilder`].
+    ///
+    fn add_bridge<T, Ev2, F>(mut self, target: &T, map: F) -> Self
+    where
+        T: SyncMediatorInternalHierarchy<Ev2>,
+        Ev2: Debug + Send + 'static,
+        F: Fn(Ev) -> Option<Ev2> + Send + 'static,
+        Ev: Debug + Clone,
+    {
+        self.mediator.bridges.add(target.link(), map);
+        self
+    }
+
+    /// Returns a [`MediatorLink`] to the mediator built by the [`BasicBuilder`].
+    ///
+    fn link(&self) -> MediatorLink<Ev> {
+        self.mediator.link()
+    }
+}
+
+impl<M, Ev> ProjectionBuilderInterface<M, Ev> for BasicBuilder<Ev>
+where
+    Ev: Debug,
+{
+    /// Adds a user-defined listener for events converted by `map` to the [`BasicBuilder`].
+    ///
+    fn add_mapped_listener<Ev2, MF, F>(mut self, map: MF, f: F) -> Self
+    where
+        MF: Fn(Ev) -> Option<Ev2> + Send + 'static,
+        F: Listener<Ev2>,
+        Ev2: Debug,
+        Ev: Debug + Clone,
+    {
+        self.mediator.listener.push(Box::new(move |ev| {
+            if let Some(ev) = map(ev) {
+                f(ev)
+            }
+        }));
+        self
+    }
+
+    /// Derives the mediator built by the [`BasicBuilder`] from `source`.
+    ///
+    fn project_from<S, Src, F>(mut self, source: &S, map: F) -> Self
+    where
+        S: SyncMediatorInternalHierarchy<Src>,
+        Src: Debug + Clone + 'static,
+        F: Fn(Src) -> Option<Ev> + Send + 'static,
+        Ev: Debug + Send + 'static,
+    {
+        self.mediator
+            .bridges
+            .project_from(source.link(), &self.mediator.channel.0, map);
+        self
+    }
+}
+
+impl<M, Ev> OutboxBuilderInterface<M, Ev> for BasicBuilder<Ev>
+where
+    Ev: Debug,
+{
+    /// Makes requests handled by the mediator built by the [`BasicBuilder`] transactional.
+    ///
+    fn transactional(mut self) -> Self {
+        self.mediator.outbox.enable();
+        self
+    }
+}
+
+impl<M, Ev> HistoryBuilderInterface<M, Ev> for BasicBuilder<Ev>
+where
+    Ev: Debug,
+{
+    /// Enables the command history of the mediator built by the [`BasicBuilder`],
+    /// keeping at most `capacity` steps to undo.
+    ///
+    fn command_history(mut self, capacity: usize) -> Self {
+        self.mediator.history.get_mut().unwrap().enable(capacity);
+        self
+    }
+}
+
+impl<M, Ev> DeduplicationBuilderInterface<M, Ev> for BasicBuilder<Ev>
+where
+    Ev: Debug,
+{
+    /// Deduplicates the requests of the mediator built by the [`BasicBuilder`]
+    /// within a window of the last `capacity` keys.
+    ///
+    fn deduplicate_requests(self, capacity: usize) -> Self {
+        <Self as DeduplicationBuilderInterface<M, Ev>>::deduplicate_requests_with(
+            self,
+            MemoryDedupStore::new(capacity),
+        )
+    }
+
+    /// Deduplicates the requests of the mediator built by the [`BasicBuilder`]
+    /// within the window of `store`.
+    ///
+    fn deduplicate_requests_with<St>(mut self, store: St) -> Self
+    where
+        St: DedupStore<CachedOutcome> + 'static,
+    {
+        self.mediator.dedup.set_requests(store);
+        self
+    }
+
+    /// Deduplicates the events of the mediator built by the [`BasicBuilder`]
+    /// within a window of the last `capacity` keys.
+    ///
+    fn deduplicate_events(self, capacity: usize) -> Self
+    where
+        Ev: Idempotent,
+    {
+        <Self as DeduplicationBuilderInterface<M, Ev>>::deduplicate_events_with(
+            self,
+            MemoryDedupStore::new(capacity),
+        )
+    }
+
+    /// Deduplicates the events of the mediator built by the [`BasicBuilder`]
+    /// within the window of `store`.
+    ///
+    fn deduplicate_events_with<St>(mut self, store: St) -> Self
+    where
+        St: DedupStore<()> + 'static,
+        Ev: Idempotent,
+    {
+        self.mediator.dedup.set_events(store);
+        self
+    }
+}
+
+impl<Ev> SagaBuilderInterface<BasicMediator<Ev>, Ev> for BasicBuilder<Ev>
+where
+    Ev: Debug,
+{
+    /// Adds a [`Saga`] to the [`BasicBuilder`], whose instances are kept in memory.
+    ///
+    fn add_saga<S>(self, saga: S) -> Self
+    where
+        S: Saga<Ev>,
+        BasicMediator<Ev>: TryRequestHandler<S::Request, Ev>,
+        Ev: Debug + 'static,
+    {
+        self.add_saga_boxed(saga, None)
+    }
+
+    /// Adds a [`Saga`] to the [`BasicBuilder`], whose instances are persisted in `storage`.
+    ///
+    fn add_saga_with_storage<S, St>(self, saga: S, storage: St) -> Self
+    where
+        S: Saga<Ev>,
+        St: Storage<SagaInstance<S::Id, S::State>> + 'static,
+        BasicMediator<Ev>: TryRequestHandler<S::Request, Ev>,
+        Ev: Debug + 'static,
+    {
+        self.add_saga_boxed(saga, Some(Box::new(storage)))
+    }
+}
+
+impl<Ev> QueryCacheBuilderInterface<BasicMediator<Ev>, Ev> for BasicBuilder<Ev>
+where
+    Ev: Debug,
+{
+    /// Caches the responses to queries of type `Q` as declared by `cache`.
+    ///
+    fn cache_queries<Q>(mut self, cache: QueryCache<Ev>) -> Self
+    where
+        BasicMediator<Ev>: QueryHandler<Q, Ev>,
+        Q: Hash + Eq + Clone + Send + 'static,
+        <BasicMediator<Ev> as QueryHandler<Q, Ev>>::Response: Clone + Send,
+    {
+        self.mediator
+            .caches
+            .insert::<Q, <BasicMediator<Ev> as QueryHandler<Q, Ev>>::Response>(cache);
+        self
+    }
+}
+
+impl<Ev> BasicBuilder<Ev>
+where
+    Ev: Debug,
+{
+    /// Registers the [`SagaRunner`] of `saga` as timed listener
+    /// and drives its pending requests after every `next()`.
+    fn add_saga_boxed<S>(mut self, saga: S, storage: Option<InstanceStorage<S, Ev>>) -> Self
+    where
+        S: Saga<Ev>,
+        BasicMediator<Ev>: TryRequestHandler<S::Request, Ev>,
+        Ev: Debug + 'static,
+    {
+        let runner = Arc::new(SagaRunner::new(saga, storage));
+        self.mediator.timed.push(Box::new(runner.clone()));
+        self.mediator
+            .sagas
+            .drivers
+            .push(Box::new(move |m: &BasicMediator<Ev>| {
+                runner.drain(|req| m.try_send(req).is_ok())
+            }));
+        self
+    }
+}
+
 impl<Ev> BasicBuilder<Ev>
 where
     Ev: Debug,
@@ -96,6 +518,1242 @@ where
     {
         <Self as BasicMediatorBuilderInterface<BasicMediator<Ev>, Ev>>::add_listener(self, f)
     }
+
+    /// Adds a user-defined listener subscribed to `pattern` to the [`BasicBuilder`].
+    ///
+    /// The listener is called for every event published to a topic matching `pattern`
+    /// via `publish_to()`. Topics and patterns consist of segments separated by dots,
+    /// e.g. `orders.eu.created`. In patterns, the segment `*` matches exactly one segment
+    /// and the segment `#` matches zero or more segments.
+    /// Listeners added via `add_listener()` receive events published to topics as well.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     OrderCreated(u32),
+    ///     OrderShipped(u32),
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .add_topic_listener("orders.*.created", |ev| {
+    ///         /* Your listening logic for created orders of any region */
+    ///     })
+    ///     .add_topic_listener("orders.eu.#", |ev| {
+    ///         /* Your listening logic for all events of european orders */
+    ///     })
+    ///     .build();
+    ///
+    pub fn add_topic_listener<F>(self, pattern: &str, f: F) -> Self
+    where
+        F: Listener<Ev>,
+    {
+        <Self as TopicBuilderInterface<BasicMediator<Ev>, Ev>>::add_topic_listener(self, pattern, f)
+    }
+
+    /// Links the mediator built by the [`BasicBuilder`] as a child to `parent`.
+    ///
+    /// The [`Propagation`] decides which events published on the child bubble up
+    /// to the parent and which events published on the parent flow down to the child.
+    /// Events never travel back in the direction they came from, so they cannot loop.
+    /// `parent` may be any mediator of this crate with the same event type.
+    /// The child is unlinked from its parent once it is dropped.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     PluginLoaded,
+    ///     Shutdown,
+    /// }
+    ///
+    /// let app = BasicMediator::<MyEvent>::builder()
+    ///     .add_listener(|ev| {
+    ///         /* Your listening logic for events of the whole application */
+    ///     })
+    ///     .build();
+    ///
+    /// let plugin = BasicMediator::<MyEvent>::builder()
+    ///     .parent(&app, Propagation::up().down_if(|ev| matches!(ev, MyEvent::Shutdown)))
+    ///     .add_listener(|ev| {
+    ///         /* Your listening logic for events of the plugin */
+    ///     })
+    ///     .build();
+    ///
+    pub fn parent<P>(self, parent: &P, propagation: Propagation<Ev>) -> Self
+    where
+        P: SyncMediatorInternalHierarchy<Ev>,
+    {
+        <Self as HierarchyBuilderInterface<BasicMediator<Ev>, Ev>>::parent(
+            self,
+            parent,
+            propagation,
+        )
+    }
+
+    /// Adds a bridge forwarding events to `target` to the [`BasicBuilder`].
+    ///
+    /// Every event the mediator receives on `next()` or `recv()` is converted by `map`
+    /// and published on `target`, unless `map` returns `None`.
+    /// `target` may be any mediator of this crate, synchronous or asynchronous,
+    /// or a [`MediatorLink`] to a mediator that is not yet built.
+    /// Forwarding never blocks and events that come back to a mediator they passed through
+    /// are dropped, so mediators can be bridged in both directions, also when they are linked.
+    /// `target` waits for forwarded events in `recv()` without polling `next()`,
+    /// e.g. a blocking worker on one side and an async task on the other.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum Job {
+    ///     Resize(u32),
+    ///     Done(u32),
+    /// }
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum Audit {
+    ///     Finished(u32),
+    /// }
+    ///
+    /// let audit = BasicMediator::<Audit>::builder()
+    ///     .add_listener(|ev| {
+    ///         /* Your listening logic */
+    ///     })
+    ///     .build();
+    ///
+    /// let worker = BasicMediator::<Job>::builder()
+    ///     .add_bridge(&audit, |job| match job {
+    ///         Job::Done(id) => Some(Audit::Finished(id)),
+    ///         _ => None,
+    ///     })
+    ///     .build();
+    ///
+    pub fn add_bridge<T, Ev2, F>(self, target: &T, map: F) -> Self
+    where
+        T: SyncMediatorInternalHierarchy<Ev2>,
+        Ev2: Debug + Send + 'static,
+        F: Fn(Ev) -> Option<Ev2> + Send + 'static,
+        Ev: Clone,
+    {
+        <Self as BridgeBuilderInterface<BasicMediator<Ev>, Ev>>::add_bridge(self, target, map)
+    }
+
+    /// Returns a [`MediatorLink`] to the mediator built by the [`BasicBuilder`].
+    ///
+    /// It can be used as target of a bridge or as parent of a child mediator
+    /// before the mediator is built.
+    ///
+    pub fn link(&self) -> MediatorLink<Ev> {
+        <Self as BridgeBuilderInterface<BasicMediator<Ev>, Ev>>::link(self)
+    }
+
+    /// Adds a user-defined listener for events converted by `map` to the [`BasicBuilder`].
+    ///
+    /// The listener is called with every event mapped to `Some`,
+    /// which lets it depend on a narrower event type than the mediator.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum OrderEvent {
+    ///     Placed(u32),
+    ///     Paid(u32, u64),
+    /// }
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum BillingEvent {
+    ///     Charged(u64),
+    /// }
+    ///
+    /// let mediator = BasicMediator::<OrderEvent>::builder()
+    ///     .add_mapped_listener(
+    ///         |ev| match ev {
+    ///             OrderEvent::Paid(_, amount) => Some(BillingEvent::Charged(amount)),
+    ///             _ => None,
+    ///         },
+    ///         |ev| {
+    ///             /* Your listening logic for billing events */
+    ///         },
+    ///     )
+    ///     .build();
+    ///
+    pub fn add_mapped_listener<Ev2, MF, F>(self, map: MF, f: F) -> Self
+    where
+        MF: Fn(Ev) -> Option<Ev2> + Send + 'static,
+        F: Listener<Ev2>,
+        Ev2: Debug,
+        Ev: Clone,
+    {
+        <Self as ProjectionBuilderInterface<BasicMediator<Ev>, Ev>>::add_mapped_listener(
+            self, map, f,
+        )
+    }
+
+    /// Derives the mediator built by the [`BasicBuilder`] from `source`.
+    ///
+    /// Every event `source` receives on `next()` or `recv()` is converted by `map`
+    /// and published on the derived mediator, unless `map` returns `None`.
+    /// This lets downstream modules depend on the derived mediator
+    /// with a narrower event type than `source`.
+    /// `source` may be any mediator of this crate or a [`MediatorLink`].
+    /// The projection is removed from `source` once the derived mediator is dropped.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum OrderEvent {
+    ///     Placed(u32),
+    ///     Paid(u32, u64),
+    /// }
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum BillingEvent {
+    ///     Charged(u64),
+    /// }
+    ///
+    /// let orders = BasicMediator::<OrderEvent>::builder()
+    ///     .build();
+    ///
+    /// // The billing module only depends on billing events.
+    /// let billing = BasicMediator::<BillingEvent>::builder()
+    ///     .project_from(&orders, |ev| match ev {
+    ///         OrderEvent::Paid(_, amount) => Some(BillingEvent::Charged(amount)),
+    ///         _ => None,
+    ///     })
+    ///     .add_listener(|ev| {
+    ///         /* Your listening logic for billing events */
+    ///     })
+    ///     .build();
+    ///
+    pub fn project_from<S, Src, F>(self, source: &S, map: F) -> Self
+    where
+        S: SyncMediatorInternalHierarchy<Src>,
+        Src: Debug + Clone + 'static,
+        F: Fn(Src) -> Option<Ev> + Send + 'static,
+        Ev: Send + 'static,
+    {
+        <Self as ProjectionBuilderInterface<BasicMediator<Ev>, Ev>>::project_from(self, source, map)
+    }
+
+    /// Makes requests handled by the mediator built by the [`BasicBuilder`] transactional.
+    ///
+    /// Events published while a request sent via `try_send()` is handled
+    /// are buffered in an outbox of the request. They are delivered once the handler
+    /// succeeded and discarded once it failed. This applies to every attempt
+    /// of `send_with_retry()` as well.
+    /// Requests sent from within a handler commit into the outbox of the enclosing request.
+    /// Events published via `publish_immediately()` bypass the outbox.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Debited(u32),
+    ///     Credited(u32),
+    /// }
+    ///
+    /// struct Transfer(u32);
+    ///
+    /// impl TryRequestHandler<Transfer, MyEvent> for BasicMediator<MyEvent> {
+    ///     type Error = String;
+    ///
+    ///     fn try_handle(&self, req: Transfer) -> Result<(), String> {
+    ///         self.publish(MyEvent::Debited(req.0));
+    ///         if req.0 > 100 {
+    ///             return Err(String::from("limit exceeded"));
+    ///         }
+    ///         self.publish(MyEvent::Credited(req.0));
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .add_listener(|ev| {
+    ///         /* Your listening logic */
+    ///     })
+    ///     .transactional()
+    ///     .build();
+    ///
+    /// // The debit of the failed transfer is discarded.
+    /// assert!(mediator.try_send(Transfer(500)).is_err());
+    /// assert!(mediator.next().is_err());
+    ///
+    /// assert!(mediator.try_send(Transfer(50)).is_ok());
+    /// assert!(mediator.next().is_ok());
+    /// assert!(mediator.next().is_ok());
+    ///
+    pub fn transactional(self) -> Self {
+        <Self as OutboxBuilderInterface<BasicMediator<Ev>, Ev>>::transactional(self)
+    }
+
+    /// Enables the command history of the mediator built by the [`BasicBuilder`],
+    /// keeping at most `capacity` steps to undo.
+    ///
+    /// Requests implementing [`crate::history::Undoable`] sent via `send_undoable()` are recorded,
+    /// so they can be undone and redone via `undo()` and `redo()`.
+    /// Requests sent between `begin_group()` and `end_group()` form a single step.
+    /// Once there are more than `capacity` steps, the oldest one is discarded.
+    /// Without a command history, `send_undoable()` behaves like `send()`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Moved(i32),
+    /// }
+    ///
+    /// #[derive(Clone)]
+    /// struct Move(i32);
+    ///
+    /// impl Undoable for Move {
+    ///     type Inverse = Move;
+    ///
+    ///     fn inverse(&self) -> Move {
+    ///         Move(-self.0)
+    ///     }
+    /// }
+    ///
+    /// impl RequestHandler<Move, MyEvent> for BasicMediator<MyEvent> {
+    ///     fn handle(&self, req: Move) {
+    ///         self.publish(MyEvent::Moved(req.0));
+    ///     }
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .add_listener(|ev| {
+    ///         /* Your listening logic */
+    ///     })
+    ///     .command_history(100)
+    ///     .build();
+    ///
+    /// mediator.send_undoable(Move(3));
+    /// assert!(mediator.undo());
+    /// assert!(mediator.redo());
+    /// assert_eq!(mediator.history().undo.len(), 1);
+    ///
+    pub fn command_history(self, capacity: usize) -> Self {
+        <Self as HistoryBuilderInterface<BasicMediator<Ev>, Ev>>::command_history(self, capacity)
+    }
+
+    /// Deduplicates the requests of the mediator built by the [`BasicBuilder`]
+    /// within a window of the last `capacity` keys.
+    ///
+    /// Requests implementing [`Idempotent`] sent via `send_idempotent()` or
+    /// `try_send_idempotent()` are only handled once per key and request type.
+    /// Duplicates return the cached outcome, i.e. the result of the handler
+    /// for `try_send_idempotent()`, as long as the key is within the window.
+    /// Only successful results are cached, failed requests are handled again.
+    /// Without deduplication, these methods behave like `send()` and `try_send()`.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Charged(u32),
+    /// }
+    ///
+    /// struct Charge(u32);
+    ///
+    /// impl Idempotent for Charge {
+    ///     fn idempotency_key(&self) -> Option<String> {
+    ///         Some(format!("charge-{}", self.0))
+    ///     }
+    /// }
+    ///
+    /// impl TryRequestHandler<Charge, MyEvent> for BasicMediator<MyEvent> {
+    ///     type Error = String;
+    ///
+    ///     fn try_handle(&self, req: Charge) -> Result<(), String> {
+    ///         self.publish(MyEvent::Charged(req.0));
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .deduplicate_requests(1000)
+    ///     .build();
+    ///
+    /// assert!(mediator.try_send_idempotent(Charge(1)).is_ok());
+    /// // The duplicate returns the cached result without being handled.
+    /// assert!(mediator.try_send_idempotent(Charge(1)).is_ok());
+    ///
+    /// assert!(mediator.next().is_ok());
+    /// assert!(mediator.next().is_err());
+    ///
+    pub fn deduplicate_requests(self, capacity: usize) -> Self {
+        <Self as DeduplicationBuilderInterface<BasicMediator<Ev>, Ev>>::deduplicate_requests(
+            self, capacity,
+        )
+    }
+
+    /// Deduplicates the requests of the mediator built by the [`BasicBuilder`]
+    /// within the window of a user-defined [`DedupStore`].
+    ///
+    /// See [`BasicBuilder::deduplicate_requests()`] for more info.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Charged(u32),
+    /// }
+    ///
+    /// struct Charge(u32);
+    ///
+    /// impl Idempotent for Charge {
+    ///     fn idempotency_key(&self) -> Option<String> {
+    ///         Some(format!("charge-{}", self.0))
+    ///     }
+    /// }
+    ///
+    /// impl TryRequestHandler<Charge, MyEvent> for BasicMediator<MyEvent> {
+    ///     type Error = String;
+    ///
+    ///     fn try_handle(&self, req: Charge) -> Result<(), String> {
+    ///         self.publish(MyEvent::Charged(req.0));
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .deduplicate_requests_with(MemoryDedupStore::new(1000))
+    ///     .build();
+    ///
+    /// assert!(mediator.try_send_idempotent(Charge(1)).is_ok());
+    /// // The duplicate returns the cached result without being handled.
+    /// assert!(mediator.try_send_idempotent(Charge(1)).is_ok());
+    ///
+    /// assert!(mediator.next().is_ok());
+    /// assert!(mediator.next().is_err());
+    ///
+    pub fn deduplicate_requests_with<St>(self, store: St) -> Self
+    where
+        St: DedupStore<CachedOutcome> + 'static,
+    {
+        <Self as DeduplicationBuilderInterface<BasicMediator<Ev>, Ev>>::deduplicate_requests_with(
+            self, store,
+        )
+    }
+
+    /// Deduplicates the events of the mediator built by the [`BasicBuilder`]
+    /// within a window of the last `capacity` keys.
+    ///
+    /// Events are deduplicated by their [`Idempotent::idempotency_key()`]
+    /// when they are received by `next()`, no matter whether they were published,
+    /// bridged or propagated from another mediator. Duplicates are dropped
+    /// before they are propagated or delivered to any listener.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// struct Delivered {
+    ///     id: u64,
+    /// }
+    ///
+    /// impl Idempotent for Delivered {
+    ///     fn idempotency_key(&self) -> Option<String> {
+    ///         Some(self.id.to_string())
+    ///     }
+    /// }
+    ///
+    /// let mediator = BasicMediator::<Delivered>::builder()
+    ///     .deduplicate_events(1000)
+    ///     .build();
+    ///
+    /// mediator.publish(Delivered { id: 1 });
+    /// // The redelivered event is dropped.
+    /// mediator.publish(Delivered { id: 1 });
+    ///
+    /// assert!(mediator.next().is_ok());
+    /// assert!(mediator.next().is_err());
+    ///
+    pub fn deduplicate_events(self, capacity: usize) -> Self
+    where
+        Ev: Idempotent,
+    {
+        <Self as DeduplicationBuilderInterface<BasicMediator<Ev>, Ev>>::deduplicate_events(
+            self, capacity,
+        )
+    }
+
+    /// Deduplicates the events of the mediator built by the [`BasicBuilder`]
+    /// within the window of a user-defined [`DedupStore`].
+    ///
+    /// See [`BasicBuilder::deduplicate_events()`] for more info.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// struct Delivered {
+    ///     id: u64,
+    /// }
+    ///
+    /// impl Idempotent for Delivered {
+    ///     fn idempotency_key(&self) -> Option<String> {
+    ///         Some(self.id.to_string())
+    ///     }
+    /// }
+    ///
+    /// let mediator = BasicMediator::<Delivered>::builder()
+    ///     .deduplicate_events_with(MemoryDedupStore::new(1000))
+    ///     .build();
+    ///
+    /// mediator.publish(Delivered { id: 1 });
+    /// // The redelivered event is dropped.
+    /// mediator.publish(Delivered { id: 1 });
+    ///
+    /// assert!(mediator.next().is_ok());
+    /// assert!(mediator.next().is_err());
+    ///
+    pub fn deduplicate_events_with<St>(self, store: St) -> Self
+    where
+        St: DedupStore<()> + 'static,
+        Ev: Idempotent,
+    {
+        <Self as DeduplicationBuilderInterface<BasicMediator<Ev>, Ev>>::deduplicate_events_with(
+            self, store,
+        )
+    }
+
+    /// Adds a [`Saga`] to the [`BasicBuilder`], whose instances are kept in memory.
+    ///
+    /// The saga receives every event dispatched by `next()`.
+    /// Afterwards, `next()` sends the follow-up requests of the saga via `try_send()`.
+    /// If one of them fails or an instance times out, its remaining follow-up requests
+    /// are dropped and its compensating requests are sent instead.
+    /// Failing compensating requests are not compensated again.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     OrderPlaced(u32),
+    ///     OrderPaid(u32),
+    /// }
+    ///
+    /// enum Stock {
+    ///     Reserve(u32),
+    ///     Release(u32),
+    /// }
+    ///
+    /// struct Checkout;
+    ///
+    /// impl Saga<MyEvent> for Checkout {
+    ///     type Id = u32;
+    ///     type State = ();
+    ///     type Request = Stock;
+    ///
+    ///     fn correlate(&self, ev: &MyEvent) -> Option<u32> {
+    ///         match ev {
+    ///             MyEvent::OrderPlaced(id) | MyEvent::OrderPaid(id) => Some(*id),
+    ///         }
+    ///     }
+    ///
+    ///     fn start(&self, _id: &u32, ev: &MyEvent) -> Option<()> {
+    ///         matches!(ev, MyEvent::OrderPlaced(_)).then_some(())
+    ///     }
+    ///
+    ///     fn react(&self, _state: &mut (), ev: &MyEvent) -> Step<Stock> {
+    ///         match ev {
+    ///             MyEvent::OrderPlaced(id) => Step::Continue(vec![Stock::Reserve(*id)]),
+    ///             MyEvent::OrderPaid(_) => Step::Complete(vec![]),
+    ///         }
+    ///     }
+    ///
+    ///     fn compensate(&self, id: &u32, _state: &()) -> Vec<Stock> {
+    ///         vec![Stock::Release(*id)]
+    ///     }
+    ///
+    ///     fn timeout(&self) -> Option<Duration> {
+    ///         Some(Duration::from_secs(15 * 60))
+    ///     }
+    /// }
+    ///
+    /// impl TryRequestHandler<Stock, MyEvent> for BasicMediator<MyEvent> {
+    ///     type Error = String;
+    ///
+    ///     fn try_handle(&self, req: Stock) -> Result<(), String> {
+    ///         /* Your handling logic */
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .add_saga(Checkout)
+    ///     .build();
+    ///
+    /// mediator.publish(MyEvent::OrderPlaced(1));
+    ///
+    /// // Dispatches the event and sends `Stock::Reserve(1)`.
+    /// mediator.next().ok();
+    ///
+    pub fn add_saga<S>(self, saga: S) -> Self
+    where
+        S: Saga<Ev>,
+        BasicMediator<Ev>: TryRequestHandler<S::Request, Ev>,
+        Ev: Debug + 'static,
+    {
+        <Self as SagaBuilderInterface<BasicMediator<Ev>, Ev>>::add_saga(self, saga)
+    }
+
+    /// Adds a [`Saga`] to the [`BasicBuilder`], whose instances are persisted in `storage`.
+    ///
+    /// Works like [`BasicBuilder::add_saga()`], but replaces the contents of `storage`
+    /// with the running [`SagaInstance`]s whenever they change.
+    /// The instances of `storage` are restored on the first `next()`,
+    /// with their timeouts starting anew. Errors of `storage` are ignored.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     OrderPlaced(u32),
+    ///     OrderPaid(u32),
+    /// }
+    ///
+    /// enum Stock {
+    ///     Reserve(u32),
+    ///     Release(u32),
+    /// }
+    ///
+    /// struct Checkout;
+    ///
+    /// impl Saga<MyEvent> for Checkout {
+    ///     type Id = u32;
+    ///     type State = ();
+    ///     type Request = Stock;
+    ///
+    ///     fn correlate(&self, ev: &MyEvent) -> Option<u32> {
+    ///         match ev {
+    ///             MyEvent::OrderPlaced(id) | MyEvent::OrderPaid(id) => Some(*id),
+    ///         }
+    ///     }
+    ///
+    ///     fn start(&self, _id: &u32, ev: &MyEvent) -> Option<()> {
+    ///         matches!(ev, MyEvent::OrderPlaced(_)).then_some(())
+    ///     }
+    ///
+    ///     fn react(&self, _state: &mut (), ev: &MyEvent) -> Step<Stock> {
+    ///         match ev {
+    ///             MyEvent::OrderPlaced(id) => Step::Continue(vec![Stock::Reserve(*id)]),
+    ///             MyEvent::OrderPaid(_) => Step::Complete(vec![]),
+    ///         }
+    ///     }
+    ///
+    ///     fn compensate(&self, id: &u32, _state: &()) -> Vec<Stock> {
+    ///         vec![Stock::Release(*id)]
+    ///     }
+    ///
+    ///     fn timeout(&self) -> Option<Duration> {
+    ///         Some(Duration::from_secs(15 * 60))
+    ///     }
+    /// }
+    ///
+    /// impl TryRequestHandler<Stock, MyEvent> for BasicMediator<MyEvent> {
+    ///     type Error = String;
+    ///
+    ///     fn try_handle(&self, req: Stock) -> Result<(), String> {
+    ///         /* Your handling logic */
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .add_saga_with_storage(Checkout, MemoryStorage::new())
+    ///     .build();
+    ///
+    /// mediator.publish(MyEvent::OrderPlaced(1));
+    ///
+    /// // Dispatches the event and sends `Stock::Reserve(1)`.
+    /// mediator.next().ok();
+    ///
+    pub fn add_saga_with_storage<S, St>(self, saga: S, storage: St) -> Self
+    where
+        S: Saga<Ev>,
+        St: Storage<SagaInstance<S::Id, S::State>> + 'static,
+        BasicMediator<Ev>: TryRequestHandler<S::Request, Ev>,
+        Ev: Debug + 'static,
+    {
+        <Self as SagaBuilderInterface<BasicMediator<Ev>, Ev>>::add_saga_with_storage(
+            self, saga, storage,
+        )
+    }
+
+    /// Caches the responses to queries of type `Q` as declared by `cache`.
+    ///
+    /// [`BasicMediator::query()`](crate::synchronous::basic::SyncMediatorInternalCqrs::query)
+    /// answers a query from the cache,
+    /// as long as its response is neither expired on the [`Clock`] of the mediator
+    /// nor invalidated by a published event.
+    /// Otherwise, the query is handled and its response is cached.
+    /// Queries are compared by `Hash + Eq` and cloned to be kept as keys,
+    /// responses are cloned when answered from the cache.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::{cache::QueryCache, synchronous::basic::*};
+    /// use std::{sync::atomic::{AtomicU32, Ordering}, time::Duration};
+    ///
+    /// static BALANCE: AtomicU32 = AtomicU32::new(0);
+    /// static HANDLED: AtomicU32 = AtomicU32::new(0);
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Deposited(u32),
+    /// }
+    ///
+    /// struct Deposit(u32);
+    ///
+    /// #[derive(Hash, PartialEq, Eq, Clone)]
+    /// struct Balance;
+    ///
+    /// impl CommandHandler<Deposit, MyEvent> for BasicMediator<MyEvent> {
+    ///     fn handle(&self, cmd: Deposit) {
+    ///         BALANCE.fetch_add(cmd.0, Ordering::SeqCst);
+    ///         self.publish(MyEvent::Deposited(cmd.0));
+    ///     }
+    /// }
+    ///
+    /// impl QueryHandler<Balance, MyEvent> for BasicMediator<MyEvent> {
+    ///     type Response = u32;
+    ///
+    ///     fn handle(_: Balance, _: &QueryContext<'_, Self>) -> u32 {
+    ///         HANDLED.fetch_add(1, Ordering::SeqCst);
+    ///         BALANCE.load(Ordering::SeqCst)
+    ///     }
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .cache_queries::<Balance>(
+    ///         QueryCache::new(Duration::from_secs(60), 100)
+    ///             .invalidated_by(|ev| matches!(ev, MyEvent::Deposited(_))),
+    ///     )
+    ///     .build();
+    ///
+    /// assert_eq!(mediator.query(Balance), 0);
+    /// assert_eq!(mediator.query(Balance), 0);
+    /// assert_eq!(HANDLED.load(Ordering::SeqCst), 1);
+    ///
+    /// // Publishing `MyEvent::Deposited` drops the cached balance.
+    /// mediator.execute(Deposit(42));
+    ///
+    /// assert_eq!(mediator.query(Balance), 42);
+    /// assert_eq!(HANDLED.load(Ordering::SeqCst), 2);
+    ///
+    pub fn cache_queries<Q>(self, cache: QueryCache<Ev>) -> Self
+    where
+        BasicMediator<Ev>: QueryHandler<Q, Ev>,
+        Q: Hash + Eq + Clone + Send + 'static,
+        <BasicMediator<Ev> as QueryHandler<Q, Ev>>::Response: Clone + Send,
+    {
+        <Self as QueryCacheBuilderInterface<BasicMediator<Ev>, Ev>>::cache_queries(self, cache)
+    }
+
+    /// Adds a [`RetryPolicy`] for the request type `Req` to the [`BasicBuilder`].
+    ///
+    /// The policy is applied by
+    /// [`super::SyncMediatorInternalRetry::send_with_retry()`]
+    /// whenever a request of type `Req` fails.
+    /// Its error type is the error of the [`TryRequestHandler`] for `Req`.
+    /// A policy that was added before for the same request type is replaced.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     One,
+    ///     Two
+    /// }
+    ///
+    /// #[derive(Clone)]
+    /// struct Request(u32);
+    ///
+    /// impl TryRequestHandler<Request, MyEvent> for BasicMediator<MyEvent> {
+    ///     type Error = String;
+    ///
+    ///     fn try_handle(&self, req: Request) -> Result<(), String> {
+    ///         /* Your handling logic */
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .add_retry_policy::<Request>(
+    ///         RetryPolicy::new(3)
+    ///             .exponential(Duration::from_millis(10), Duration::from_secs(1))
+    ///             .retry_if(|err: &String| err.contains("timeout")),
+    ///     )
+    ///     .build();
+    ///
+    pub fn add_retry_policy<Req>(
+        self,
+        policy: RetryPolicy<<BasicMediator<Ev> as TryRequestHandler<Req, Ev>>::Error>,
+    ) -> Self
+    where
+        Req: 'static,
+        BasicMediator<Ev>: TryRequestHandler<Req, Ev>,
+        <BasicMediator<Ev> as TryRequestHandler<Req, Ev>>::Error: 'static,
+    {
+        <Self as RetryBuilderInterface<BasicMediator<Ev>, Ev>>::add_retry_policy::<Req, _>(
+            self, policy,
+        )
+    }
+
+    /// Replaces the [`Clock`] of the [`BasicBuilder`].
+    ///
+    /// The clock is used to wait between retries and to publish scheduled events.
+    /// Defaults to [`crate::clock::SystemClock`].
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     One,
+    ///     Two
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .clock(MockClock::new())
+    ///     .build();
+    ///
+    pub fn clock<C>(self, clock: C) -> Self
+    where
+        C: Clock,
+    {
+        <Self as RetryBuilderInterface<BasicMediator<Ev>, Ev>>::clock(self, clock)
+    }
+
+    /// Enables the dead-letter queue of the [`BasicBuilder`], backed by `storage`.
+    ///
+    /// Events dispatched while there are no listeners and events whose
+    /// listener panicked are collected in the queue instead of vanishing.
+    /// So are failed requests of the types added via
+    /// [`BasicBuilder::dead_letter_requests()`].
+    /// Use a [`crate::storage::FileStorage`] to keep the dead letters across restarts.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     One,
+    ///     Two
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .dead_letter_queue(MemoryStorage::new())
+    ///     .build();
+    ///
+    pub fn dead_letter_queue<S>(self, storage: S) -> Self
+    where
+        S: Storage<DeadLetter<Ev>> + 'static,
+    {
+        <Self as DeadLetterBuilderInterface<BasicMediator<Ev>, Ev>>::dead_letter_queue(
+            self, storage,
+        )
+    }
+
+    /// Collects requests of type `Req` in the dead-letter queue
+    /// once they failed in
+    /// [`crate::synchronous::basic::SyncMediatorInternalRetry::send_with_retry()`].
+    ///
+    /// The requests are encoded via [`Record`] and the error of the handler
+    /// is kept in its [`Debug`] representation.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     One,
+    ///     Two
+    /// }
+    ///
+    /// #[derive(Clone)]
+    /// struct Request(u32);
+    ///
+    /// impl Record for Request {
+    ///     fn encode(&self) -> String {
+    ///         self.0.to_string()
+    ///     }
+    ///
+    ///     fn decode(line: &str) -> Option<Self> {
+    ///         line.parse().ok().map(Request)
+    ///     }
+    /// }
+    ///
+    /// impl TryRequestHandler<Request, MyEvent> for BasicMediator<MyEvent> {
+    ///     type Error = String;
+    ///
+    ///     fn try_handle(&self, req: Request) -> Result<(), String> {
+    ///         /* Your handling logic */
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .dead_letter_queue(MemoryStorage::new())
+    ///     .dead_letter_requests::<Request>()
+    ///     .build();
+    ///
+    pub fn dead_letter_requests<Req>(self) -> Self
+    where
+        Req: Record + 'static,
+        BasicMediator<Ev>: TryRequestHandler<Req, Ev>,
+        <BasicMediator<Ev> as TryRequestHandler<Req, Ev>>::Error: Debug + 'static,
+    {
+        <Self as DeadLetterBuilderInterface<BasicMediator<Ev>, Ev>>::dead_letter_requests::<
+            Req,
+            <BasicMediator<Ev> as TryRequestHandler<Req, Ev>>::Error,
+        >(self)
+    }
+
+    /// Adds a debounced listener to the [`BasicBuilder`].
+    ///
+    /// The listener is called with the last event of a burst, once no further event
+    /// with the same key was dispatched for the `quiet` period on the [`Clock`] of the mediator.
+    /// The key of an event is returned by `key`, return `()` to debounce all events together.
+    /// Bursts of different keys are debounced independently.
+    /// The pending event is emitted by the first call of `next()` after the quiet period,
+    /// which `next_scheduled()` waits for.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     FileChanged(String),
+    ///     Resized(u32, u32),
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .add_debounced_listener(
+    ///         Duration::from_millis(200),
+    ///         |ev| std::mem::discriminant(ev),
+    ///         |ev| {
+    ///             /* Your listening logic, called once per burst of each kind of event */
+    ///         },
+    ///     )
+    ///     .build();
+    ///
+    pub fn add_debounced_listener<K, KF, F>(self, quiet: Duration, key: KF, f: F) -> Self
+    where
+        K: Hash + Eq + Send + 'static,
+        KF: Fn(&Ev) -> K + Send + 'static,
+        F: Listener<Ev>,
+        Ev: Send + 'static,
+    {
+        <Self as ListenerOperatorBuilderInterface<BasicMediator<Ev>, Ev>>::add_debounced_listener(
+            self, quiet, key, f,
+        )
+    }
+
+    /// Adds a throttled listener to the [`BasicBuilder`].
+    ///
+    /// The listener is called for at most `max` events per `interval` and key
+    /// on the [`Clock`] of the mediator. Further events are dropped for this listener.
+    /// The key of an event is returned by `key`, return `()` to throttle all events together.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     FileChanged(String),
+    ///     Resized(u32, u32),
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .add_throttled_listener(10, Duration::from_secs(1), |_| (), |ev| {
+    ///         /* Your listening logic, called at most 10 times per second */
+    ///     })
+    ///     .build();
+    ///
+    pub fn add_throttled_listener<K, KF, F>(
+        self,
+        max: usize,
+        interval: Duration,
+        key: KF,
+        f: F,
+    ) -> Self
+    where
+        K: Hash + Eq + Send + 'static,
+        KF: Fn(&Ev) -> K + Send + 'static,
+        F: Listener<Ev>,
+        Ev: 'static,
+    {
+        <Self as ListenerOperatorBuilderInterface<BasicMediator<Ev>, Ev>>::add_throttled_listener(
+            self, max, interval, key, f,
+        )
+    }
+
+    /// Adds a batch listener to the [`BasicBuilder`].
+    ///
+    /// The listener is called with a batch of events once it holds `max_size` events,
+    /// once its first event waited for `max_wait` on the [`Clock`] of the mediator,
+    /// or when the mediator is flushed via `flush()`.
+    /// Batches that waited for `max_wait` are emitted by the next call of `next()`,
+    /// which `next_scheduled()` waits for.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     FileChanged(String),
+    ///     Resized(u32, u32),
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .add_batch_listener(100, Duration::from_secs(1), |batch: Vec<MyEvent>| {
+    ///         /* Your listening logic, called with up to 100 events at once */
+    ///     })
+    ///     .build();
+    ///
+    pub fn add_batch_listener<F>(self, max_size: usize, max_wait: Duration, f: F) -> Self
+    where
+        F: Listener<Vec<Ev>>,
+        Ev: Clone + Send + 'static,
+    {
+        <Self as ListenerOperatorBuilderInterface<BasicMediator<Ev>, Ev>>::add_batch_listener(
+            self, max_size, max_wait, f,
+        )
+    }
+
+    /// Adds a parallel listener to the [`BasicBuilder`].
+    ///
+    /// The listener is called on a pool of `workers` threads.
+    /// Each event is routed to a worker by its `key`, so events with the same key
+    /// are processed strictly in the order they were dispatched,
+    /// while events with different keys are processed in parallel.
+    /// `flush()` waits until the workers processed all dispatched events.
+    ///
+    /// Parallel dispatch is chosen per listener: listeners added via `add_listener()`
+    /// are still called in order by `next()`, so each listener that should run
+    /// on a worker pool is added via this method with its own workers.
+    ///
+    /// A panicking listener does not stop its worker.
+    /// When the mediator is dropped, the remaining events are processed
+    /// before the workers are joined.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Deposited { account: u32, amount: u64 },
+    ///     Withdrawn { account: u32, amount: u64 },
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .add_parallel_listener(
+    ///         4,
+    ///         |ev| match ev {
+    ///             MyEvent::Deposited { account, .. } => *account,
+    ///             MyEvent::Withdrawn { account, .. } => *account,
+    ///         },
+    ///         |ev| {
+    ///             /* Your listening logic, in order per account */
+    ///         },
+    ///     )
+    ///     .build();
+    ///
+    pub fn add_parallel_listener<K, KF, F>(self, workers: usize, key: KF, f: F) -> Self
+    where
+        K: Hash,
+        KF: Fn(&Ev) -> K + Send + 'static,
+        F: Listener<Ev> + Sync,
+        Ev: Send + 'static,
+    {
+        <Self as ListenerOperatorBuilderInterface<BasicMediator<Ev>, Ev>>::add_parallel_listener(
+            self, workers, key, f,
+        )
+    }
+
+    /// Coalesces queued events with the same key before they are dispatched.
+    ///
+    /// Whenever `next()` is called, all queued events are merged:
+    /// an event whose `key` matches the key of an event that is still queued
+    /// is merged into it via `merge(queued, new)`, keeping the position of the queued event.
+    /// Events without a key are never merged.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    /// use std::time::Duration;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     FileChanged(String),
+    ///     Resized(u32, u32),
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .coalesce(
+    ///         |ev| match ev {
+    ///             MyEvent::FileChanged(path) => Some(path.clone()),
+    ///             MyEvent::Resized(..) => None,
+    ///         },
+    ///         |_queued, new| new,
+    ///     )
+    ///     .build();
+    ///
+    pub fn coalesce<K, KF, MF>(self, key: KF, merge: MF) -> Self
+    where
+        K: Eq + 'static,
+        KF: Fn(&Ev) -> Option<K> + Send + 'static,
+        MF: Fn(Ev, Ev) -> Ev + Send + 'static,
+        Ev: 'static,
+    {
+        <Self as ListenerOperatorBuilderInterface<BasicMediator<Ev>, Ev>>::coalesce(
+            self, key, merge,
+        )
+    }
+
+    /// Classifies the events of the [`BasicBuilder`] by their [`Priority`].
+    ///
+    /// `next()` always dispatches the pending event with the highest priority,
+    /// events of the same priority are dispatched in the order they were published.
+    /// Events published via `publish_with_priority()` keep their given priority.
+    /// Coalesced events are only merged with queued events of the same priority.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```
+    /// use mediator_sys::synchronous::basic::*;
+    ///
+    /// #[derive(Debug, Clone)]
+    /// enum MyEvent {
+    ///     Indexed(u32),
+    ///     Shutdown,
+    /// }
+    ///
+    /// let mediator = BasicMediator::<MyEvent>::builder()
+    ///     .prioritize(|ev| match ev {
+    ///         MyEvent::Shutdown => Priority::HIGH,
+    ///         MyEvent::Indexed(_) => Priority::LOW,
+    ///     })
+    ///     .build();
+    ///
+    pub fn prioritize<F>(self, f: F) -> Self
+    where
+        F: Fn(&Ev) -> Priority + Send + 'static,
+    {
+        <Self as PriorityBuilderInterface<BasicMediator<Ev>, Ev>>::prioritize(self, f)
+    }
 }
 
 impl<Ev> BuilderFlow<BasicMediator<Ev>> for BasicBuilder<Ev>
@@ -1,12 +1,47 @@
-use std::{fmt::Debug, sync::mpsc::TryRecvError};
+use std::{
+    fmt::Debug,
+    hash::Hash,
+    io,
+    sync::mpsc::{RecvError, TryRecvError},
+    time::{Duration, Instant},
+};
 
-use crate::mediator::listener::Listener;
+use crate::mediator::{
+    cache::QueryCache,
+    clock::Clock,
+    dead_letter::DeadLetter,
+    hierarchy::{MediatorLink, Propagation},
+    history::{CommandHistory, Undoable},
+    idempotency::{CachedOutcome, DedupStore, Idempotent},
+    listener::Listener,
+    priority::Priority,
+    retry::RetryPolicy,
+    saga::{Saga, SagaInstance},
+    schedule::ScheduledId,
+    storage::{Record, Storage},
+};
 
 /// Publish an event `Ev` from within a handler.
 pub trait SyncMediatorInternal<Ev: Debug> {
     fn publish(&self, event: Ev);
 }
 
+/// Publish an event `Ev` with a [`Priority`] from within a handler.
+pub trait SyncMediatorInternalPriority<Ev: Debug> {
+    fn publish_with_priority(&self, event: Ev, priority: Priority);
+}
+
+/// Publish an event `Ev` to a topic from within a handler.
+pub trait SyncMediatorInternalTopic<Ev: Debug> {
+    fn publish_to(&self, topic: &str, event: Ev);
+}
+
+/// Publish an event `Ev` from within a transactional handler,
+/// bypassing the outbox of the request.
+pub trait SyncMediatorInternalOutbox<Ev: Debug> {
+    fn publish_immediately(&self, event: Ev);
+}
+
 /// Send a request `Req` for processing to the mediator.
 /// This will call the handler.
 pub trait SyncMediatorInternalHandle<Ev: Debug> {
@@ -15,10 +50,136 @@ pub trait SyncMediatorInternalHandle<Ev: Debug> {
         Self: RequestHandler<Req, Ev>;
 }
 
-/// Process the next event `Ev` from the channel.
+/// Send a request `Req` for processing to the mediator.
+/// This will call the fallible handler.
+pub trait SyncMediatorInternalTryHandle<Ev: Debug> {
+    fn try_send<Req>(&self, req: Req) -> Result<(), <Self as TryRequestHandler<Req, Ev>>::Error>
+    where
+        Self: TryRequestHandler<Req, Ev>;
+}
+
+/// Execute a command `Cmd`, which may publish events, or
+/// answer a query `Q`, which cannot.
+/// This will call the command or query handler.
+pub trait SyncMediatorInternalCqrs<Ev: Debug> {
+    fn execute<Cmd>(&self, cmd: Cmd)
+    where
+        Self: CommandHandler<Cmd, Ev>;
+
+    fn query<Q>(&self, query: Q) -> <Self as QueryHandler<Q, Ev>>::Response
+    where
+        Self: QueryHandler<Q, Ev>,
+        Q: 'static;
+}
+
+/// Send an [`Undoable`] request `Req` for processing to the mediator
+/// and undo or redo the recorded requests.
+/// This will call the handler.
+pub trait SyncMediatorInternalHistory<Ev: Debug> {
+    fn send_undoable<Req>(&self, req: Req)
+    where
+        Req: Undoable,
+        Self: RequestHandler<Req, Ev> + RequestHandler<Req::Inverse, Ev>;
+
+    fn undo(&self) -> bool;
+
+    fn redo(&self) -> bool;
+
+    fn begin_group(&self, label: &str);
+
+    fn end_group(&self);
+
+    fn history(&self) -> CommandHistory;
+
+    fn clear_history(&self);
+}
+
+/// Send an [`Idempotent`] request `Req` for processing to the mediator,
+/// unless a request with the same key was already handled.
+/// This will call the handler.
+pub trait SyncMediatorInternalIdempotency<Ev: Debug> {
+    fn send_idempotent<Req>(&self, req: Req)
+    where
+        Req: Idempotent,
+        Self: RequestHandler<Req, Ev>;
+
+    fn try_send_idempotent<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), <Self as TryRequestHandler<Req, Ev>>::Error>
+    where
+        Req: Idempotent,
+        Self: TryRequestHandler<Req, Ev>,
+        <Self as TryRequestHandler<Req, Ev>>::Error: Clone + Send + Sync + 'static;
+}
+
+/// Send a request `Req` for processing to the mediator
+/// and retry it according to its [`RetryPolicy`].
+/// This will call the fallible handler.
+pub trait SyncMediatorInternalRetry<Ev: Debug> {
+    fn send_with_retry<Req>(
+        &self,
+        req: Req,
+    ) -> Result<(), <Self as TryRequestHandler<Req, Ev>>::Error>
+    where
+        Req: Clone + 'static,
+        Self: TryRequestHandler<Req, Ev>,
+        <Self as TryRequestHandler<Req, Ev>>::Error: 'static;
+}
+
+/// Inspect, re-dispatch and purge the [`DeadLetter`]s of the mediator.
+pub trait SyncMediatorInternalDeadLetter<Ev: Debug> {
+    fn dead_letters(&self) -> io::Result<Vec<DeadLetter<Ev>>>;
+
+    fn redispatch_events(&self) -> io::Result<usize>;
+
+    #[allow(clippy::type_complexity)]
+    fn redispatch_requests<Req>(
+        &self,
+    ) -> io::Result<Vec<Result<(), <Self as TryRequestHandler<Req, Ev>>::Error>>>
+    where
+        Req: Record + Clone + 'static,
+        Self: TryRequestHandler<Req, Ev>,
+        <Self as TryRequestHandler<Req, Ev>>::Error: 'static;
+
+    fn purge_dead_letters(&self) -> io::Result<usize>;
+}
+
+/// Process the next event `Ev` from the channel, or block until it arrives.
 /// This will call all listeners with a clone of that event.
 pub trait SyncMediatorInternalNext {
     fn next(&self) -> Result<(), TryRecvError>;
+
+    fn recv(&self) -> Result<(), RecvError>;
+}
+
+/// Schedule an event `Ev` to be published later or periodically.
+/// Due events are published on the next call of `next()`.
+pub trait SyncMediatorInternalSchedule<Ev: Debug> {
+    fn publish_after(&self, event: Ev, delay: Duration) -> ScheduledId;
+
+    fn publish_at(&self, event: Ev, at: Instant) -> ScheduledId;
+
+    fn publish_every<F>(&self, f: F, interval: Duration) -> ScheduledId
+    where
+        F: FnMut() -> Ev + Send + 'static;
+
+    fn cancel_scheduled(&self, id: ScheduledId) -> bool;
+
+    fn next_scheduled(&self) -> Result<(), TryRecvError>;
+}
+
+/// Flush the events buffered by batch and debounced listeners
+/// and wait for parallel listeners to process their events.
+pub trait SyncMediatorInternalFlush {
+    fn flush(&self);
+}
+
+/// Link child mediators to the mediator.
+pub trait SyncMediatorInternalHierarchy<Ev: Debug> {
+    fn link(&self) -> MediatorLink<Ev>;
+
+    fn child_count(&self) -> usize;
 }
 
 /// Handles the request `Req`.
@@ -27,6 +188,69 @@ pub trait RequestHandler<Req, Res> {
     fn handle(&self, req: Req);
 }
 
+/// Handles the request `Req` and may fail.
+/// Implemented by the user.
+pub trait TryRequestHandler<Req, Res> {
+    type Error;
+    fn try_handle(&self, req: Req) -> Result<(), Self::Error>;
+}
+
+/// Handles the command `Cmd`, which may publish events `Ev`.
+/// Implemented by the user.
+/// Unlike a [`RequestHandler`], it is only called by [`SyncMediatorInternalCqrs::execute()`],
+/// so the write side of the mediator is kept apart from plain requests.
+pub trait CommandHandler<Cmd, Ev> {
+    fn handle(&self, cmd: Cmd);
+}
+
+/// Answers the query `Q` with a `Response`.
+/// Implemented by the user.
+/// A query only reads, so the handler is not given the mediator but a
+/// [`QueryContext`], which can only answer other queries.
+pub trait QueryHandler<Q, Ev>: Sized {
+    type Response: 'static;
+    fn handle(query: Q, cx: &QueryContext<'_, Self>) -> Self::Response;
+}
+
+/// Read-only access to the mediator `M` while answering a query.
+///
+/// A query handler can answer other queries through it,
+/// but cannot publish events, send requests or execute commands.
+///
+/// # Examples
+///
+/// Publishing from a query handler does not compile:
+///
+/// ```compile_fail
+/// use mediator_sys::synchronous::basic::*;
+///
+/// #[derive(Debug, Clone)]
+/// enum MyEvent {
+///     Read,
+/// }
+///
+/// struct Balance;
+///
+/// impl QueryHandler<Balance, MyEvent> for BasicMediator<MyEvent> {
+///     type Response = u32;
+///
+///     fn handle(_: Balance, cx: &QueryContext<'_, Self>) -> u32 {
+///         cx.publish(MyEvent::Read);
+///         0
+///     }
+/// }
+///
+#[derive(Debug)]
+pub struct QueryContext<'a, M> {
+    pub(crate) mediator: &'a M,
+}
+
+impl<'a, M> QueryContext<'a, M> {
+    pub(crate) fn new(mediator: &'a M) -> Self {
+        Self { mediator }
+    }
+}
+
 /// Basic builder fuctionality:
 /// Adding a [`Listener`] to the builder.
 pub trait BasicMediatorBuilderInterface<M, Ev> {
@@ -35,3 +259,192 @@ pub trait BasicMediatorBuilderInterface<M, Ev> {
         F: Listener<Ev>,
         Ev: Debug;
 }
+
+/// Topic builder fuctionality:
+/// Adding a [`Listener`] subscribed to a topic pattern.
+pub trait TopicBuilderInterface<M, Ev> {
+    fn add_topic_listener<F>(self, pattern: &str, f: F) -> Self
+    where
+        F: Listener<Ev>,
+        Ev: Debug;
+}
+
+/// Hierarchy builder fuctionality:
+/// Linking the mediator as a child to a parent mediator.
+pub trait HierarchyBuilderInterface<M, Ev> {
+    fn parent<P>(self, parent: &P, propagation: Propagation<Ev>) -> Self
+    where
+        P: SyncMediatorInternalHierarchy<Ev>,
+        Ev: Debug;
+}
+
+/// Bridge builder fuctionality:
+/// Forwarding events to another mediator, possibly with a different event type,
+/// and linking to the mediator before it is built.
+pub trait BridgeBuilderInterface<M, Ev> {
+    fn add_bridge<T, Ev2, F>(self, target: &T, map: F) -> Self
+    where
+        T: SyncMediatorInternalHierarchy<Ev2>,
+        Ev2: Debug + Send + 'static,
+        F: Fn(Ev) -> Option<Ev2> + Send + 'static,
+        Ev: Debug + Clone;
+
+    fn link(&self) -> MediatorLink<Ev>;
+}
+
+/// Projection builder fuctionality:
+/// Adding a [`Listener`] for mapped events and deriving the mediator
+/// from another mediator, whose events it receives mapped.
+pub trait ProjectionBuilderInterface<M, Ev> {
+    fn add_mapped_listener<Ev2, MF, F>(self, map: MF, f: F) -> Self
+    where
+        MF: Fn(Ev) -> Option<Ev2> + Send + 'static,
+        F: Listener<Ev2>,
+        Ev2: Debug,
+        Ev: Debug + Clone;
+
+    fn project_from<S, Src, F>(self, source: &S, map: F) -> Self
+    where
+        S: SyncMediatorInternalHierarchy<Src>,
+        Src: Debug + Clone + 'static,
+        F: Fn(Src) -> Option<Ev> + Send + 'static,
+        Ev: Debug + Send + 'static;
+}
+
+/// Outbox builder fuctionality:
+/// Making requests handled by the mediator transactional.
+pub trait OutboxBuilderInterface<M, Ev> {
+    fn transactional(self) -> Self;
+}
+
+/// Deduplication builder fuctionality:
+/// Handling [`Idempotent`] requests and delivering [`Idempotent`] events only once.
+pub trait DeduplicationBuilderInterface<M, Ev> {
+    fn deduplicate_requests(self, capacity: usize) -> Self;
+
+    fn deduplicate_requests_with<St>(self, store: St) -> Self
+    where
+        St: DedupStore<CachedOutcome> + 'static;
+
+    fn deduplicate_events(self, capacity: usize) -> Self
+    where
+        Ev: Idempotent;
+
+    fn deduplicate_events_with<St>(self, store: St) -> Self
+    where
+        St: DedupStore<()> + 'static,
+        Ev: Idempotent;
+}
+
+/// History builder fuctionality:
+/// Recording the [`Undoable`] requests sent to the mediator.
+pub trait HistoryBuilderInterface<M, Ev> {
+    fn command_history(self, capacity: usize) -> Self;
+}
+
+/// Saga builder fuctionality:
+/// Adding a [`Saga`], whose follow-up requests are handled by the mediator.
+pub trait SagaBuilderInterface<M, Ev> {
+    fn add_saga<S>(self, saga: S) -> Self
+    where
+        S: Saga<Ev>,
+        M: TryRequestHandler<S::Request, Ev>,
+        Ev: Debug + 'static;
+
+    fn add_saga_with_storage<S, St>(self, saga: S, storage: St) -> Self
+    where
+        S: Saga<Ev>,
+        St: Storage<SagaInstance<S::Id, S::State>> + 'static,
+        M: TryRequestHandler<S::Request, Ev>,
+        Ev: Debug + 'static;
+}
+
+/// Query cache builder fuctionality:
+/// Caching the responses to queries of type `Q` with a [`QueryCache`].
+pub trait QueryCacheBuilderInterface<M, Ev> {
+    fn cache_queries<Q>(self, cache: QueryCache<Ev>) -> Self
+    where
+        M: QueryHandler<Q, Ev>,
+        Q: Hash + Eq + Clone + Send + 'static,
+        <M as QueryHandler<Q, Ev>>::Response: Clone + Send;
+}
+
+/// Listener operator builder fuctionality:
+/// Adding debounced and throttled [`Listener`]s per key, batch and parallel [`Listener`]s
+/// and coalescing queued events with the same key.
+pub trait ListenerOperatorBuilderInterface<M, Ev> {
+    fn add_debounced_listener<K, KF, F>(self, quiet: Duration, key: KF, f: F) -> Self
+    where
+        K: Hash + Eq + Send + 'static,
+        KF: Fn(&Ev) -> K + Send + 'static,
+        F: Listener<Ev>,
+        Ev: Debug + Send + 'static;
+
+    fn add_throttled_listener<K, KF, F>(
+        self,
+        max: usize,
+        interval: Duration,
+        key: KF,
+        f: F,
+    ) -> Self
+    where
+        K: Hash + Eq + Send + 'static,
+        KF: Fn(&Ev) -> K + Send + 'static,
+        F: Listener<Ev>,
+        Ev: Debug + 'static;
+
+    fn add_batch_listener<F>(self, max_size: usize, max_wait: Duration, f: F) -> Self
+    where
+        F: Listener<Vec<Ev>>,
+        Ev: Debug + Clone + Send + 'static;
+
+    fn add_parallel_listener<K, KF, F>(self, workers: usize, key: KF, f: F) -> Self
+    where
+        K: Hash,
+        KF: Fn(&Ev) -> K + Send + 'static,
+        F: Listener<Ev> + Sync,
+        Ev: Debug + Send + 'static;
+
+    fn coalesce<K, KF, MF>(self, key: KF, merge: MF) -> Self
+    where
+        K: Eq + 'static,
+        KF: Fn(&Ev) -> Option<K> + Send + 'static,
+        MF: Fn(Ev, Ev) -> Ev + Send + 'static,
+        Ev: 'static;
+}
+
+/// Priority builder fuctionality:
+/// Classifying events by their [`Priority`].
+pub trait PriorityBuilderInterface<M, Ev> {
+    fn prioritize<F>(self, f: F) -> Self
+    where
+        F: Fn(&Ev) -> Priority + Send + 'static;
+}
+
+/// Retry builder fuctionality:
+/// Adding a [`RetryPolicy`] for the request type `Req`
+/// and replacing the [`Clock`] used to wait between attempts.
+pub trait RetryBuilderInterface<M, Ev> {
+    fn add_retry_policy<Req, E>(self, policy: RetryPolicy<E>) -> Self
+    where
+        Req: 'static,
+        E: 'static;
+
+    fn clock<C>(self, clock: C) -> Self
+    where
+        C: Clock;
+}
+
+/// Dead-letter builder fuctionality:
+/// Enabling the dead-letter queue with a [`Storage`]
+/// and collecting failed requests of type `Req`.
+pub trait DeadLetterBuilderInterface<M, Ev> {
+    fn dead_letter_queue<S>(self, storage: S) -> Self
+    where
+        S: Storage<DeadLetter<Ev>> + 'static;
+
+    fn dead_letter_requests<Req, E>(self) -> Self
+    where
+        Req: Record + 'static,
+        E: Debug + 'static;
+}
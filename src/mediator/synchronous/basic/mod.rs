@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 pub(crate) mod basic;
 pub(crate) mod builder;
 pub(crate) mod interface;
@@ -7,4 +8,14 @@ pub use builder::*;
 pub use interface::*;
 
 pub use crate::builder::{BuilderFlow, BuilderInternal};
+pub use crate::clock::{Clock, MockClock, SystemClock};
+pub use crate::dead_letter::{DeadLetter, DeadLetterReason, Letter};
+pub use crate::hierarchy::{MediatorLink, Propagation};
+pub use crate::history::{CommandHistory, HistoryStep, Undoable};
+pub use crate::idempotency::{CachedOutcome, DedupStore, Idempotent, MemoryDedupStore};
 pub use crate::listener::*;
+pub use crate::priority::Priority;
+pub use crate::retry::{Backoff, RetryPolicy};
+pub use crate::saga::{Saga, SagaInstance, Step};
+pub use crate::schedule::ScheduledId;
+pub use crate::storage::{FileStorage, MemoryStorage, Record, Storage};
@@ -0,0 +1,88 @@
+use std::{collections::HashMap, fmt::Debug};
+
+/// An event along with the topic it was published to, if any.
+pub(crate) type Routed<Ev> = (Ev, Option<String>);
+
+/// A trie of topic patterns, used to route events published to a topic
+/// to the listeners subscribed with a matching pattern.
+///
+/// Topics and patterns consist of segments separated by dots, e.g. `orders.eu.created`.
+/// In patterns, the segment `*` matches exactly one segment
+/// and the segment `#` matches zero or more segments.
+/// Any other segment, including one that merely contains `*` or `#`, matches literally.
+pub(crate) struct TopicTrie<T> {
+    root: Node<T>,
+    len: usize,
+}
+
+struct Node<T> {
+    children: HashMap<String, Node<T>>,
+    values: Vec<(usize, T)>,
+}
+
+impl<T> Node<T> {
+    fn new() -> Self {
+        Self {
+            children: HashMap::new(),
+            values: vec![],
+        }
+    }
+}
+
+impl<T> TopicTrie<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            root: Node::new(),
+            len: 0,
+        }
+    }
+
+    /// Subscribes `value` to all topics matching `pattern`.
+    pub(crate) fn insert(&mut self, pattern: &str, value: T) {
+        let node = pattern.split('.').fold(&mut self.root, |node, segment| {
+            node.children
+                .entry(segment.to_owned())
+                .or_insert_with(Node::new)
+        });
+        node.values.push((self.len, value));
+        self.len += 1;
+    }
+
+    /// Returns every value subscribed to `topic`, in the order they were inserted.
+    ///
+    /// A value whose pattern matches `topic` in several ways is returned once.
+    pub(crate) fn matches(&self, topic: &str) -> Vec<&T> {
+        let segments: Vec<&str> = topic.split('.').collect();
+        let mut matched = vec![];
+        Self::collect(&self.root, &segments, &mut matched);
+        matched.sort_by_key(|entry| entry.0);
+        matched.dedup_by_key(|entry| entry.0);
+        matched.into_iter().map(|entry| &entry.1).collect()
+    }
+
+    fn collect<'a>(node: &'a Node<T>, segments: &[&str], matched: &mut Vec<&'a (usize, T)>) {
+        if let Some(rest) = node.children.get("#") {
+            for skipped in 0..=segments.len() {
+                Self::collect(rest, &segments[skipped..], matched);
+            }
+        }
+        let (segment, segments) = match segments.split_first() {
+            Some(split) => split,
+            None => return matched.extend(node.values.iter()),
+        };
+        if let Some(child) = node.children.get(*segment) {
+            Self::collect(child, segments, matched);
+        }
+        if let Some(child) = node.children.get("*") {
+            Self::collect(child, segments, matched);
+        }
+    }
+}
+
+impl<T> Debug for TopicTrie<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TopicTrie")
+            .field("subscriptions", &self.len)
+            .finish()
+    }
+}
@@ -86,6 +86,7 @@ fn atomic_test_async() {
 
 #[cfg(feature = "async")]
 #[test]
+#[allow(clippy::clone_on_copy)]
 fn cxaware_mediator_atomic_test_async() {
     use async_trait::async_trait;
     use std::sync::{Arc, Mutex};
@@ -137,6 +138,7 @@ fn cxaware_mediator_atomic_test_async() {
 
 #[cfg(feature = "async")]
 #[test]
+#[allow(clippy::clone_on_copy, clippy::assign_op_pattern)]
 fn cxaware_mediator_atomic_arc_test_async() {
     use async_trait::async_trait;
     use std::sync::{Arc, Mutex};
@@ -191,3 +193,4188 @@ fn cxaware_mediator_atomic_arc_test_async() {
         assert_eq!(*(u.lock().unwrap()), 12usize);
     })
 }
+
+#[cfg(feature = "async")]
+#[test]
+fn cxaware_mediator_container_test_async() {
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    use crate::asynchronous::contextaware::*;
+
+    #[derive(Debug)]
+    struct Db(usize);
+    #[derive(Debug)]
+    struct Clock(usize);
+
+    struct IncrementRequest;
+    #[derive(Debug, Clone)]
+    struct IncrementEvent(usize);
+
+    #[async_trait]
+    impl CxAwareAsyncRequestHandler<Container, IncrementRequest, IncrementEvent>
+        for CxAwareAsyncMediator<Container, IncrementEvent>
+    {
+        async fn handle(&self, _req: IncrementRequest, cx: &Container) {
+            let db = cx.get::<Db>().unwrap();
+            let clock = cx.get::<Clock>().unwrap();
+            self.publish(IncrementEvent(db.0 * clock.0)).await
+        }
+    }
+
+    let missing = CxAwareAsyncMediator::<Container, IncrementEvent>::builder()
+        .add_service(Db(2))
+        .require_service::<Db>()
+        .require_service::<Clock>()
+        .require_service::<String>()
+        .require_service::<Clock>()
+        .build();
+
+    match missing {
+        Err(CxAwareBuildError::MissingServices(services)) => {
+            assert_eq!(services.len(), 2);
+            assert!(services[0].ends_with("Clock"));
+            assert!(services[1].ends_with("String"));
+        }
+        _ => panic!("expected missing services"),
+    }
+
+    async_std::task::block_on(async {
+        let u = Arc::new(Mutex::new(0usize));
+        let cloned = u.clone();
+        let async_mediator = CxAwareAsyncMediator::<Container, IncrementEvent>::builder()
+            .add_listener(move |x: IncrementEvent| {
+                let mut m = cloned.lock().unwrap();
+                *m += x.0;
+            })
+            .require_service::<Db>()
+            .require_service::<Clock>()
+            .add_service(Db(2))
+            .add_service(Clock(1))
+            .add_service(Clock(3))
+            .build()
+            .unwrap();
+
+        async_mediator.send(IncrementRequest).await;
+        async_mediator.next().await.ok();
+
+        assert_eq!(*(u.lock().unwrap()), 6usize);
+    })
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn cxaware_mediator_scoped_test_async() {
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    use crate::asynchronous::contextaware::*;
+
+    struct Transaction(usize);
+    struct IncrementRequest(bool);
+    #[derive(Debug, Clone)]
+    struct IncrementEvent(usize);
+
+    #[async_trait]
+    impl TryCxAwareAsyncRequestHandler<usize, IncrementRequest, IncrementEvent>
+        for CxAwareAsyncMediator<usize, IncrementEvent>
+    {
+        type Error = ();
+
+        async fn try_handle(
+            &self,
+            req: IncrementRequest,
+            _dep: &usize,
+            scope: &mut Container,
+        ) -> Result<(), ()> {
+            let tx = scope.get_mut::<Transaction>().unwrap();
+            tx.0 += 1;
+            self.publish(IncrementEvent(tx.0)).await;
+            req.0.then_some(()).ok_or(())
+        }
+    }
+
+    async_std::task::block_on(async {
+        let u = Arc::new(Mutex::new(0usize));
+        let cloned_u = u.clone();
+        let outcomes = Arc::new(Mutex::new(vec![]));
+        let cloned = outcomes.clone();
+        let async_mediator = CxAwareAsyncMediator::<usize, IncrementEvent>::builder()
+            .add_listener(move |x: IncrementEvent| {
+                let mut m = cloned_u.lock().unwrap();
+                *m += x.0;
+            })
+            .add_dependency(10)
+            .add_scoped(
+                |dep: &usize| Transaction(*dep),
+                move |tx, outcome| cloned.lock().unwrap().push((tx.0, outcome)),
+            )
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            async_mediator.try_send(IncrementRequest(true)).await,
+            Ok(())
+        );
+        assert_eq!(
+            async_mediator.try_send(IncrementRequest(false)).await,
+            Err(())
+        );
+
+        async_mediator.next().await.ok();
+        async_mediator.next().await.ok();
+        assert_eq!(*(u.lock().unwrap()), 22usize);
+
+        assert_eq!(
+            *outcomes.lock().unwrap(),
+            vec![(11, Outcome::Success), (11, Outcome::Failure)]
+        );
+    })
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn cxaware_mediator_scoped_send_test_async() {
+    use async_trait::async_trait;
+    use futures::FutureExt;
+    use std::{
+        panic::AssertUnwindSafe,
+        sync::{Arc, Mutex},
+    };
+
+    use crate::asynchronous::contextaware::*;
+
+    struct Transaction(usize);
+    enum Request {
+        Increment,
+        Hang,
+        Panic,
+    }
+    #[derive(Debug, Clone)]
+    struct IncrementEvent(usize);
+
+    #[async_trait]
+    impl CxAwareAsyncRequestHandler<usize, Request, IncrementEvent>
+        for CxAwareAsyncMediator<usize, IncrementEvent>
+    {
+        async fn handle(&self, _req: Request, _dep: &usize) {
+            unreachable!()
+        }
+
+        async fn handle_scoped(&self, req: Request, _dep: &usize, scope: &mut Container) {
+            let tx = scope.get_mut::<Transaction>().unwrap();
+            tx.0 += 1;
+            match req {
+                Request::Increment => self.publish(IncrementEvent(tx.0)).await,
+                Request::Hang => futures::future::pending().await,
+                Request::Panic => panic!("handler failed"),
+            }
+        }
+    }
+
+    async_std::task::block_on(async {
+        let received = Arc::new(Mutex::new(vec![]));
+        let cloned_received = received.clone();
+        let outcomes = Arc::new(Mutex::new(vec![]));
+        let cloned = outcomes.clone();
+        let async_mediator = CxAwareAsyncMediator::<usize, IncrementEvent>::builder()
+            .add_listener(move |x: IncrementEvent| cloned_received.lock().unwrap().push(x.0))
+            .add_dependency(10)
+            .add_scoped(
+                |dep: &usize| Transaction(*dep),
+                move |tx, outcome| cloned.lock().unwrap().push((tx.0, outcome)),
+            )
+            .build()
+            .unwrap();
+
+        async_mediator.send(Request::Increment).await;
+        assert!(async_mediator.send(Request::Hang).now_or_never().is_none());
+        assert!(AssertUnwindSafe(async_mediator.send(Request::Panic))
+            .catch_unwind()
+            .await
+            .is_err());
+        while async_mediator.next().await.is_ok() {}
+
+        assert_eq!(*received.lock().unwrap(), vec![11]);
+
+        assert_eq!(
+            *outcomes.lock().unwrap(),
+            vec![
+                (11, Outcome::Success),
+                (11, Outcome::Failure),
+                (11, Outcome::Failure)
+            ]
+        );
+    })
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn cxaware_mediator_replace_dependency_test_async() {
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    use crate::asynchronous::contextaware::*;
+
+    struct IncrementRequest;
+    struct RotateRequest;
+    #[derive(Debug, Clone)]
+    struct IncrementEvent(usize);
+
+    #[async_trait]
+    impl CxAwareAsyncRequestHandler<usize, IncrementRequest, IncrementEvent>
+        for CxAwareAsyncMediator<usize, IncrementEvent>
+    {
+        async fn handle(&self, _req: IncrementRequest, dep: &usize) {
+            self.publish(IncrementEvent(*dep)).await
+        }
+    }
+
+    #[async_trait]
+    impl CxAwareAsyncRequestHandler<usize, RotateRequest, IncrementEvent>
+        for CxAwareAsyncMediator<usize, IncrementEvent>
+    {
+        async fn handle(&self, _req: RotateRequest, dep: &usize) {
+            self.update_dependency(|dep| dep * 10).await;
+            self.publish(IncrementEvent(*dep)).await
+        }
+    }
+
+    async_std::task::block_on(async {
+        let u = Arc::new(Mutex::new(vec![]));
+        let cloned = u.clone();
+        let async_mediator = CxAwareAsyncMediator::<usize, IncrementEvent>::builder()
+            .add_listener(move |x: IncrementEvent| cloned.lock().unwrap().push(x.0))
+            .add_dependency(1)
+            .build()
+            .unwrap();
+
+        async_mediator.send(IncrementRequest).await;
+        async_mediator.send(RotateRequest).await;
+        async_mediator.send(IncrementRequest).await;
+
+        let old = async_mediator.replace_dependency(3).await;
+        assert_eq!(*old, 10);
+        async_mediator.send(IncrementRequest).await;
+
+        while async_mediator.next().await.is_ok() {}
+
+        assert_eq!(*(u.lock().unwrap()), vec![1, 1, 10, 3]);
+    })
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn notification_test_async() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::asynchronous::contextaware::*;
+
+    #[derive(Debug, Clone)]
+    struct IncrementEvent;
+    #[derive(Clone)]
+    struct IncrementNotification(usize);
+
+    impl Notification for IncrementNotification {
+        type Error = usize;
+    }
+
+    async_std::task::block_on(async {
+        let u = Arc::new(Mutex::new(0usize));
+        let first = u.clone();
+        let second = u.clone();
+        let async_mediator = CxAwareAsyncMediator::<usize, IncrementEvent>::builder()
+            .add_dependency(2)
+            .add_notification_handler(move |n: IncrementNotification, dep: Arc<usize>| {
+                let first = first.clone();
+                async move {
+                    *first.lock().unwrap() += n.0 * *dep;
+                    Err(1)
+                }
+            })
+            .add_notification_handler(move |n: IncrementNotification, _| {
+                let second = second.clone();
+                async move {
+                    *second.lock().unwrap() += n.0;
+                    Err(2)
+                }
+            })
+            .build()
+            .unwrap();
+
+        let result = async_mediator
+            .publish_notification(IncrementNotification(1))
+            .await;
+        assert_eq!(result, Err(vec![1, 2]));
+        assert_eq!(*(u.lock().unwrap()), 3usize);
+
+        let result = async_mediator
+            .publish_notification_with(IncrementNotification(1), NotificationStrategy::Parallel)
+            .await;
+        assert_eq!(result, Err(vec![1, 2]));
+        assert_eq!(*(u.lock().unwrap()), 6usize);
+
+        let result = async_mediator
+            .publish_notification_with(
+                IncrementNotification(1),
+                NotificationStrategy::StopOnFirstError,
+            )
+            .await;
+        assert_eq!(result, Err(vec![1]));
+        assert_eq!(*(u.lock().unwrap()), 8usize);
+    })
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn stream_test_async() {
+    use async_trait::async_trait;
+    use futures::StreamExt;
+
+    use crate::asynchronous::contextaware::*;
+
+    struct IncrementRequest;
+    #[derive(Debug, Clone, PartialEq)]
+    struct IncrementEvent(usize);
+
+    #[async_trait]
+    impl CxAwareAsyncRequestHandler<usize, IncrementRequest, IncrementEvent>
+        for CxAwareAsyncMediator<usize, IncrementEvent>
+    {
+        async fn handle(&self, _req: IncrementRequest, dep: &usize) {
+            self.publish(IncrementEvent(*dep)).await
+        }
+    }
+
+    async_std::task::block_on(async {
+        let async_mediator = CxAwareAsyncMediator::<usize, IncrementEvent>::builder()
+            .add_dependency(1)
+            .build()
+            .unwrap();
+
+        let early = async_mediator.subscribe_stream().await;
+        let dropped = async_mediator.subscribe_stream().await;
+        drop(dropped);
+
+        async_mediator.send(IncrementRequest).await;
+        async_mediator.next().await.ok();
+
+        let late = async_mediator.subscribe_stream().await;
+        async_mediator.replace_dependency(2).await;
+        async_mediator.send(IncrementRequest).await;
+        async_mediator.next().await.ok();
+
+        drop(async_mediator);
+
+        let early: Vec<IncrementEvent> = early.collect().await;
+        let late: Vec<IncrementEvent> = late.map(|ev| IncrementEvent(ev.0 * 10)).collect().await;
+        assert_eq!(early, vec![IncrementEvent(1), IncrementEvent(2)]);
+        assert_eq!(late, vec![IncrementEvent(20)]);
+    })
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn stream_bounded_test_async() {
+    use futures::StreamExt;
+
+    use crate::asynchronous::basic::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Tick(u32);
+
+    async_std::task::block_on(async {
+        let async_mediator = BasicAsyncMediator::<Tick>::builder().build();
+
+        let mut lagging = async_mediator
+            .subscribe_stream_bounded(2, Overflow::DropNewest)
+            .await;
+        let closing = async_mediator
+            .subscribe_stream_bounded(2, Overflow::Close)
+            .await;
+        let forgotten = async_mediator.subscribe_stream().await;
+        assert!(format!("{:?}", async_mediator).contains("Subscribers { count: 3 }"));
+
+        // Dropped streams are unsubscribed without waiting for the next event.
+        drop(forgotten);
+        assert!(format!("{:?}", async_mediator).contains("Subscribers { count: 2 }"));
+
+        for n in 0..3 {
+            async_mediator.publish(Tick(n)).await;
+        }
+        while async_mediator.next().await.is_ok() {}
+        assert_eq!(lagging.lagged(), 1);
+        assert_eq!(closing.lagged(), 1);
+        assert!(format!("{:?}", async_mediator).contains("Subscribers { count: 1 }"));
+
+        // Consuming the buffer makes room for new events again.
+        assert_eq!(lagging.next().await, Some(Tick(0)));
+        assert_eq!(lagging.next().await, Some(Tick(1)));
+        async_mediator.publish(Tick(3)).await;
+        async_mediator.next().await.ok();
+        drop(async_mediator);
+
+        let lagging: Vec<Tick> = lagging.collect().await;
+        let closing: Vec<Tick> = closing.collect().await;
+        assert_eq!(lagging, vec![Tick(3)]);
+        assert_eq!(closing, vec![Tick(0), Tick(1)]);
+    })
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn sink_test_async() {
+    use async_trait::async_trait;
+    use futures::{stream, StreamExt};
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use crate::asynchronous::basic::*;
+
+    static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+    static MAX_IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+    struct IncrementRequest(usize);
+    #[derive(Debug, Clone)]
+    struct IncrementEvent(usize);
+
+    #[async_trait]
+    impl TryAsyncRequestHandler<IncrementRequest, IncrementEvent>
+        for BasicAsyncMediator<IncrementEvent>
+    {
+        type Error = usize;
+
+        async fn try_handle(&self, req: IncrementRequest) -> Result<(), usize> {
+            let current = IN_FLIGHT.fetch_add(1, Ordering::SeqCst) + 1;
+            MAX_IN_FLIGHT.fetch_max(current, Ordering::SeqCst);
+            async_std::task::sleep(Duration::from_millis(5)).await;
+            IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+
+            if req.0 == 0 {
+                return Err(req.0);
+            }
+            self.publish(IncrementEvent(req.0)).await;
+            Ok(())
+        }
+    }
+
+    async_std::task::block_on(async {
+        let async_mediator = BasicAsyncMediator::<IncrementEvent>::builder().build();
+        let events = async_mediator.subscribe_stream().await;
+
+        let requests = stream::iter((1..=6).map(IncrementRequest)).map(Ok);
+        let result = requests.forward(async_mediator.request_sink(2)).await;
+        assert_eq!(result, Ok(()));
+        assert_eq!(MAX_IN_FLIGHT.load(Ordering::SeqCst), 2);
+
+        let requests = stream::iter([1, 0, 1].map(IncrementRequest)).map(Ok);
+        let result = requests.forward(async_mediator.request_sink(1)).await;
+        assert_eq!(result, Err(0));
+
+        while async_mediator.next().await.is_ok() {}
+        drop(async_mediator);
+
+        let sum: usize = events.map(|ev| ev.0).collect::<Vec<_>>().await.iter().sum();
+        assert_eq!(sum, 22);
+    })
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn timeout_cancellation_test_async() {
+    use async_trait::async_trait;
+    use std::{
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    use crate::asynchronous::{basic::*, cancellation};
+
+    struct OuterRequest;
+    struct InnerRequest;
+    struct SlowRequest;
+    struct FinishingRequest(CancellationToken);
+    #[derive(Debug, Clone)]
+    enum DeadlineEvent {
+        Outer(Option<Instant>),
+        Inner(Option<Instant>),
+        Finished,
+    }
+
+    #[async_trait]
+    impl AsyncRequestHandler<OuterRequest, DeadlineEvent> for BasicAsyncMediator<DeadlineEvent> {
+        async fn handle(&self, _req: OuterRequest) {
+            let deadline = cancellation::current().deadline();
+            self.publish(DeadlineEvent::Outer(deadline)).await;
+            self.send(InnerRequest).await;
+            self.send_with_timeout(InnerRequest, Duration::from_secs(60))
+                .await
+                .unwrap();
+        }
+    }
+
+    #[async_trait]
+    impl AsyncRequestHandler<InnerRequest, DeadlineEvent> for BasicAsyncMediator<DeadlineEvent> {
+        async fn handle(&self, _req: InnerRequest) {
+            let deadline = cancellation::current().deadline();
+            self.publish(DeadlineEvent::Inner(deadline)).await
+        }
+    }
+
+    #[async_trait]
+    impl AsyncRequestHandler<SlowRequest, DeadlineEvent> for BasicAsyncMediator<DeadlineEvent> {
+        async fn handle(&self, _req: SlowRequest) {
+            futures::future::pending::<()>().await
+        }
+    }
+
+    #[async_trait]
+    impl AsyncRequestHandler<FinishingRequest, DeadlineEvent> for BasicAsyncMediator<DeadlineEvent> {
+        async fn handle(&self, req: FinishingRequest) {
+            self.publish(DeadlineEvent::Finished).await;
+            req.0.cancel();
+            assert!(cancellation::current().is_interrupted());
+        }
+    }
+
+    async_std::task::block_on(async {
+        let deadlines = Arc::new(Mutex::new(vec![]));
+        let cloned = deadlines.clone();
+        let async_mediator = BasicAsyncMediator::<DeadlineEvent>::builder()
+            .add_listener(move |ev| cloned.lock().unwrap().push(ev))
+            .build();
+
+        async_mediator.send(InnerRequest).await;
+        let result = async_mediator
+            .send_with_timeout(OuterRequest, Duration::from_secs(10))
+            .await;
+        assert_eq!(result, Ok(()));
+        while async_mediator.next().await.is_ok() {}
+
+        {
+            let deadlines = deadlines.lock().unwrap();
+            assert!(matches!(deadlines[0], DeadlineEvent::Inner(None)));
+            let outer = match deadlines[1] {
+                DeadlineEvent::Outer(Some(deadline)) => deadline,
+                _ => panic!("expected outer deadline"),
+            };
+            assert!(matches!(deadlines[2], DeadlineEvent::Inner(Some(d)) if d == outer));
+            assert!(matches!(deadlines[3], DeadlineEvent::Inner(Some(d)) if d == outer));
+        }
+
+        let result = async_mediator
+            .send_with_timeout(SlowRequest, Duration::from_millis(10))
+            .await;
+        assert_eq!(result, Err(Interrupted::TimedOut));
+
+        let token = CancellationToken::new();
+        let cancel = {
+            let token = token.clone();
+            async move {
+                async_std::task::sleep(Duration::from_millis(10)).await;
+                token.cancel();
+            }
+        };
+        let (result, _) = futures::join!(
+            async_mediator.send_with_cancellation(SlowRequest, token),
+            cancel
+        );
+        assert_eq!(result, Err(Interrupted::Cancelled));
+
+        // A handler that finished despite an interruption took effect.
+        deadlines.lock().unwrap().clear();
+        let token = CancellationToken::new();
+        let result = async_mediator
+            .send_with_cancellation(FinishingRequest(token.clone()), token)
+            .await;
+        assert_eq!(result, Ok(()));
+        async_mediator.next().await.ok();
+        assert!(matches!(
+            deadlines.lock().unwrap()[..],
+            [DeadlineEvent::Finished]
+        ));
+    })
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn retry_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    #[derive(Clone)]
+    struct IncrementRequest(usize);
+    #[derive(Debug, Clone)]
+    struct IncrementEvent;
+    #[derive(Debug, PartialEq)]
+    enum IncrementError {
+        Busy,
+        Invalid,
+    }
+
+    let attempts = Arc::new(Mutex::new(0usize));
+    let cloned = attempts.clone();
+
+    impl TryRequestHandler<IncrementRequest, IncrementEvent> for BasicMediator<IncrementEvent> {
+        type Error = IncrementError;
+
+        fn try_handle(&self, req: IncrementRequest) -> Result<(), IncrementError> {
+            match req.0 {
+                0 => Err(IncrementError::Invalid),
+                1 => Err(IncrementError::Busy),
+                _ => {
+                    self.publish(IncrementEvent);
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    let clock = Arc::new(MockClock::new());
+    let mediator = BasicMediator::<IncrementEvent>::builder()
+        .add_listener(move |_| *cloned.lock().unwrap() += 1)
+        .add_retry_policy::<IncrementRequest>(
+            RetryPolicy::new(5)
+                .exponential(Duration::from_millis(10), Duration::from_millis(50))
+                .retry_if(|err| *err == IncrementError::Busy),
+        )
+        .clock(clock.clone())
+        .build();
+
+    assert_eq!(mediator.send_with_retry(IncrementRequest(2)), Ok(()));
+    assert!(clock.sleeps().is_empty());
+
+    assert_eq!(
+        mediator.send_with_retry(IncrementRequest(0)),
+        Err(IncrementError::Invalid)
+    );
+    assert!(clock.sleeps().is_empty());
+
+    assert_eq!(
+        mediator.send_with_retry(IncrementRequest(1)),
+        Err(IncrementError::Busy)
+    );
+    assert_eq!(
+        clock.sleeps(),
+        [10, 20, 40, 50].map(Duration::from_millis).to_vec()
+    );
+
+    mediator.next().ok();
+    assert_eq!(*(attempts.lock().unwrap()), 1usize);
+
+    let policy = RetryPolicy::<()>::new(100)
+        .fixed(Duration::from_millis(100))
+        .with_jitter(0.5)
+        .with_seed(7);
+    let clock = MockClock::new();
+    assert_eq!(
+        crate::retry::retry(&policy, &clock, (), |_| Err(())),
+        Err(())
+    );
+    let sleeps = clock.sleeps();
+    assert_eq!(sleeps.len(), 99);
+    assert!(sleeps
+        .iter()
+        .all(|d| *d > Duration::from_millis(50) && *d <= Duration::from_millis(100)));
+    assert!(sleeps.windows(2).any(|w| w[0] != w[1]));
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn retry_test_async() {
+    use async_trait::async_trait;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use crate::asynchronous::contextaware::*;
+
+    struct Attempt(usize);
+    #[derive(Clone)]
+    struct IncrementRequest(usize);
+    #[derive(Debug, Clone)]
+    struct IncrementEvent;
+
+    #[async_trait]
+    impl TryCxAwareAsyncRequestHandler<Arc<Mutex<usize>>, IncrementRequest, IncrementEvent>
+        for CxAwareAsyncMediator<Arc<Mutex<usize>>, IncrementEvent>
+    {
+        type Error = usize;
+
+        async fn try_handle(
+            &self,
+            req: IncrementRequest,
+            _dep: &Arc<Mutex<usize>>,
+            scope: &mut Container,
+        ) -> Result<(), usize> {
+            let attempt = scope.get::<Attempt>().unwrap().0;
+            if attempt < req.0 {
+                return Err(attempt);
+            }
+            self.publish(IncrementEvent).await;
+            Ok(())
+        }
+    }
+
+    async_std::task::block_on(async {
+        let outcomes = Arc::new(Mutex::new(vec![]));
+        let cloned = outcomes.clone();
+        let clock = Arc::new(MockClock::new());
+        let async_mediator = CxAwareAsyncMediator::<Arc<Mutex<usize>>, IncrementEvent>::builder()
+            .add_dependency(Arc::new(Mutex::new(0)))
+            .add_scoped(
+                |attempts: &Arc<Mutex<usize>>| {
+                    let mut attempts = attempts.lock().unwrap();
+                    *attempts += 1;
+                    Attempt(*attempts)
+                },
+                move |_, outcome| cloned.lock().unwrap().push(outcome),
+            )
+            .add_retry_policy::<IncrementRequest>(RetryPolicy::new(3).fixed(Duration::from_secs(1)))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            async_mediator.send_with_retry(IncrementRequest(3)).await,
+            Ok(())
+        );
+        assert_eq!(
+            *outcomes.lock().unwrap(),
+            vec![Outcome::Failure, Outcome::Failure, Outcome::Success]
+        );
+        assert_eq!(clock.sleeps(), vec![Duration::from_secs(1); 2]);
+
+        assert_eq!(
+            async_mediator.send_with_retry(IncrementRequest(10)).await,
+            Err(6)
+        );
+        assert_eq!(clock.sleeps().len(), 4);
+    })
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn circuit_breaker_bulkhead_test_async() {
+    use async_trait::async_trait;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use crate::asynchronous::guard::BreakerTransition;
+
+    #[derive(Debug, Clone)]
+    enum MyEvent {
+        Breaker(BreakerTransition),
+    }
+
+    struct FlakyRequest(bool);
+    struct SlowRequest;
+
+    {
+        use crate::asynchronous::basic::*;
+
+        #[async_trait]
+        impl TryAsyncRequestHandler<FlakyRequest, MyEvent> for BasicAsyncMediator<MyEvent> {
+            type Error = &'static str;
+
+            async fn try_handle(&self, req: FlakyRequest) -> Result<(), &'static str> {
+                match req.0 {
+                    true => Ok(()),
+                    false => Err("unavailable"),
+                }
+            }
+        }
+
+        async_std::task::block_on(async {
+            let transitions = Arc::new(Mutex::new(vec![]));
+            let cloned = transitions.clone();
+            let clock = Arc::new(MockClock::new());
+            let mediator = BasicAsyncMediator::<MyEvent>::builder()
+                .add_listener(move |MyEvent::Breaker(t)| {
+                    cloned.lock().unwrap().push((t.from, t.to))
+                })
+                .add_circuit_breaker::<FlakyRequest>(
+                    CircuitBreaker::new(2).open_for(Duration::from_secs(10)),
+                )
+                .on_breaker_transition(MyEvent::Breaker)
+                .clock(clock.clone())
+                .build();
+
+            assert_eq!(
+                mediator.send_guarded(FlakyRequest(false)).await,
+                Err(GuardError::Handler("unavailable"))
+            );
+            assert_eq!(mediator.send_guarded(FlakyRequest(true)).await, Ok(()));
+            assert_eq!(
+                mediator.breaker_state::<FlakyRequest>(),
+                Some(BreakerState::Closed)
+            );
+
+            mediator.send_guarded(FlakyRequest(false)).await.ok();
+            mediator.send_guarded(FlakyRequest(false)).await.ok();
+            assert_eq!(
+                mediator.breaker_state::<FlakyRequest>(),
+                Some(BreakerState::Open)
+            );
+            assert_eq!(
+                mediator.send_guarded(FlakyRequest(true)).await,
+                Err(GuardError::CircuitOpen)
+            );
+
+            clock.advance(Duration::from_secs(10));
+            assert_eq!(
+                mediator.send_guarded(FlakyRequest(false)).await,
+                Err(GuardError::Handler("unavailable"))
+            );
+            assert_eq!(
+                mediator.send_guarded(FlakyRequest(true)).await,
+                Err(GuardError::CircuitOpen)
+            );
+
+            clock.advance(Duration::from_secs(10));
+            assert_eq!(mediator.send_guarded(FlakyRequest(true)).await, Ok(()));
+
+            while mediator.next().await.is_ok() {}
+            assert_eq!(
+                *transitions.lock().unwrap(),
+                vec![
+                    (BreakerState::Closed, BreakerState::Open),
+                    (BreakerState::Open, BreakerState::HalfOpen),
+                    (BreakerState::HalfOpen, BreakerState::Open),
+                    (BreakerState::Open, BreakerState::HalfOpen),
+                    (BreakerState::HalfOpen, BreakerState::Closed),
+                ]
+            );
+        });
+    }
+
+    {
+        use crate::asynchronous::contextaware::*;
+
+        #[async_trait]
+        impl TryCxAwareAsyncRequestHandler<Arc<Mutex<usize>>, SlowRequest, MyEvent>
+            for CxAwareAsyncMediator<Arc<Mutex<usize>>, MyEvent>
+        {
+            type Error = ();
+
+            async fn try_handle(
+                &self,
+                _req: SlowRequest,
+                dep: &Arc<Mutex<usize>>,
+                _scope: &mut Container,
+            ) -> Result<(), ()> {
+                async_std::task::sleep(Duration::from_millis(50)).await;
+                *dep.lock().unwrap() += 1;
+                Ok(())
+            }
+        }
+
+        async_std::task::block_on(async {
+            let handled = Arc::new(Mutex::new(0usize));
+            let mediator = CxAwareAsyncMediator::<Arc<Mutex<usize>>, MyEvent>::builder()
+                .add_dependency(handled.clone())
+                .add_bulkhead::<SlowRequest>(1)
+                .build()
+                .unwrap();
+
+            let (first, second) = futures::join!(
+                mediator.send_guarded(SlowRequest),
+                mediator.send_guarded(SlowRequest)
+            );
+            assert_eq!(first, Ok(()));
+            assert_eq!(second, Err(GuardError::BulkheadFull));
+            assert!(second.unwrap_err().is_rejected());
+
+            assert_eq!(mediator.send_guarded(SlowRequest).await, Ok(()));
+            assert_eq!(*handled.lock().unwrap(), 2);
+            assert_eq!(mediator.breaker_state::<SlowRequest>(), None);
+        });
+    }
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn dead_letter_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct OrderPlaced(String);
+
+    impl Record for OrderPlaced {
+        fn encode(&self) -> String {
+            self.0.encode()
+        }
+
+        fn decode(line: &str) -> Option<Self> {
+            String::decode(line).map(OrderPlaced)
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct PlaceOrder(u32);
+
+    impl Record for PlaceOrder {
+        fn encode(&self) -> String {
+            self.0.to_string()
+        }
+
+        fn decode(line: &str) -> Option<Self> {
+            line.parse().ok().map(PlaceOrder)
+        }
+    }
+
+    static AVAILABLE: AtomicBool = AtomicBool::new(false);
+
+    impl TryRequestHandler<PlaceOrder, OrderPlaced> for BasicMediator<OrderPlaced> {
+        type Error = String;
+
+        fn try_handle(&self, req: PlaceOrder) -> Result<(), String> {
+            if !AVAILABLE.load(Ordering::SeqCst) {
+                return Err(String::from("warehouse\toffline"));
+            }
+            self.publish(OrderPlaced(format!("order #{}\nconfirmed", req.0)));
+            Ok(())
+        }
+    }
+
+    let path = std::env::temp_dir().join(format!(
+        "mediator-sys-dead-letter-test-{}.log",
+        std::process::id()
+    ));
+    std::fs::remove_file(&path).ok();
+
+    let received = Arc::new(Mutex::new(vec![]));
+    {
+        let mediator = BasicMediator::<OrderPlaced>::builder()
+            .dead_letter_queue(FileStorage::new(&path))
+            .dead_letter_requests::<PlaceOrder>()
+            .build();
+
+        mediator.publish(OrderPlaced(String::from("lost")));
+        mediator.next().ok();
+
+        assert!(mediator.send_with_retry(PlaceOrder(7)).is_err());
+        assert!(mediator.try_send(PlaceOrder(8)).is_err());
+    }
+
+    let cloned = received.clone();
+    let mediator = BasicMediator::<OrderPlaced>::builder()
+        .add_listener(move |ev| {
+            if ev.0 == "lost" {
+                panic!("listener failed");
+            }
+            cloned.lock().unwrap().push(ev)
+        })
+        .dead_letter_queue(FileStorage::new(&path))
+        .dead_letter_requests::<PlaceOrder>()
+        .build();
+
+    let letters = mediator.dead_letters().unwrap();
+    assert_eq!(
+        letters,
+        vec![
+            DeadLetter {
+                id: 0,
+                reason: DeadLetterReason::NoListeners,
+                letter: Letter::Event(OrderPlaced(String::from("lost"))),
+            },
+            DeadLetter {
+                id: 1,
+                reason: DeadLetterReason::RequestFailed(String::from("\"warehouse\\toffline\"")),
+                letter: Letter::Request {
+                    type_name: std::any::type_name::<PlaceOrder>().to_owned(),
+                    payload: String::from("7"),
+                },
+            },
+        ]
+    );
+
+    AVAILABLE.store(true, Ordering::SeqCst);
+    assert_eq!(
+        mediator.redispatch_requests::<PlaceOrder>().unwrap(),
+        vec![Ok(())]
+    );
+    assert_eq!(mediator.redispatch_events().unwrap(), 1);
+    while mediator.next().is_ok() {}
+
+    assert_eq!(
+        *received.lock().unwrap(),
+        vec![OrderPlaced(String::from("order #7\nconfirmed"))]
+    );
+    let letters = mediator.dead_letters().unwrap();
+    assert_eq!(letters.len(), 1);
+    assert_eq!(letters[0].id, 2);
+    assert_eq!(letters[0].reason, DeadLetterReason::ListenerPanicked);
+
+    assert_eq!(mediator.purge_dead_letters().unwrap(), 1);
+    assert!(mediator.dead_letters().unwrap().is_empty());
+    std::fs::remove_file(&path).ok();
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn dead_letter_storage_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::{
+        io,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Lost(u32);
+
+    /// A storage that fails while `offline` is set.
+    #[derive(Debug)]
+    struct Flaky {
+        offline: Arc<AtomicBool>,
+        items: MemoryStorage<DeadLetter<Lost>>,
+    }
+
+    impl Flaky {
+        fn check(&self) -> io::Result<()> {
+            match self.offline.load(Ordering::SeqCst) {
+                true => Err(io::Error::other("offline")),
+                false => Ok(()),
+            }
+        }
+    }
+
+    impl Storage<DeadLetter<Lost>> for Flaky {
+        fn append(&self, item: &DeadLetter<Lost>) -> io::Result<()> {
+            self.check()?;
+            self.items.append(item)
+        }
+
+        fn load(&self) -> io::Result<Vec<DeadLetter<Lost>>> {
+            self.check()?;
+            self.items.load()
+        }
+
+        fn replace(&self, items: Vec<DeadLetter<Lost>>) -> io::Result<()> {
+            self.check()?;
+            self.items.replace(items)
+        }
+    }
+
+    let offline = Arc::new(AtomicBool::new(true));
+    let mediator = BasicMediator::<Lost>::builder()
+        .dead_letter_queue(Flaky {
+            offline: offline.clone(),
+            items: MemoryStorage::new(),
+        })
+        .build();
+
+    // Letters that cannot be stored are kept and the error is surfaced.
+    mediator.publish(Lost(1));
+    mediator.publish(Lost(2));
+    while mediator.next().is_ok() {}
+    assert_eq!(
+        mediator.dead_letters().unwrap_err().kind(),
+        io::ErrorKind::Other
+    );
+    assert!(mediator.redispatch_events().is_err());
+
+    offline.store(false, Ordering::SeqCst);
+    let letters: Vec<Letter<Lost>> = mediator
+        .dead_letters()
+        .unwrap()
+        .into_iter()
+        .map(|letter| letter.letter)
+        .collect();
+    assert_eq!(
+        letters,
+        vec![Letter::Event(Lost(1)), Letter::Event(Lost(2))]
+    );
+
+    // Replacing a file does not touch files of other storages sharing its stem.
+    let path = std::env::temp_dir().join(format!(
+        "mediator-sys-dead-letter-storage-test-{}.log",
+        std::process::id()
+    ));
+    let other = path.with_extension("tmp");
+    std::fs::write(&other, "other storage").unwrap();
+    let storage = FileStorage::<String>::new(&path);
+    storage.append(&String::from("kept")).unwrap();
+    storage.replace(vec![String::from("replaced")]).unwrap();
+    assert_eq!(storage.load().unwrap(), vec![String::from("replaced")]);
+    assert_eq!(std::fs::read_to_string(&other).unwrap(), "other storage");
+    std::fs::remove_file(&path).ok();
+    std::fs::remove_file(&other).ok();
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn dead_letter_test_async() {
+    use async_trait::async_trait;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use crate::asynchronous::contextaware::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Charged(u32),
+    }
+
+    #[derive(Debug, Clone)]
+    struct Charge(u32);
+
+    impl Record for Charge {
+        fn encode(&self) -> String {
+            self.0.to_string()
+        }
+
+        fn decode(line: &str) -> Option<Self> {
+            line.parse().ok().map(Charge)
+        }
+    }
+
+    #[async_trait]
+    impl TryCxAwareAsyncRequestHandler<Arc<Mutex<bool>>, Charge, MyEvent>
+        for CxAwareAsyncMediator<Arc<Mutex<bool>>, MyEvent>
+    {
+        type Error = &'static str;
+
+        async fn try_handle(
+            &self,
+            req: Charge,
+            dep: &Arc<Mutex<bool>>,
+            _scope: &mut Container,
+        ) -> Result<(), &'static str> {
+            let online = *dep.lock().unwrap();
+            if !online {
+                return Err("gateway offline");
+            }
+            self.publish(MyEvent::Charged(req.0)).await;
+            Ok(())
+        }
+    }
+
+    async_std::task::block_on(async {
+        let online = Arc::new(Mutex::new(false));
+        let clock = Arc::new(MockClock::new());
+        let mediator = CxAwareAsyncMediator::<Arc<Mutex<bool>>, MyEvent>::builder()
+            .add_dependency(online.clone())
+            .add_retry_policy::<Charge>(RetryPolicy::new(3).fixed(Duration::from_secs(1)))
+            .clock(clock.clone())
+            .dead_letter_queue(MemoryStorage::new())
+            .dead_letter_requests::<Charge>()
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            mediator.send_with_retry(Charge(10)).await,
+            Err("gateway offline")
+        );
+        assert_eq!(
+            mediator.send_with_retry(Charge(20)).await,
+            Err("gateway offline")
+        );
+        assert_eq!(clock.sleeps().len(), 4);
+
+        let letters = mediator.dead_letters().await.unwrap();
+        assert_eq!(letters.len(), 2);
+        assert_eq!(
+            letters[0].reason,
+            DeadLetterReason::RequestFailed(String::from("\"gateway offline\""))
+        );
+
+        *online.lock().unwrap() = true;
+        assert_eq!(
+            mediator.redispatch_requests::<Charge>().await.unwrap(),
+            vec![Ok(()), Ok(())]
+        );
+        assert!(mediator.dead_letters().await.unwrap().is_empty());
+
+        mediator.next().await.ok();
+        mediator.next().await.ok();
+        let letters = mediator.dead_letters().await.unwrap();
+        assert_eq!(
+            letters.iter().map(|l| l.letter.clone()).collect::<Vec<_>>(),
+            vec![
+                Letter::Event(MyEvent::Charged(10)),
+                Letter::Event(MyEvent::Charged(20))
+            ]
+        );
+
+        let mut stream = mediator.subscribe_stream().await;
+        assert_eq!(mediator.redispatch_events().await.unwrap(), 2);
+        mediator.next().await.ok();
+        assert_eq!(
+            futures::StreamExt::next(&mut stream).await,
+            Some(MyEvent::Charged(10))
+        );
+        assert_eq!(mediator.purge_dead_letters().await.unwrap(), 0);
+    })
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn schedule_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Tick(u32),
+        TimedOut,
+        Reminder,
+        Cancelled,
+    }
+
+    let received = Arc::new(Mutex::new(vec![]));
+    let cloned = received.clone();
+    let clock = Arc::new(MockClock::new());
+    let mediator = BasicMediator::<MyEvent>::builder()
+        .add_listener(move |ev| cloned.lock().unwrap().push(ev))
+        .clock(clock.clone())
+        .build();
+
+    let start = clock.now();
+    mediator.publish_after(MyEvent::TimedOut, Duration::from_secs(5));
+    mediator.publish_at(MyEvent::Reminder, start + Duration::from_secs(3));
+    let cancelled = mediator.publish_after(MyEvent::Cancelled, Duration::from_secs(4));
+    let mut ticks = 0;
+    let ticker = mediator.publish_every(
+        move || {
+            ticks += 1;
+            MyEvent::Tick(ticks)
+        },
+        Duration::from_secs(2),
+    );
+
+    assert!(mediator.cancel_scheduled(cancelled));
+    assert!(!mediator.cancel_scheduled(cancelled));
+    assert_eq!(mediator.next(), Err(std::sync::mpsc::TryRecvError::Empty));
+
+    clock.advance(Duration::from_secs(1));
+    assert!(mediator.next().is_err());
+
+    for _ in 0..4 {
+        mediator.next_scheduled().unwrap();
+    }
+    assert_eq!(
+        *received.lock().unwrap(),
+        vec![
+            MyEvent::Tick(1),
+            MyEvent::Reminder,
+            MyEvent::Tick(2),
+            MyEvent::TimedOut,
+        ]
+    );
+    assert_eq!(clock.now() - start, Duration::from_secs(5));
+    assert_eq!(
+        clock.sleeps(),
+        [1, 1, 1, 1].map(Duration::from_secs).to_vec()
+    );
+
+    // Falling behind by more than an interval skips the missed ticks.
+    clock.advance(Duration::from_secs(7));
+    mediator.next().unwrap();
+    assert!(mediator.next().is_err());
+    assert_eq!(received.lock().unwrap().last(), Some(&MyEvent::Tick(3)));
+
+    assert!(mediator.cancel_scheduled(ticker));
+    assert_eq!(
+        mediator.next_scheduled(),
+        Err(std::sync::mpsc::TryRecvError::Empty)
+    );
+
+    // Times beyond the range of `Instant` are never due, but can be cancelled.
+    let never = mediator.publish_after(MyEvent::TimedOut, Duration::MAX);
+    let never_ticking = mediator.publish_every(|| MyEvent::Tick(0), Duration::MAX);
+    clock.advance(Duration::from_secs(3600));
+    assert!(mediator.next().is_err());
+    assert!(mediator.cancel_scheduled(never));
+    assert!(mediator.cancel_scheduled(never_ticking));
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn schedule_from_factory_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::{
+        cell::RefCell,
+        rc::Rc,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Tick,
+        Follow,
+    }
+
+    thread_local! {
+        static MEDIATOR: RefCell<Option<Rc<BasicMediator<MyEvent>>>> = const { RefCell::new(None) };
+    }
+
+    let received = Arc::new(Mutex::new(vec![]));
+    let cloned = received.clone();
+    let clock = Arc::new(MockClock::new());
+    let mediator = Rc::new(
+        BasicMediator::<MyEvent>::builder()
+            .add_listener(move |ev| cloned.lock().unwrap().push(ev))
+            .clock(clock.clone())
+            .build(),
+    );
+    MEDIATOR.with(|cell| *cell.borrow_mut() = Some(mediator.clone()));
+
+    // The factory schedules another event while its own tick is being published.
+    let ticker = mediator.publish_every(
+        || {
+            MEDIATOR.with(|cell| {
+                if let Some(mediator) = &*cell.borrow() {
+                    mediator.publish_after(MyEvent::Follow, Duration::from_secs(1));
+                }
+            });
+            MyEvent::Tick
+        },
+        Duration::from_secs(2),
+    );
+
+    clock.advance(Duration::from_secs(2));
+    mediator.next().unwrap();
+    clock.advance(Duration::from_secs(1));
+    mediator.next().unwrap();
+    assert_eq!(
+        *received.lock().unwrap(),
+        vec![MyEvent::Tick, MyEvent::Follow]
+    );
+
+    assert!(mediator.cancel_scheduled(ticker));
+    MEDIATOR.with(|cell| cell.borrow_mut().take());
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn schedule_test_async() {
+    use async_trait::async_trait;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use crate::asynchronous::contextaware::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Heartbeat,
+        PaymentTimedOut(u32),
+    }
+
+    struct StartPayment(u32);
+
+    #[async_trait]
+    impl CxAwareAsyncRequestHandler<Duration, StartPayment, MyEvent>
+        for CxAwareAsyncMediator<Duration, MyEvent>
+    {
+        async fn handle(&self, req: StartPayment, timeout: &Duration) {
+            self.publish_after(MyEvent::PaymentTimedOut(req.0), *timeout)
+                .await;
+        }
+    }
+
+    async_std::task::block_on(async {
+        let received = Arc::new(Mutex::new(vec![]));
+        let cloned = received.clone();
+        let clock = Arc::new(MockClock::new());
+        let mediator = CxAwareAsyncMediator::<Duration, MyEvent>::builder()
+            .add_dependency(Duration::from_secs(30))
+            .add_listener(move |ev| cloned.lock().unwrap().push(ev))
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+
+        let heartbeat = mediator
+            .publish_every(|| MyEvent::Heartbeat, Duration::from_secs(20))
+            .await;
+        mediator.send(StartPayment(1)).await;
+
+        mediator.next_scheduled().await.unwrap();
+        mediator.next_scheduled().await.unwrap();
+        mediator.next_scheduled().await.unwrap();
+        assert!(mediator.cancel_scheduled(heartbeat).await);
+        assert!(mediator.next_scheduled().await.is_err());
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![
+                MyEvent::Heartbeat,
+                MyEvent::PaymentTimedOut(1),
+                MyEvent::Heartbeat,
+            ]
+        );
+        assert_eq!(
+            clock.sleeps(),
+            [20, 10, 10].map(Duration::from_secs).to_vec()
+        );
+    })
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn operators_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Changed(&'static str, u32),
+        Resized(u32),
+    }
+
+    let received = Arc::new(Mutex::new(vec![]));
+    let debounced = Arc::new(Mutex::new(vec![]));
+    let throttled = Arc::new(Mutex::new(vec![]));
+    let (c1, c2, c3) = (received.clone(), debounced.clone(), throttled.clone());
+    let clock = Arc::new(MockClock::new());
+    let mediator = BasicMediator::<MyEvent>::builder()
+        .add_listener(move |ev| c1.lock().unwrap().push(ev))
+        .add_debounced_listener(
+            Duration::from_secs(1),
+            |_| (),
+            move |ev| c2.lock().unwrap().push(ev),
+        )
+        .add_throttled_listener(
+            2,
+            Duration::from_secs(10),
+            |_| (),
+            move |ev| c3.lock().unwrap().push(ev),
+        )
+        .coalesce(
+            |ev| match ev {
+                MyEvent::Changed(path, _) => Some(*path),
+                MyEvent::Resized(_) => None,
+            },
+            |queued, new| match (queued, new) {
+                (MyEvent::Changed(path, a), MyEvent::Changed(_, b)) => {
+                    MyEvent::Changed(path, a + b)
+                }
+                (_, new) => new,
+            },
+        )
+        .clock(clock.clone())
+        .build();
+
+    mediator.publish(MyEvent::Changed("a", 1));
+    mediator.publish(MyEvent::Changed("b", 1));
+    mediator.publish(MyEvent::Changed("a", 2));
+    mediator.publish(MyEvent::Resized(1));
+    for _ in 0..3 {
+        mediator.next().unwrap();
+    }
+    assert!(mediator.next().is_err());
+
+    assert_eq!(
+        *received.lock().unwrap(),
+        vec![
+            MyEvent::Changed("a", 3),
+            MyEvent::Changed("b", 1),
+            MyEvent::Resized(1),
+        ]
+    );
+    assert_eq!(
+        *throttled.lock().unwrap(),
+        vec![MyEvent::Changed("a", 3), MyEvent::Changed("b", 1)]
+    );
+    assert!(debounced.lock().unwrap().is_empty());
+
+    mediator.next_scheduled().unwrap();
+    assert_eq!(*debounced.lock().unwrap(), vec![MyEvent::Resized(1)]);
+    assert_eq!(clock.sleeps(), vec![Duration::from_secs(1)]);
+
+    // Only the last event of a burst is emitted.
+    mediator.publish(MyEvent::Resized(2));
+    mediator.next().unwrap();
+    clock.advance(Duration::from_millis(500));
+    mediator.publish(MyEvent::Resized(3));
+    mediator.next().unwrap();
+    clock.advance(Duration::from_millis(500));
+    assert!(mediator.next().is_err());
+    mediator.next_scheduled().unwrap();
+    assert_eq!(
+        *debounced.lock().unwrap(),
+        vec![MyEvent::Resized(1), MyEvent::Resized(3)]
+    );
+
+    // The throttle lets events through again after the interval.
+    clock.advance(Duration::from_secs(10));
+    mediator.publish(MyEvent::Resized(4));
+    mediator.next().unwrap();
+    assert_eq!(throttled.lock().unwrap().last(), Some(&MyEvent::Resized(4)));
+    assert_eq!(throttled.lock().unwrap().len(), 3);
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn operators_per_key_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        A(u32),
+        B(u32),
+        C(u32),
+    }
+
+    let debounced = Arc::new(Mutex::new(vec![]));
+    let throttled = Arc::new(Mutex::new(vec![]));
+    let (c1, c2) = (debounced.clone(), throttled.clone());
+    let clock = Arc::new(MockClock::new());
+    let mediator = BasicMediator::<MyEvent>::builder()
+        .add_debounced_listener(Duration::from_secs(1), std::mem::discriminant, move |ev| {
+            c1.lock().unwrap().push(ev)
+        })
+        .add_throttled_listener(
+            1,
+            Duration::from_secs(10),
+            std::mem::discriminant,
+            move |ev| c2.lock().unwrap().push(ev),
+        )
+        .clock(clock.clone())
+        .build();
+
+    // An interleaved burst keeps the last event of every key.
+    for ev in [MyEvent::A(1), MyEvent::B(1), MyEvent::A(2), MyEvent::B(2)] {
+        mediator.publish(ev);
+    }
+    while mediator.next().is_ok() {}
+    clock.advance(Duration::from_millis(500));
+    mediator.publish(MyEvent::C(1));
+    mediator.next().unwrap();
+    assert_eq!(
+        *throttled.lock().unwrap(),
+        vec![MyEvent::A(1), MyEvent::B(1), MyEvent::C(1)]
+    );
+
+    // Keys become due independently, the earliest first.
+    mediator.next_scheduled().unwrap();
+    assert_eq!(
+        *debounced.lock().unwrap(),
+        vec![MyEvent::A(2), MyEvent::B(2)]
+    );
+    mediator.next_scheduled().unwrap();
+    assert_eq!(
+        *debounced.lock().unwrap(),
+        vec![MyEvent::A(2), MyEvent::B(2), MyEvent::C(1)]
+    );
+    assert_eq!(
+        clock.sleeps(),
+        vec![Duration::from_millis(500), Duration::from_millis(500)]
+    );
+
+    // A new burst of one key does not delay another.
+    mediator.publish(MyEvent::A(3));
+    mediator.next().unwrap();
+    clock.advance(Duration::from_millis(800));
+    mediator.publish(MyEvent::B(3));
+    mediator.next().unwrap();
+    clock.advance(Duration::from_millis(200));
+    mediator.next().unwrap();
+    assert_eq!(debounced.lock().unwrap().last(), Some(&MyEvent::A(3)));
+    assert!(mediator.next().is_err());
+    mediator.flush();
+    assert_eq!(debounced.lock().unwrap().last(), Some(&MyEvent::B(3)));
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn operators_test_async() {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use crate::asynchronous::basic::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Progress(u32, u32),
+        Done(u32),
+    }
+
+    async_std::task::block_on(async {
+        let received = Arc::new(Mutex::new(vec![]));
+        let debounced = Arc::new(Mutex::new(vec![]));
+        let throttled = Arc::new(Mutex::new(vec![]));
+        let (c1, c2, c3) = (received.clone(), debounced.clone(), throttled.clone());
+        let clock = Arc::new(MockClock::new());
+        let mediator = BasicAsyncMediator::<MyEvent>::builder()
+            .add_listener(move |ev| c1.lock().unwrap().push(ev))
+            .add_debounced_listener(
+                Duration::from_millis(300),
+                |_| (),
+                move |ev| c2.lock().unwrap().push(ev),
+            )
+            .add_throttled_listener(
+                1,
+                Duration::from_secs(1),
+                |_| (),
+                move |ev| c3.lock().unwrap().push(ev),
+            )
+            .coalesce(
+                |ev| match ev {
+                    MyEvent::Progress(job, _) => Some(*job),
+                    MyEvent::Done(_) => None,
+                },
+                |_queued, new| new,
+            )
+            .clock(clock.clone())
+            .build();
+
+        for percent in [10, 50, 90] {
+            mediator.publish(MyEvent::Progress(1, percent)).await;
+        }
+        mediator.publish(MyEvent::Done(1)).await;
+        mediator.next().await.unwrap();
+        mediator.next().await.unwrap();
+        assert!(mediator.next().await.is_err());
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![MyEvent::Progress(1, 90), MyEvent::Done(1)]
+        );
+        assert_eq!(*throttled.lock().unwrap(), vec![MyEvent::Progress(1, 90)]);
+
+        mediator.next_scheduled().await.unwrap();
+        assert_eq!(*debounced.lock().unwrap(), vec![MyEvent::Done(1)]);
+        assert_eq!(clock.sleeps(), vec![Duration::from_millis(300)]);
+        assert!(mediator.next_scheduled().await.is_err());
+    })
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn batch_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Indexed(u32),
+    }
+
+    let batches = Arc::new(Mutex::new(vec![]));
+    let cloned = batches.clone();
+    let clock = Arc::new(MockClock::new());
+    let mediator = BasicMediator::<MyEvent>::builder()
+        .add_batch_listener(3, Duration::from_secs(5), move |batch| {
+            cloned.lock().unwrap().push(batch)
+        })
+        .clock(clock.clone())
+        .build();
+
+    // Flushed on size.
+    for id in 1..=4 {
+        mediator.publish(MyEvent::Indexed(id));
+    }
+    while mediator.next().is_ok() {}
+    assert_eq!(
+        *batches.lock().unwrap(),
+        vec![vec![
+            MyEvent::Indexed(1),
+            MyEvent::Indexed(2),
+            MyEvent::Indexed(3)
+        ]]
+    );
+
+    // Flushed on time.
+    clock.advance(Duration::from_secs(2));
+    mediator.next_scheduled().unwrap();
+    assert_eq!(clock.sleeps(), vec![Duration::from_secs(3)]);
+    assert_eq!(
+        batches.lock().unwrap().last(),
+        Some(&vec![MyEvent::Indexed(4)])
+    );
+
+    // Flushed explicitly.
+    mediator.publish(MyEvent::Indexed(5));
+    mediator.publish(MyEvent::Indexed(6));
+    while mediator.next().is_ok() {}
+    assert_eq!(batches.lock().unwrap().len(), 2);
+    mediator.flush();
+    assert_eq!(
+        batches.lock().unwrap().last(),
+        Some(&vec![MyEvent::Indexed(5), MyEvent::Indexed(6)])
+    );
+
+    // Nothing is pending anymore.
+    mediator.flush();
+    assert_eq!(batches.lock().unwrap().len(), 3);
+    assert_eq!(
+        mediator.next_scheduled(),
+        Err(std::sync::mpsc::TryRecvError::Empty)
+    );
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn batch_test_async() {
+    use async_trait::async_trait;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use crate::asynchronous::contextaware::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Stored(u32),
+    }
+
+    #[derive(Debug)]
+    struct Db;
+
+    struct Store(u32);
+
+    #[async_trait]
+    impl CxAwareAsyncRequestHandler<Db, Store, MyEvent> for CxAwareAsyncMediator<Db, MyEvent> {
+        async fn handle(&self, req: Store, _db: &Db) {
+            self.publish(MyEvent::Stored(req.0)).await;
+        }
+    }
+
+    async_std::task::block_on(async {
+        let batches = Arc::new(Mutex::new(vec![]));
+        let cloned = batches.clone();
+        let clock = Arc::new(MockClock::new());
+        let mediator = CxAwareAsyncMediator::<Db, MyEvent>::builder()
+            .add_dependency(Db)
+            .add_batch_listener(2, Duration::from_millis(100), move |batch| {
+                cloned.lock().unwrap().push(batch)
+            })
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+
+        for id in 1..=3 {
+            mediator.send(Store(id)).await;
+        }
+        while mediator.next().await.is_ok() {}
+        assert_eq!(
+            *batches.lock().unwrap(),
+            vec![vec![MyEvent::Stored(1), MyEvent::Stored(2)]]
+        );
+
+        mediator.flush().await;
+        assert_eq!(
+            batches.lock().unwrap().last(),
+            Some(&vec![MyEvent::Stored(3)])
+        );
+
+        mediator.send(Store(4)).await;
+        mediator.next().await.unwrap();
+        assert_eq!(batches.lock().unwrap().len(), 2);
+        mediator.next_scheduled().await.unwrap();
+        assert_eq!(
+            batches.lock().unwrap().last(),
+            Some(&vec![MyEvent::Stored(4)])
+        );
+        assert_eq!(clock.sleeps(), vec![Duration::from_millis(100)]);
+    })
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn priority_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Routine(u32),
+        Alert(u32),
+        Shutdown,
+    }
+
+    let received = Arc::new(Mutex::new(vec![]));
+    let cloned = received.clone();
+    let mediator = BasicMediator::<MyEvent>::builder()
+        .add_listener(move |ev| cloned.lock().unwrap().push(ev))
+        .prioritize(|ev| match ev {
+            MyEvent::Routine(_) => Priority::LOW,
+            MyEvent::Alert(_) => Priority::HIGH,
+            MyEvent::Shutdown => Priority::NORMAL,
+        })
+        .build();
+
+    mediator.publish(MyEvent::Routine(1));
+    mediator.publish(MyEvent::Alert(1));
+    mediator.publish(MyEvent::Routine(2));
+    mediator.publish(MyEvent::Alert(2));
+    mediator.publish_with_priority(MyEvent::Shutdown, Priority(10));
+    mediator.next().unwrap();
+    mediator.next().unwrap();
+
+    // Events published in between are ordered as well.
+    mediator.publish(MyEvent::Alert(3));
+    while mediator.next().is_ok() {}
+
+    assert_eq!(
+        *received.lock().unwrap(),
+        vec![
+            MyEvent::Shutdown,
+            MyEvent::Alert(1),
+            MyEvent::Alert(2),
+            MyEvent::Alert(3),
+            MyEvent::Routine(1),
+            MyEvent::Routine(2),
+        ]
+    );
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn priority_test_async() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::asynchronous::basic::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Progress(u32, u32),
+        Cancelled(u32),
+    }
+
+    async_std::task::block_on(async {
+        let received = Arc::new(Mutex::new(vec![]));
+        let cloned = received.clone();
+        let mediator = BasicAsyncMediator::<MyEvent>::builder()
+            .add_listener(move |ev| cloned.lock().unwrap().push(ev))
+            .coalesce(
+                |ev| match ev {
+                    MyEvent::Progress(job, _) => Some(*job),
+                    MyEvent::Cancelled(_) => None,
+                },
+                |_queued, new| new,
+            )
+            .build();
+
+        mediator.publish(MyEvent::Progress(1, 10)).await;
+        mediator.publish(MyEvent::Progress(2, 10)).await;
+        mediator
+            .publish_with_priority(MyEvent::Cancelled(2), Priority::HIGH)
+            .await;
+        mediator.publish(MyEvent::Progress(1, 20)).await;
+        // Only merged with queued events of the same priority.
+        mediator
+            .publish_with_priority(MyEvent::Progress(2, 30), Priority::HIGH)
+            .await;
+        while mediator.next().await.is_ok() {}
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![
+                MyEvent::Cancelled(2),
+                MyEvent::Progress(2, 30),
+                MyEvent::Progress(1, 20),
+                MyEvent::Progress(2, 10),
+            ]
+        );
+    })
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn parallel_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::{
+        collections::{HashMap, HashSet},
+        sync::{Arc, Mutex},
+        thread,
+    };
+
+    #[derive(Debug, Clone)]
+    struct Moved {
+        entity: u32,
+        seq: u32,
+    }
+
+    const ENTITIES: u32 = 64;
+    const MOVES: u32 = 200;
+
+    let seen = Arc::new(Mutex::new(HashMap::<u32, Vec<u32>>::new()));
+    let threads = Arc::new(Mutex::new(HashSet::new()));
+    let (c1, c2) = (seen.clone(), threads.clone());
+    let mediator = BasicMediator::<Moved>::builder()
+        .add_parallel_listener(
+            8,
+            |ev: &Moved| ev.entity,
+            move |ev: Moved| {
+                c2.lock().unwrap().insert(thread::current().id());
+                // Give other workers the chance to interleave.
+                thread::yield_now();
+                c1.lock()
+                    .unwrap()
+                    .entry(ev.entity)
+                    .or_default()
+                    .push(ev.seq);
+            },
+        )
+        .build();
+
+    for seq in 0..MOVES {
+        for entity in 0..ENTITIES {
+            mediator.publish(Moved { entity, seq });
+        }
+    }
+    while mediator.next().is_ok() {}
+    mediator.flush();
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), ENTITIES as usize);
+    for seqs in seen.values() {
+        assert_eq!(*seqs, (0..MOVES).collect::<Vec<_>>());
+    }
+    assert!(threads.lock().unwrap().len() > 1);
+    assert!(!threads.lock().unwrap().contains(&thread::current().id()));
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn parallel_test_async() {
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    use crate::asynchronous::basic::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Deposited { account: u32, amount: u64 },
+        Withdrawn { account: u32, amount: u64 },
+    }
+
+    impl MyEvent {
+        fn account(&self) -> u32 {
+            match self {
+                MyEvent::Deposited { account, .. } | MyEvent::Withdrawn { account, .. } => *account,
+            }
+        }
+    }
+
+    const ACCOUNTS: u32 = 32;
+    const ROUNDS: u64 = 100;
+
+    async_std::task::block_on(async {
+        // Withdrawals never overdraw an account as long as they are applied in order.
+        let balances = Arc::new(Mutex::new(HashMap::<u32, u64>::new()));
+        let overdrawn = Arc::new(AtomicUsize::new(0));
+        let (c1, c2) = (balances.clone(), overdrawn.clone());
+        let mediator = BasicAsyncMediator::<MyEvent>::builder()
+            .add_parallel_listener(4, MyEvent::account, move |ev| {
+                let mut balances = c1.lock().unwrap();
+                let balance = balances.entry(ev.account()).or_default();
+                match ev {
+                    MyEvent::Deposited { amount, .. } => *balance += amount,
+                    MyEvent::Withdrawn { amount, .. } => match balance.checked_sub(amount) {
+                        Some(rest) => *balance = rest,
+                        None => {
+                            c2.fetch_add(1, Ordering::SeqCst);
+                        }
+                    },
+                }
+            })
+            .build();
+
+        for round in 1..=ROUNDS {
+            for account in 0..ACCOUNTS {
+                mediator
+                    .publish(MyEvent::Deposited {
+                        account,
+                        amount: round,
+                    })
+                    .await;
+                mediator
+                    .publish(MyEvent::Withdrawn {
+                        account,
+                        amount: round,
+                    })
+                    .await;
+            }
+        }
+        while mediator.next().await.is_ok() {}
+        mediator.flush().await;
+
+        assert_eq!(overdrawn.load(Ordering::SeqCst), 0);
+        let balances = balances.lock().unwrap();
+        assert_eq!(balances.len(), ACCOUNTS as usize);
+        assert!(balances.values().all(|balance| *balance == 0));
+    })
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn parallel_flush_test_async() {
+    use std::{
+        sync::{mpsc::channel, Arc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    use crate::asynchronous::basic::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Blocked,
+        Released,
+    }
+
+    let (done_tx, done_rx) = channel();
+    thread::spawn(move || {
+        async_std::task::block_on(async {
+            // The listener waits for the release, which is published while flushing.
+            let (release_tx, release_rx) = channel::<()>();
+            let release_rx = Arc::new(Mutex::new(release_rx));
+            let received = Arc::new(Mutex::new(vec![]));
+            let cloned = received.clone();
+            let mediator = BasicAsyncMediator::<MyEvent>::builder()
+                .add_parallel_listener(
+                    2,
+                    |_| 0,
+                    move |ev| {
+                        release_rx.lock().unwrap().recv().ok();
+                        cloned.lock().unwrap().push(ev);
+                    },
+                )
+                .build();
+
+            mediator.publish(MyEvent::Blocked).await;
+            mediator.next().await.unwrap();
+            futures::join!(mediator.flush(), async {
+                mediator.publish(MyEvent::Released).await;
+                release_tx.send(()).unwrap();
+            });
+            assert_eq!(*received.lock().unwrap(), vec![MyEvent::Blocked]);
+        });
+        done_tx.send(()).unwrap();
+    });
+    done_rx
+        .recv_timeout(Duration::from_secs(5))
+        .expect("flush held the mediator while waiting for the workers");
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn topic_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Order(u32);
+
+    let received = Arc::new(Mutex::new(vec![]));
+    let subscribe = |name: &'static str| {
+        let received = received.clone();
+        move |ev: Order| received.lock().unwrap().push((name, ev.0))
+    };
+    let mediator = BasicMediator::<Order>::builder()
+        .add_topic_listener("orders.*.created", subscribe("created"))
+        .add_topic_listener("orders.#", subscribe("orders"))
+        .add_topic_listener("orders.#.shipped", subscribe("shipped"))
+        .add_topic_listener("orders.eu.created", subscribe("eu"))
+        .add_topic_listener("#", subscribe("all"))
+        .add_topic_listener("*.*", subscribe("pair"))
+        .dead_letter_queue(MemoryStorage::new())
+        .build();
+
+    mediator.publish_to("orders.eu.created", Order(1));
+    mediator.publish_to("orders.us.created", Order(2));
+    mediator.publish_to("orders", Order(3));
+    mediator.publish_to("orders.eu.express.shipped", Order(4));
+    mediator.publish_to("orders.shipped", Order(5));
+    mediator.publish_to("orders.eu.created.late", Order(6));
+    mediator.publish_to("invoices.sent", Order(7));
+    while mediator.next().is_ok() {}
+
+    assert_eq!(
+        *received.lock().unwrap(),
+        vec![
+            ("created", 1),
+            ("orders", 1),
+            ("eu", 1),
+            ("all", 1),
+            ("created", 2),
+            ("orders", 2),
+            ("all", 2),
+            ("orders", 3),
+            ("all", 3),
+            ("orders", 4),
+            ("shipped", 4),
+            ("all", 4),
+            ("orders", 5),
+            ("shipped", 5),
+            ("all", 5),
+            ("pair", 5),
+            ("orders", 6),
+            ("all", 6),
+            ("all", 7),
+            ("pair", 7),
+        ]
+    );
+
+    // Events without a matching subscription are dead letters.
+    let mediator = BasicMediator::<Order>::builder()
+        .add_topic_listener("orders.#", |_| ())
+        .dead_letter_queue(MemoryStorage::new())
+        .build();
+    mediator.publish_to("orders.eu", Order(1));
+    mediator.publish_to("invoices.eu", Order(2));
+    mediator.publish(Order(3));
+    while mediator.next().is_ok() {}
+    let dead: Vec<_> = mediator
+        .dead_letters()
+        .unwrap()
+        .into_iter()
+        .map(|letter| letter.letter)
+        .collect();
+    assert_eq!(dead, vec![Letter::Event(Order(2)), Letter::Event(Order(3))]);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn topic_test_async() {
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    use crate::asynchronous::contextaware::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Created(u32),
+        Cancelled(u32),
+    }
+
+    #[derive(Debug)]
+    struct Region(&'static str);
+
+    struct CreateOrder(u32);
+    struct CancelOrder(u32);
+
+    #[async_trait]
+    impl CxAwareAsyncRequestHandler<Region, CreateOrder, MyEvent>
+        for CxAwareAsyncMediator<Region, MyEvent>
+    {
+        async fn handle(&self, req: CreateOrder, region: &Region) {
+            let topic = format!("orders.{}.created", region.0);
+            self.publish_to(&topic, MyEvent::Created(req.0)).await;
+        }
+    }
+
+    #[async_trait]
+    impl CxAwareAsyncRequestHandler<Region, CancelOrder, MyEvent>
+        for CxAwareAsyncMediator<Region, MyEvent>
+    {
+        async fn handle(&self, req: CancelOrder, region: &Region) {
+            let topic = format!("orders.{}.cancelled", region.0);
+            self.publish_to(&topic, MyEvent::Cancelled(req.0)).await;
+        }
+    }
+
+    async_std::task::block_on(async {
+        let all = Arc::new(Mutex::new(vec![]));
+        let created = Arc::new(Mutex::new(vec![]));
+        let eu = Arc::new(Mutex::new(vec![]));
+        let (c1, c2, c3) = (all.clone(), created.clone(), eu.clone());
+        let mediator = CxAwareAsyncMediator::<Region, MyEvent>::builder()
+            .add_dependency(Region("eu"))
+            .add_listener(move |ev| c1.lock().unwrap().push(ev))
+            .add_topic_listener("orders.*.created", move |ev| c2.lock().unwrap().push(ev))
+            .add_topic_listener("orders.eu.#", move |ev| c3.lock().unwrap().push(ev))
+            .build()
+            .unwrap();
+
+        mediator.send(CreateOrder(1)).await;
+        mediator.send(CancelOrder(1)).await;
+        mediator
+            .publish_to("orders.us.created", MyEvent::Created(2))
+            .await;
+        mediator.publish(MyEvent::Cancelled(3)).await;
+        while mediator.next().await.is_ok() {}
+
+        assert_eq!(all.lock().unwrap().len(), 4);
+        assert_eq!(
+            *created.lock().unwrap(),
+            vec![MyEvent::Created(1), MyEvent::Created(2)]
+        );
+        assert_eq!(
+            *eu.lock().unwrap(),
+            vec![MyEvent::Created(1), MyEvent::Cancelled(1)]
+        );
+    })
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn hierarchy_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Saved(&'static str),
+        Shutdown,
+    }
+
+    let received = Arc::new(Mutex::new(vec![]));
+    let listen = |name: &'static str| {
+        let received = received.clone();
+        move |ev: MyEvent| received.lock().unwrap().push((name, ev))
+    };
+    let drain = |mediator: &BasicMediator<MyEvent>| while mediator.next().is_ok() {};
+
+    let app = BasicMediator::<MyEvent>::builder()
+        .add_listener(listen("app"))
+        .build();
+    let editor = BasicMediator::<MyEvent>::builder()
+        .parent(
+            &app,
+            Propagation::both().down_if(|ev| matches!(ev, MyEvent::Shutdown)),
+        )
+        .add_listener(listen("editor"))
+        .build();
+    let tab = BasicMediator::<MyEvent>::builder()
+        .parent(&editor, Propagation::up())
+        .add_listener(listen("tab"))
+        .build();
+    let plugin = BasicMediator::<MyEvent>::builder()
+        .parent(
+            &app,
+            Propagation::none().up_if(|ev| matches!(ev, MyEvent::Saved("plugin"))),
+        )
+        .add_listener(listen("plugin"))
+        .build();
+    assert_eq!(app.child_count(), 2);
+    assert_eq!(editor.child_count(), 1);
+
+    // Events bubble up to the grandparent, but never back down.
+    tab.publish(MyEvent::Saved("tab"));
+    drain(&tab);
+    drain(&editor);
+    drain(&app);
+    assert!(editor.next().is_err());
+    assert!(tab.next().is_err());
+    assert!(plugin.next().is_err());
+
+    // Filtered propagation in both directions.
+    plugin.publish(MyEvent::Saved("plugin"));
+    plugin.publish(MyEvent::Saved("settings"));
+    drain(&plugin);
+    drain(&app);
+    app.publish(MyEvent::Saved("app"));
+    app.publish(MyEvent::Shutdown);
+    drain(&app);
+    drain(&editor);
+    drain(&tab);
+    drain(&plugin);
+
+    assert_eq!(
+        *received.lock().unwrap(),
+        vec![
+            ("tab", MyEvent::Saved("tab")),
+            ("editor", MyEvent::Saved("tab")),
+            ("app", MyEvent::Saved("tab")),
+            ("plugin", MyEvent::Saved("plugin")),
+            ("plugin", MyEvent::Saved("settings")),
+            ("app", MyEvent::Saved("plugin")),
+            ("app", MyEvent::Saved("app")),
+            ("app", MyEvent::Shutdown),
+            ("editor", MyEvent::Shutdown),
+        ]
+    );
+
+    // Dropped children are unlinked.
+    drop(plugin);
+    assert_eq!(app.child_count(), 1);
+    drop(editor);
+    assert_eq!(app.child_count(), 0);
+    tab.publish(MyEvent::Saved("orphan"));
+    drain(&tab);
+    assert!(app.next().is_err());
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn hierarchy_test_async() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::asynchronous::basic::{BasicAsyncMediator, BuilderFlow, BuilderInternal};
+    use crate::asynchronous::contextaware::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Joined(u32),
+        Closed,
+    }
+
+    #[derive(Debug)]
+    struct Room;
+
+    async_std::task::block_on(async {
+        let lobby_received = Arc::new(Mutex::new(vec![]));
+        let room_received = Arc::new(Mutex::new(vec![]));
+        let (c1, c2) = (lobby_received.clone(), room_received.clone());
+
+        let lobby = BasicAsyncMediator::<MyEvent>::builder()
+            .add_listener(move |ev| c1.lock().unwrap().push(ev))
+            .build();
+        let room = CxAwareAsyncMediator::<Room, MyEvent>::builder()
+            .add_dependency(Room)
+            .parent(&lobby, Propagation::both())
+            .add_listener(move |ev| c2.lock().unwrap().push(ev))
+            .build()
+            .unwrap();
+        assert_eq!(lobby.child_count(), 1);
+
+        room.publish(MyEvent::Joined(7)).await;
+        while room.next().await.is_ok() {}
+        while lobby.next().await.is_ok() {}
+        // The event bubbled up is not sent back to the room.
+        assert!(room.next().await.is_err());
+
+        lobby.publish(MyEvent::Closed).await;
+        while lobby.next().await.is_ok() {}
+        while room.next().await.is_ok() {}
+
+        assert_eq!(
+            *lobby_received.lock().unwrap(),
+            vec![MyEvent::Joined(7), MyEvent::Closed]
+        );
+        assert_eq!(
+            *room_received.lock().unwrap(),
+            vec![MyEvent::Joined(7), MyEvent::Closed]
+        );
+
+        drop(room);
+        assert_eq!(lobby.child_count(), 0);
+    })
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn bridge_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Job {
+        Resize(u32),
+        Done(u32),
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Audit {
+        Finished(u32),
+        Requeued(u32),
+    }
+
+    let jobs = Arc::new(Mutex::new(vec![]));
+    let audits = Arc::new(Mutex::new(vec![]));
+    let (c1, c2) = (jobs.clone(), audits.clone());
+
+    let worker =
+        BasicMediator::<Job>::builder().add_listener(move |ev| c1.lock().unwrap().push(ev));
+    let worker_link = worker.link();
+    let audit = BasicMediator::<Audit>::builder()
+        .add_listener(move |ev| c2.lock().unwrap().push(ev))
+        // Maps finished jobs back, which must not bounce them back and forth.
+        .add_bridge(&worker_link, |ev| match ev {
+            Audit::Finished(id) => Some(Job::Done(id)),
+            Audit::Requeued(id) => Some(Job::Resize(id)),
+        })
+        .build();
+    let worker = worker
+        .add_bridge(&audit, |ev| match ev {
+            Job::Done(id) => Some(Audit::Finished(id)),
+            Job::Resize(_) => None,
+        })
+        .build();
+
+    worker.publish(Job::Done(1));
+    worker.publish(Job::Resize(2));
+    while worker.next().is_ok() {}
+    while audit.next().is_ok() {}
+    assert!(worker.next().is_err());
+
+    audit.publish(Audit::Requeued(3));
+    while audit.next().is_ok() {}
+    while worker.next().is_ok() {}
+    assert!(audit.next().is_err());
+
+    assert_eq!(
+        *jobs.lock().unwrap(),
+        vec![Job::Done(1), Job::Resize(2), Job::Resize(3)]
+    );
+    assert_eq!(
+        *audits.lock().unwrap(),
+        vec![Audit::Finished(1), Audit::Requeued(3)]
+    );
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn bridge_recv_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::{
+        sync::{mpsc::channel, Arc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Job {
+        Resize(u32),
+        Done(u32),
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Audit {
+        Requested(u32),
+        Finished(u32),
+    }
+
+    let (done_tx, done_rx) = channel();
+    thread::spawn(move || {
+        let jobs = Arc::new(Mutex::new(vec![]));
+        let resized = Arc::new(Mutex::new(vec![]));
+        let audits = Arc::new(Mutex::new(vec![]));
+        let (c1, c2, c3) = (jobs.clone(), resized.clone(), audits.clone());
+
+        let worker = BasicMediator::<Job>::builder().add_listener(move |ev| {
+            if let Job::Resize(id) = ev {
+                c2.lock().unwrap().push(id)
+            }
+            c1.lock().unwrap().push(ev)
+        });
+        let worker_link = worker.link();
+        let audit = BasicMediator::<Audit>::builder()
+            .add_listener(move |ev| c3.lock().unwrap().push(ev))
+            .add_bridge(&worker_link, |ev| match ev {
+                Audit::Requested(id) => Some(Job::Resize(id)),
+                Audit::Finished(_) => None,
+            })
+            .build();
+        let audit_link = audit.link();
+
+        // The worker only ever blocks on `recv()` and is never polled.
+        let worker = thread::spawn(move || {
+            let worker = worker
+                .add_bridge(&audit_link, |ev| match ev {
+                    Job::Done(id) => Some(Audit::Finished(id)),
+                    Job::Resize(_) => None,
+                })
+                .build();
+            // Three jobs are resized and three are done.
+            for _ in 0..6 {
+                worker.recv().unwrap();
+                let jobs: Vec<u32> = resized.lock().unwrap().drain(..).collect();
+                for id in jobs {
+                    worker.publish(Job::Done(id));
+                }
+            }
+        });
+
+        for id in 0..3 {
+            audit.publish(Audit::Requested(id));
+        }
+        while audits.lock().unwrap().len() < 6 {
+            audit.recv().unwrap();
+        }
+        worker.join().unwrap();
+
+        let mut jobs = jobs.lock().unwrap().clone();
+        jobs.sort_by_key(|ev| format!("{:?}", ev));
+        assert_eq!(
+            jobs,
+            vec![
+                Job::Done(0),
+                Job::Done(1),
+                Job::Done(2),
+                Job::Resize(0),
+                Job::Resize(1),
+                Job::Resize(2),
+            ]
+        );
+        assert_eq!(
+            *audits.lock().unwrap(),
+            vec![
+                Audit::Requested(0),
+                Audit::Requested(1),
+                Audit::Requested(2),
+                Audit::Finished(0),
+                Audit::Finished(1),
+                Audit::Finished(2),
+            ]
+        );
+        done_tx.send(()).unwrap();
+    });
+    done_rx
+        .recv_timeout(Duration::from_secs(5))
+        .expect("bridged events were not received without polling");
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn bridge_cycle_test_sync() {
+    use crate::hierarchy::Propagation;
+    use crate::synchronous::basic::*;
+
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    enum Ping {
+        Parent(u32),
+        Child(u32),
+    }
+
+    let received = Arc::new(Mutex::new(vec![]));
+    let (c1, c2, c3) = (received.clone(), received.clone(), received.clone());
+
+    let parent = BasicMediator::<Ping>::builder()
+        .add_listener(move |ev| c1.lock().unwrap().push(("parent", ev)));
+    let child = BasicMediator::<Ping>::builder()
+        .add_listener(move |ev| c2.lock().unwrap().push(("child", ev)))
+        .parent(&parent.link(), Propagation::up());
+    let child_link = child.link();
+    // The mirror closes a second cycle over a projection and a bridge.
+    let mirror = BasicMediator::<Ping>::builder()
+        .project_from(&child_link, Some)
+        .add_listener(move |ev| c3.lock().unwrap().push(("mirror", ev)))
+        .add_bridge(&child_link, Some)
+        .build();
+    let child = child.build();
+    let parent = parent.add_bridge(&child, Some).build();
+
+    parent.publish(Ping::Parent(1));
+    child.publish(Ping::Child(2));
+    for _ in 0..10 {
+        parent.next().ok();
+        child.next().ok();
+        mirror.next().ok();
+    }
+    assert!(parent.next().is_err());
+    assert!(child.next().is_err());
+    assert!(mirror.next().is_err());
+
+    let mut received = received.lock().unwrap().clone();
+    received.sort();
+    assert_eq!(
+        received,
+        vec![
+            ("child", Ping::Parent(1)),
+            ("child", Ping::Child(2)),
+            ("mirror", Ping::Parent(1)),
+            ("mirror", Ping::Child(2)),
+            ("parent", Ping::Parent(1)),
+            ("parent", Ping::Child(2)),
+        ]
+    );
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn bridge_test_async() {
+    use async_trait::async_trait;
+    use std::{
+        sync::{Arc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    use crate::asynchronous::basic::*;
+    use crate::synchronous::basic::{
+        BasicMediator, SyncMediatorInternal, SyncMediatorInternalNext,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Job {
+        Resize(u32),
+        Done(u32),
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Message {
+        Uploaded(u32),
+        Ready(u32),
+    }
+
+    struct Upload(u32);
+
+    #[async_trait]
+    impl AsyncRequestHandler<Upload, Message> for BasicAsyncMediator<Message> {
+        async fn handle(&self, req: Upload) {
+            self.publish(Message::Uploaded(req.0)).await;
+        }
+    }
+
+    async_std::task::block_on(async {
+        let ready = Arc::new(Mutex::new(vec![]));
+        let resized = Arc::new(Mutex::new(vec![]));
+        let (c1, c2) = (ready.clone(), resized.clone());
+
+        let worker = BasicMediator::<Job>::builder().add_listener(move |ev| {
+            if let Job::Resize(id) = ev {
+                c2.lock().unwrap().push(id)
+            }
+        });
+        let worker_link = worker.link();
+        let server = BasicAsyncMediator::<Message>::builder()
+            .add_listener(move |ev| {
+                if let Message::Ready(id) = ev {
+                    c1.lock().unwrap().push(id)
+                }
+            })
+            .add_bridge(&worker_link, |ev| match ev {
+                Message::Uploaded(id) => Some(Job::Resize(id)),
+                Message::Ready(_) => None,
+            })
+            .build();
+        let worker = worker
+            .add_bridge(&server, |ev| match ev {
+                Job::Done(id) => Some(Message::Ready(id)),
+                Job::Resize(_) => None,
+            })
+            .build();
+
+        // The blocking worker runs in its own thread and only ever blocks on `recv()`.
+        let worker = thread::spawn(move || {
+            // Three jobs are resized and three are done.
+            for _ in 0..6 {
+                worker.recv().unwrap();
+                let jobs: Vec<u32> = resized.lock().unwrap().drain(..).collect();
+                for id in jobs {
+                    worker.publish(Job::Done(id));
+                }
+            }
+        });
+
+        for id in 0..3 {
+            server.send(Upload(id)).await;
+        }
+        // The server awaits the events forwarded by the worker without polling.
+        async_std::future::timeout(Duration::from_secs(5), async {
+            while ready.lock().unwrap().len() < 3 {
+                server.recv().await.unwrap();
+            }
+        })
+        .await
+        .expect("bridged events were not received without polling");
+        worker.join().unwrap();
+        assert_eq!(*ready.lock().unwrap(), vec![0, 1, 2]);
+    })
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn projection_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum OrderEvent {
+        Placed(u32),
+        Paid(u32, u64),
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum BillingEvent {
+        Charged(u64),
+    }
+
+    let charged = Arc::new(Mutex::new(vec![]));
+    let billed = Arc::new(Mutex::new(vec![]));
+    let (c1, c2) = (charged.clone(), billed.clone());
+    let to_billing = |ev| match ev {
+        OrderEvent::Paid(_, amount) => Some(BillingEvent::Charged(amount)),
+        OrderEvent::Placed(_) => None,
+    };
+
+    let orders = BasicMediator::<OrderEvent>::builder()
+        .add_mapped_listener(to_billing, move |ev| c1.lock().unwrap().push(ev))
+        .build();
+    let billing = BasicMediator::<BillingEvent>::builder()
+        .project_from(&orders, to_billing)
+        .add_listener(move |ev| c2.lock().unwrap().push(ev))
+        .build();
+
+    orders.publish(OrderEvent::Placed(1));
+    orders.publish(OrderEvent::Paid(1, 100));
+    orders.publish(OrderEvent::Paid(2, 250));
+    while orders.next().is_ok() {}
+    while billing.next().is_ok() {}
+
+    let expected = vec![BillingEvent::Charged(100), BillingEvent::Charged(250)];
+    assert_eq!(*charged.lock().unwrap(), expected);
+    assert_eq!(*billed.lock().unwrap(), expected);
+
+    // Dropping the derived mediator removes the projection.
+    drop(billing);
+    orders.publish(OrderEvent::Paid(3, 5));
+    while orders.next().is_ok() {}
+    assert!(format!("{:?}", orders).contains("projections: 0"));
+    assert_eq!(charged.lock().unwrap().len(), 3);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn projection_test_async() {
+    use std::sync::{Arc, Mutex};
+
+    use crate::asynchronous::basic::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum OrderEvent {
+        Placed(u32),
+        Paid(u32, u64),
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum BillingEvent {
+        Charged(u32, u64),
+    }
+
+    async_std::task::block_on(async {
+        let placed = Arc::new(Mutex::new(vec![]));
+        let billed = Arc::new(Mutex::new(vec![]));
+        let (c1, c2) = (placed.clone(), billed.clone());
+
+        let orders = BasicAsyncMediator::<OrderEvent>::builder()
+            .add_mapped_listener(
+                |ev| match ev {
+                    OrderEvent::Placed(id) => Some(id),
+                    OrderEvent::Paid(..) => None,
+                },
+                move |id| c1.lock().unwrap().push(id),
+            )
+            .build();
+        let billing = BasicAsyncMediator::<BillingEvent>::builder()
+            .project_from(&orders, |ev| match ev {
+                OrderEvent::Paid(id, amount) => Some(BillingEvent::Charged(id, amount)),
+                OrderEvent::Placed(_) => None,
+            })
+            .add_listener(move |ev| c2.lock().unwrap().push(ev))
+            .build();
+
+        orders.publish(OrderEvent::Placed(1)).await;
+        orders.publish(OrderEvent::Paid(1, 100)).await;
+        orders.publish(OrderEvent::Placed(2)).await;
+        while orders.next().await.is_ok() {}
+        while billing.next().await.is_ok() {}
+
+        assert_eq!(*placed.lock().unwrap(), vec![1, 2]);
+        assert_eq!(*billed.lock().unwrap(), vec![BillingEvent::Charged(1, 100)]);
+    })
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn outbox_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Reserved(u32),
+        Charged(u32),
+        Attempted(u32),
+        Shipped(u32),
+    }
+
+    #[derive(Clone)]
+    struct Order {
+        id: u32,
+        fail: bool,
+    }
+
+    struct Ship(u32);
+
+    impl TryRequestHandler<Order, MyEvent> for BasicMediator<MyEvent> {
+        type Error = String;
+
+        fn try_handle(&self, req: Order) -> Result<(), String> {
+            self.publish_immediately(MyEvent::Attempted(req.id));
+            self.publish(MyEvent::Reserved(req.id));
+            self.try_send(Ship(req.id))?;
+            self.publish(MyEvent::Charged(req.id));
+            match req.fail {
+                true => Err(String::from("payment declined")),
+                false => Ok(()),
+            }
+        }
+    }
+
+    impl TryRequestHandler<Ship, MyEvent> for BasicMediator<MyEvent> {
+        type Error = String;
+
+        fn try_handle(&self, req: Ship) -> Result<(), String> {
+            self.publish(MyEvent::Shipped(req.0));
+            Ok(())
+        }
+    }
+
+    let received = Arc::new(Mutex::new(vec![]));
+    let c1 = received.clone();
+    let mediator = BasicMediator::<MyEvent>::builder()
+        .add_listener(move |ev| c1.lock().unwrap().push(ev))
+        .transactional()
+        .build();
+
+    assert!(mediator.try_send(Order { id: 1, fail: true }).is_err());
+    assert!(mediator.try_send(Order { id: 2, fail: false }).is_ok());
+    while mediator.next().is_ok() {}
+
+    // The events of the failed order, including those of the nested
+    // request, are discarded, except for the one published immediately.
+    assert_eq!(
+        *received.lock().unwrap(),
+        vec![
+            MyEvent::Attempted(1),
+            MyEvent::Attempted(2),
+            MyEvent::Reserved(2),
+            MyEvent::Shipped(2),
+            MyEvent::Charged(2),
+        ]
+    );
+
+    // Without an outbox, events are delivered even if the handler fails.
+    received.lock().unwrap().clear();
+    let c2 = received.clone();
+    let mediator = BasicMediator::<MyEvent>::builder()
+        .add_listener(move |ev| c2.lock().unwrap().push(ev))
+        .build();
+    assert!(mediator.try_send(Order { id: 3, fail: true }).is_err());
+    while mediator.next().is_ok() {}
+    assert_eq!(received.lock().unwrap().len(), 4);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn outbox_test_async() {
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    use crate::asynchronous::contextaware::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Step(u32, u32),
+        Attempted(u32),
+    }
+
+    #[derive(Debug)]
+    struct Steps(u32);
+
+    struct Job {
+        id: u32,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl TryCxAwareAsyncRequestHandler<Steps, Job, MyEvent> for CxAwareAsyncMediator<Steps, MyEvent> {
+        type Error = String;
+
+        async fn try_handle(
+            &self,
+            req: Job,
+            steps: &Steps,
+            _scope: &mut Container,
+        ) -> Result<(), String> {
+            self.publish_immediately(MyEvent::Attempted(req.id)).await;
+            for step in 0..steps.0 {
+                self.publish(MyEvent::Step(req.id, step)).await;
+                // Lets the other job interleave with this one.
+                async_std::task::yield_now().await;
+            }
+            match req.fail {
+                true => Err(String::from("job failed")),
+                false => Ok(()),
+            }
+        }
+    }
+
+    async_std::task::block_on(async {
+        let received = Arc::new(Mutex::new(vec![]));
+        let c1 = received.clone();
+        let mediator = CxAwareAsyncMediator::<Steps, MyEvent>::builder()
+            .add_dependency(Steps(3))
+            .add_listener(move |ev| c1.lock().unwrap().push(ev))
+            .transactional()
+            .build()
+            .unwrap();
+
+        let (failed, succeeded) = futures::join!(
+            mediator.try_send(Job { id: 1, fail: true }),
+            mediator.try_send(Job { id: 2, fail: false }),
+        );
+        assert!(failed.is_err());
+        assert!(succeeded.is_ok());
+        while mediator.next().await.is_ok() {}
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![
+                MyEvent::Attempted(1),
+                MyEvent::Attempted(2),
+                MyEvent::Step(2, 0),
+                MyEvent::Step(2, 1),
+                MyEvent::Step(2, 2),
+            ]
+        );
+    })
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn saga_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Placed(String),
+        Reserved(String),
+        Charged(String),
+        Shipped(String),
+        Released(String),
+    }
+
+    enum Command {
+        Reserve(String),
+        Charge(String),
+        Ship(String),
+        Release(String),
+    }
+
+    struct Checkout;
+
+    impl Saga<MyEvent> for Checkout {
+        type Id = String;
+        type State = String;
+        type Request = Command;
+
+        fn correlate(&self, ev: &MyEvent) -> Option<String> {
+            match ev {
+                MyEvent::Placed(id)
+                | MyEvent::Reserved(id)
+                | MyEvent::Charged(id)
+                | MyEvent::Shipped(id)
+                | MyEvent::Released(id) => Some(id.clone()),
+            }
+        }
+
+        fn start(&self, _id: &String, ev: &MyEvent) -> Option<String> {
+            matches!(ev, MyEvent::Placed(_)).then(|| String::from("placed"))
+        }
+
+        fn react(&self, state: &mut String, ev: &MyEvent) -> Step<Command> {
+            match ev {
+                MyEvent::Placed(id) => Step::Continue(vec![Command::Reserve(id.clone())]),
+                MyEvent::Reserved(id) => {
+                    *state = String::from("reserved");
+                    Step::Continue(vec![Command::Charge(id.clone())])
+                }
+                MyEvent::Charged(id) => Step::Complete(vec![Command::Ship(id.clone())]),
+                _ => Step::Continue(vec![]),
+            }
+        }
+
+        fn compensate(&self, id: &String, state: &String) -> Vec<Command> {
+            match state.as_str() {
+                "reserved" => vec![Command::Release(id.clone())],
+                _ => vec![],
+            }
+        }
+
+        fn timeout(&self) -> Option<Duration> {
+            Some(Duration::from_secs(60))
+        }
+    }
+
+    impl TryRequestHandler<Command, MyEvent> for BasicMediator<MyEvent> {
+        type Error = String;
+
+        fn try_handle(&self, req: Command) -> Result<(), String> {
+            match req {
+                // Reservations of slow orders are confirmed by nobody.
+                Command::Reserve(id) if id == "slow" => {}
+                Command::Reserve(id) => self.publish(MyEvent::Reserved(id)),
+                Command::Charge(id) if id == "declined" => {
+                    return Err(String::from("payment declined"))
+                }
+                // Payments of stuck orders are confirmed by nobody.
+                Command::Charge(id) if id == "stuck" => {}
+                Command::Charge(id) => self.publish(MyEvent::Charged(id)),
+                Command::Ship(id) => self.publish(MyEvent::Shipped(id)),
+                Command::Release(id) => self.publish(MyEvent::Released(id)),
+            }
+            Ok(())
+        }
+    }
+
+    let path =
+        std::env::temp_dir().join(format!("mediator-sys-saga-test-{}.log", std::process::id()));
+    std::fs::remove_file(&path).ok();
+
+    let received = Arc::new(Mutex::new(vec![]));
+    let clock = Arc::new(MockClock::new());
+    {
+        let c1 = received.clone();
+        let mediator = BasicMediator::<MyEvent>::builder()
+            .add_listener(move |ev| c1.lock().unwrap().push(ev))
+            .add_saga_with_storage(Checkout, FileStorage::new(&path))
+            .clock(clock.clone())
+            .build();
+
+        mediator.publish(MyEvent::Placed(String::from("ok")));
+        mediator.publish(MyEvent::Placed(String::from("declined")));
+        while mediator.next().is_ok() {}
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![
+                MyEvent::Placed(String::from("ok")),
+                MyEvent::Placed(String::from("declined")),
+                MyEvent::Reserved(String::from("ok")),
+                MyEvent::Reserved(String::from("declined")),
+                MyEvent::Charged(String::from("ok")),
+                MyEvent::Released(String::from("declined")),
+                MyEvent::Shipped(String::from("ok")),
+            ]
+        );
+
+        received.lock().unwrap().clear();
+        mediator.publish(MyEvent::Placed(String::from("slow")));
+        mediator.publish(MyEvent::Placed(String::from("stuck")));
+        while mediator.next().is_ok() {}
+    }
+
+    // Only the running instances are persisted, along with their deadlines.
+    let storage = FileStorage::<SagaInstance<String, String>>::new(&path);
+    let instances = storage.load().unwrap();
+    assert_eq!(
+        instances
+            .iter()
+            .map(|instance| (instance.id.as_str(), instance.state.as_str()))
+            .collect::<Vec<_>>(),
+        vec![("slow", "placed"), ("stuck", "reserved")]
+    );
+    assert!(instances.iter().all(|instance| instance.deadline.is_some()));
+
+    // A restarted mediator restores the instances, which time out
+    // and are compensated according to their state.
+    received.lock().unwrap().clear();
+    let c2 = received.clone();
+    let mediator = BasicMediator::<MyEvent>::builder()
+        .add_listener(move |ev| c2.lock().unwrap().push(ev))
+        .add_saga_with_storage(Checkout, FileStorage::new(&path))
+        .clock(clock.clone())
+        .build();
+
+    assert!(mediator.next().is_err());
+    clock.advance(Duration::from_secs(61));
+    while mediator.next().is_ok() {}
+
+    assert_eq!(
+        *received.lock().unwrap(),
+        vec![MyEvent::Released(String::from("stuck"))]
+    );
+    assert!(storage.load().unwrap().is_empty());
+    drop(mediator);
+
+    // An instance whose deadline passed while no mediator was running
+    // is compensated right away.
+    storage
+        .replace(vec![SagaInstance {
+            id: String::from("late"),
+            state: String::from("reserved"),
+            deadline: Some(std::time::SystemTime::now() - Duration::from_secs(1)),
+        }])
+        .unwrap();
+    received.lock().unwrap().clear();
+    let c3 = received.clone();
+    let mediator = BasicMediator::<MyEvent>::builder()
+        .add_listener(move |ev| c3.lock().unwrap().push(ev))
+        .add_saga_with_storage(Checkout, FileStorage::new(&path))
+        .clock(clock.clone())
+        .build();
+
+    while mediator.next().is_ok() {}
+    assert_eq!(
+        *received.lock().unwrap(),
+        vec![MyEvent::Released(String::from("late"))]
+    );
+    std::fs::remove_file(&path).ok();
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn saga_test_async() {
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    use crate::asynchronous::contextaware::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Booked(u32),
+        Confirmed(u32),
+        Cancelled(u32),
+    }
+
+    enum Booking {
+        Hold(u32),
+        Confirm(u32),
+        Cancel(u32),
+    }
+
+    #[derive(Debug)]
+    struct Hotel {
+        rooms: u32,
+    }
+
+    struct Trip;
+
+    impl Saga<MyEvent> for Trip {
+        type Id = u32;
+        type State = ();
+        type Request = Booking;
+
+        fn correlate(&self, ev: &MyEvent) -> Option<u32> {
+            match ev {
+                MyEvent::Booked(id) | MyEvent::Confirmed(id) => Some(*id),
+                MyEvent::Cancelled(_) => None,
+            }
+        }
+
+        fn start(&self, _id: &u32, ev: &MyEvent) -> Option<()> {
+            matches!(ev, MyEvent::Booked(_)).then_some(())
+        }
+
+        fn react(&self, _state: &mut (), ev: &MyEvent) -> Step<Booking> {
+            match ev {
+                MyEvent::Booked(id) => {
+                    Step::Continue(vec![Booking::Hold(*id), Booking::Confirm(*id)])
+                }
+                _ => Step::Complete(vec![]),
+            }
+        }
+
+        fn compensate(&self, id: &u32, _state: &()) -> Vec<Booking> {
+            vec![Booking::Cancel(*id)]
+        }
+    }
+
+    #[async_trait]
+    impl TryCxAwareAsyncRequestHandler<Hotel, Booking, MyEvent>
+        for CxAwareAsyncMediator<Hotel, MyEvent>
+    {
+        type Error = String;
+
+        async fn try_handle(
+            &self,
+            req: Booking,
+            hotel: &Hotel,
+            _scope: &mut Container,
+        ) -> Result<(), String> {
+            match req {
+                Booking::Hold(id) if id > hotel.rooms => return Err(String::from("fully booked")),
+                Booking::Hold(_) => {}
+                Booking::Confirm(id) => self.publish(MyEvent::Confirmed(id)).await,
+                Booking::Cancel(id) => self.publish(MyEvent::Cancelled(id)).await,
+            }
+            Ok(())
+        }
+    }
+
+    async_std::task::block_on(async {
+        let received = Arc::new(Mutex::new(vec![]));
+        let c1 = received.clone();
+        let mediator = CxAwareAsyncMediator::<Hotel, MyEvent>::builder()
+            .add_dependency(Hotel { rooms: 1 })
+            .add_listener(move |ev| c1.lock().unwrap().push(ev))
+            .add_saga(Trip)
+            .build()
+            .unwrap();
+
+        mediator.publish(MyEvent::Booked(1)).await;
+        mediator.publish(MyEvent::Booked(2)).await;
+        while mediator.next().await.is_ok() {}
+
+        // The failed hold of the second booking is compensated
+        // and its pending confirmation is dropped.
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![
+                MyEvent::Booked(1),
+                MyEvent::Booked(2),
+                MyEvent::Confirmed(1),
+                MyEvent::Cancelled(2),
+            ]
+        );
+    })
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn history_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Clone)]
+    enum Edit {
+        Insert(usize, String),
+        Delete(usize, String),
+    }
+
+    impl Undoable for Edit {
+        type Inverse = Edit;
+
+        fn inverse(&self) -> Edit {
+            match self {
+                Edit::Insert(at, text) => Edit::Delete(*at, text.clone()),
+                Edit::Delete(at, text) => Edit::Insert(*at, text.clone()),
+            }
+        }
+
+        fn label(&self) -> String {
+            match self {
+                Edit::Insert(..) => String::from("insert"),
+                Edit::Delete(..) => String::from("delete"),
+            }
+        }
+    }
+
+    impl RequestHandler<Edit, Edit> for BasicMediator<Edit> {
+        fn handle(&self, req: Edit) {
+            if matches!(&req, Edit::Delete(_, text) if text == "boom") {
+                panic!("cannot delete");
+            }
+            self.publish(req);
+        }
+    }
+
+    let text = Arc::new(Mutex::new(String::new()));
+    let cloned = text.clone();
+    let mediator = BasicMediator::<Edit>::builder()
+        .add_listener(move |ev| {
+            let mut text = cloned.lock().unwrap();
+            match ev {
+                Edit::Insert(at, inserted) => text.insert_str(at, &inserted),
+                Edit::Delete(at, deleted) => {
+                    text.replace_range(at..at + deleted.len(), "");
+                }
+            }
+        })
+        .command_history(3)
+        .build();
+    let apply = || while mediator.next().is_ok() {};
+
+    assert!(!mediator.undo());
+    mediator.send_undoable(Edit::Insert(0, String::from("world")));
+    mediator.begin_group("greet");
+    mediator.send_undoable(Edit::Insert(0, String::from("hello")));
+    mediator.begin_group("nested");
+    mediator.send_undoable(Edit::Insert(5, String::from(", ")));
+    mediator.end_group();
+    mediator.end_group();
+    apply();
+    assert_eq!(*text.lock().unwrap(), "hello, world");
+    assert_eq!(
+        mediator.history().undo,
+        vec![
+            HistoryStep {
+                label: String::from("insert"),
+                requests: vec![String::from("insert")],
+            },
+            HistoryStep {
+                label: String::from("greet"),
+                requests: vec![String::from("insert"), String::from("insert")],
+            },
+        ]
+    );
+
+    // The group is undone as a whole, in reverse order.
+    assert!(mediator.undo());
+    apply();
+    assert_eq!(*text.lock().unwrap(), "world");
+    assert!(mediator.undo());
+    apply();
+    assert_eq!(*text.lock().unwrap(), "");
+    assert!(!mediator.undo());
+
+    assert!(mediator.redo());
+    assert!(mediator.redo());
+    apply();
+    assert_eq!(*text.lock().unwrap(), "hello, world");
+    assert!(!mediator.redo());
+
+    // A new request discards the steps to redo.
+    assert!(mediator.undo());
+    mediator.send_undoable(Edit::Delete(0, String::from("wor")));
+    apply();
+    assert_eq!(*text.lock().unwrap(), "ld");
+    assert!(mediator.history().redo.is_empty());
+
+    // Only the last three steps are kept.
+    mediator.send_undoable(Edit::Insert(2, String::from("!")));
+    mediator.send_undoable(Edit::Insert(3, String::from("!")));
+    assert_eq!(mediator.history().undo.len(), 3);
+    while mediator.undo() {}
+    apply();
+    assert_eq!(*text.lock().unwrap(), "world");
+
+    // A step whose undo panics stays in the history.
+    mediator.send_undoable(Edit::Insert(5, String::from("boom")));
+    apply();
+    let undone = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| mediator.undo()));
+    assert!(undone.is_err());
+    assert_eq!(mediator.history().undo.len(), 1);
+    assert!(mediator.history().redo.is_empty());
+
+    // Without a command history, nothing is recorded.
+    let mediator = BasicMediator::<Edit>::builder().build();
+    mediator.send_undoable(Edit::Insert(0, String::from("a")));
+    assert!(!mediator.undo());
+    assert_eq!(mediator.history(), CommandHistory::default());
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn history_test_async() {
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+
+    use crate::asynchronous::contextaware::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum MyEvent {
+        Painted(u32, String),
+    }
+
+    #[derive(Debug, Clone)]
+    struct Paint {
+        pixel: u32,
+        color: String,
+        previous: String,
+    }
+
+    impl Undoable for Paint {
+        type Inverse = Paint;
+
+        fn inverse(&self) -> Paint {
+            Paint {
+                pixel: self.pixel,
+                color: self.previous.clone(),
+                previous: self.color.clone(),
+            }
+        }
+    }
+
+    #[derive(Debug)]
+    struct Palette(Vec<&'static str>);
+
+    #[async_trait]
+    impl CxAwareAsyncRequestHandler<Palette, Paint, MyEvent>
+        for CxAwareAsyncMediator<Palette, MyEvent>
+    {
+        async fn handle(&self, req: Paint, palette: &Palette) {
+            if req.color == "slow" {
+                futures::future::pending::<()>().await;
+            }
+            if palette.0.contains(&req.color.as_str()) {
+                self.publish(MyEvent::Painted(req.pixel, req.color)).await;
+            }
+        }
+    }
+
+    let paint = |pixel, color: &str, previous: &str| Paint {
+        pixel,
+        color: String::from(color),
+        previous: String::from(previous),
+    };
+
+    async_std::task::block_on(async {
+        let received = Arc::new(Mutex::new(vec![]));
+        let c1 = received.clone();
+        let mediator = CxAwareAsyncMediator::<Palette, MyEvent>::builder()
+            .add_dependency(Palette(vec!["white", "red", "blue"]))
+            .add_listener(move |ev| c1.lock().unwrap().push(ev))
+            .command_history(10)
+            .build()
+            .unwrap();
+
+        mediator.begin_group("fill");
+        mediator.send_undoable(paint(1, "red", "white")).await;
+        mediator.send_undoable(paint(2, "red", "white")).await;
+        mediator.end_group();
+        mediator.send_undoable(paint(1, "blue", "red")).await;
+
+        assert_eq!(mediator.history().undo.len(), 2);
+        assert!(mediator.undo().await);
+        assert!(mediator.undo().await);
+        assert!(mediator.redo().await);
+        assert_eq!(mediator.history().redo.len(), 1);
+        while mediator.next().await.is_ok() {}
+
+        let painted = |pixel, color: &str| MyEvent::Painted(pixel, String::from(color));
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![
+                painted(1, "red"),
+                painted(2, "red"),
+                painted(1, "blue"),
+                painted(1, "red"),
+                painted(2, "white"),
+                painted(1, "white"),
+                painted(1, "red"),
+                painted(2, "red"),
+            ]
+        );
+
+        // An interrupted undo keeps its step below the ones recorded meanwhile.
+        mediator.send_undoable(paint(3, "red", "slow")).await;
+        let mut undo = Box::pin(mediator.undo());
+        assert!(futures::FutureExt::now_or_never(&mut undo).is_none());
+        mediator.send_undoable(paint(4, "blue", "white")).await;
+        drop(undo);
+        assert_eq!(mediator.history().undo.len(), 3);
+        assert!(mediator.undo().await);
+        assert!(futures::FutureExt::now_or_never(mediator.undo()).is_none());
+        while mediator.next().await.is_ok() {}
+        assert_eq!(received.lock().unwrap().last(), Some(&painted(4, "white")));
+        assert_eq!(mediator.history().undo.len(), 2);
+    })
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn idempotency_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Paid {
+        order: u32,
+        attempt: Option<u32>,
+    }
+
+    impl Idempotent for Paid {
+        fn idempotency_key(&self) -> Option<String> {
+            self.attempt
+                .map(|attempt| format!("{}/{}", self.order, attempt))
+        }
+    }
+
+    struct Pay {
+        order: u32,
+        key: Option<&'static str>,
+    }
+
+    impl Idempotent for Pay {
+        fn idempotency_key(&self) -> Option<String> {
+            self.key.map(String::from)
+        }
+    }
+
+    impl TryRequestHandler<Pay, Paid> for BasicMediator<Paid> {
+        type Error = String;
+
+        fn try_handle(&self, req: Pay) -> Result<(), String> {
+            if req.order == 0 {
+                return Err(String::from("unknown order"));
+            }
+            self.publish(Paid {
+                order: req.order,
+                attempt: None,
+            });
+            Ok(())
+        }
+    }
+
+    impl RequestHandler<Pay, Paid> for BasicMediator<Paid> {
+        fn handle(&self, req: Pay) {
+            self.try_send(req).ok();
+        }
+    }
+
+    /// Remembers every key, counting the lookups.
+    #[derive(Debug, Default)]
+    struct Counting {
+        keys: Mutex<Vec<String>>,
+        lookups: Mutex<usize>,
+    }
+
+    impl DedupStore<()> for Arc<Counting> {
+        fn get(&self, key: &str) -> Option<()> {
+            *self.lookups.lock().unwrap() += 1;
+            self.keys
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|k| *k == key)
+                .map(|_| ())
+        }
+
+        fn insert(&self, key: String, _value: ()) {
+            self.keys.lock().unwrap().push(key);
+        }
+    }
+
+    let received = Arc::new(Mutex::new(vec![]));
+    let c1 = received.clone();
+    let events = Arc::new(Counting::default());
+    let mediator = BasicMediator::<Paid>::builder()
+        .add_listener(move |ev| c1.lock().unwrap().push(ev))
+        .deduplicate_requests(2)
+        .deduplicate_events_with(events.clone())
+        .build();
+
+    // Failures are not cached, so the request is handled again, successes are.
+    let unknown = || Pay {
+        order: 0,
+        key: Some("a"),
+    };
+    assert!(mediator.try_send_idempotent(unknown()).is_err());
+    assert_eq!(
+        mediator.try_send_idempotent(Pay {
+            order: 1,
+            key: Some("a"),
+        }),
+        Ok(())
+    );
+    assert_eq!(mediator.try_send_idempotent(unknown()), Ok(()));
+
+    mediator.send_idempotent(Pay {
+        order: 2,
+        key: Some("b"),
+    });
+    mediator.send_idempotent(Pay {
+        order: 2,
+        key: Some("b"),
+    });
+    mediator.send_idempotent(Pay {
+        order: 3,
+        key: None,
+    });
+    mediator.send_idempotent(Pay {
+        order: 3,
+        key: None,
+    });
+    while mediator.next().is_ok() {}
+    let orders: Vec<u32> = received.lock().unwrap().iter().map(|ev| ev.order).collect();
+    assert_eq!(orders, vec![1, 2, 3, 3]);
+
+    // Only the last two keys are remembered, so "a" is handled again.
+    assert!(mediator
+        .try_send_idempotent(Pay {
+            order: 4,
+            key: Some("c"),
+        })
+        .is_ok());
+    assert!(mediator
+        .try_send_idempotent(Pay {
+            order: 5,
+            key: Some("a"),
+        })
+        .is_ok());
+
+    // Events are deduplicated by their own key, even if published repeatedly.
+    received.lock().unwrap().clear();
+    let paid = |order, attempt| Paid { order, attempt };
+    for ev in [
+        paid(6, Some(1)),
+        paid(6, Some(1)),
+        paid(6, Some(2)),
+        paid(6, Some(1)),
+    ] {
+        mediator.publish(ev);
+    }
+    while mediator.next().is_ok() {}
+    assert_eq!(
+        *received.lock().unwrap(),
+        vec![
+            paid(4, None),
+            paid(5, None),
+            paid(6, Some(1)),
+            paid(6, Some(2))
+        ]
+    );
+    assert_eq!(*events.keys.lock().unwrap(), vec!["6/1", "6/2"]);
+    assert_eq!(*events.lookups.lock().unwrap(), 4);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn idempotency_test_async() {
+    use async_trait::async_trait;
+    use futures::FutureExt;
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        },
+        time::Duration,
+    };
+
+    use crate::asynchronous::basic::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Shipped(u32);
+
+    impl Idempotent for Shipped {
+        fn idempotency_key(&self) -> Option<String> {
+            Some(self.0.to_string())
+        }
+    }
+
+    struct Ship(u32);
+
+    impl Idempotent for Ship {
+        fn idempotency_key(&self) -> Option<String> {
+            Some(format!("ship-{}", self.0))
+        }
+    }
+
+    #[async_trait]
+    impl TryAsyncRequestHandler<Ship, Shipped> for BasicAsyncMediator<Shipped> {
+        type Error = String;
+
+        async fn try_handle(&self, req: Ship) -> Result<(), String> {
+            HANDLED.fetch_add(1, Ordering::SeqCst);
+            // Lets duplicates be sent while the request is being handled.
+            async_std::task::yield_now().await;
+            // Publishes the same event twice, as an at-least-once sender would.
+            self.publish(Shipped(req.0)).await;
+            self.publish(Shipped(req.0)).await;
+            match req.0 {
+                0 => Err(String::from("nothing to ship")),
+                _ => Ok(()),
+            }
+        }
+    }
+
+    struct Resend(u32);
+
+    impl Idempotent for Resend {
+        fn idempotency_key(&self) -> Option<String> {
+            Some(format!("resend-{}", self.0))
+        }
+    }
+
+    #[async_trait]
+    impl AsyncRequestHandler<Resend, Shipped> for BasicAsyncMediator<Shipped> {
+        async fn handle(&self, req: Resend) {
+            HANDLED.fetch_add(1, Ordering::SeqCst);
+            self.send_idempotent(Resend(req.0)).await;
+        }
+    }
+
+    #[async_trait]
+    impl TryAsyncRequestHandler<Resend, Shipped> for BasicAsyncMediator<Shipped> {
+        type Error = String;
+
+        async fn try_handle(&self, req: Resend) -> Result<(), String> {
+            HANDLED.fetch_add(1, Ordering::SeqCst);
+            assert_eq!(self.try_send_idempotent(Resend(req.0)).await, Ok(()));
+            Err(String::from("resent"))
+        }
+    }
+
+    static HANDLED: AtomicUsize = AtomicUsize::new(0);
+
+    async_std::task::block_on(async {
+        let received = Arc::new(Mutex::new(vec![]));
+        let c1 = received.clone();
+        let mediator = BasicAsyncMediator::<Shipped>::builder()
+            .add_listener(move |ev| c1.lock().unwrap().push(ev))
+            .deduplicate_requests(100)
+            .deduplicate_events(100)
+            .build();
+
+        // Failures are handled again, successes are not.
+        for _ in 0..3 {
+            assert!(mediator.try_send_idempotent(Ship(1)).await.is_ok());
+            assert!(mediator.try_send_idempotent(Ship(0)).await.is_err());
+        }
+        while mediator.next().await.is_ok() {}
+        assert_eq!(*received.lock().unwrap(), vec![Shipped(1), Shipped(0)]);
+        assert_eq!(HANDLED.swap(0, Ordering::SeqCst), 4);
+
+        // Concurrent duplicates wait for the outcome of the first request.
+        let (first, second) = futures::join!(
+            mediator.try_send_idempotent(Ship(2)),
+            mediator.try_send_idempotent(Ship(2)),
+        );
+        assert!(first.is_ok() && second.is_ok());
+        let (first, second) = futures::join!(
+            mediator.try_send_idempotent(Ship(0)),
+            mediator.try_send_idempotent(Ship(0)),
+        );
+        assert!(first.is_err() && second.is_err());
+        assert_eq!(HANDLED.swap(0, Ordering::SeqCst), 2);
+
+        // An interrupted request releases its key.
+        assert!(mediator
+            .try_send_idempotent(Ship(3))
+            .now_or_never()
+            .is_none());
+        assert!(mediator.try_send_idempotent(Ship(3)).await.is_ok());
+        assert!(mediator.try_send_idempotent(Ship(3)).await.is_ok());
+        assert_eq!(HANDLED.swap(0, Ordering::SeqCst), 2);
+
+        // A duplicate sent by the handler itself returns instead of waiting for itself.
+        let timeout = Duration::from_secs(5);
+        let resent = async_std::future::timeout(timeout, mediator.send_idempotent(Resend(1))).await;
+        assert!(resent.is_ok());
+        let resent =
+            async_std::future::timeout(timeout, mediator.try_send_idempotent(Resend(2))).await;
+        assert_eq!(resent.ok(), Some(Err(String::from("resent"))));
+        assert_eq!(HANDLED.load(Ordering::SeqCst), 2);
+    })
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn cqrs_test_sync() {
+    use crate::synchronous::basic::*;
+
+    use std::{
+        cell::RefCell,
+        sync::{Arc, Mutex},
+    };
+
+    thread_local! {
+        static STOCK: RefCell<Vec<(String, u32)>> = const { RefCell::new(vec![]) };
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Stock {
+        Restocked(String, u32),
+    }
+
+    struct Restock(&'static str, u32);
+
+    struct InStock(&'static str);
+
+    struct Items;
+
+    impl CommandHandler<Restock, Stock> for BasicMediator<Stock> {
+        fn handle(&self, cmd: Restock) {
+            STOCK.with(|stock| stock.borrow_mut().push((cmd.0.to_owned(), cmd.1)));
+            self.publish(Stock::Restocked(cmd.0.to_owned(), cmd.1));
+        }
+    }
+
+    impl QueryHandler<InStock, Stock> for BasicMediator<Stock> {
+        type Response = u32;
+
+        fn handle(query: InStock, _: &QueryContext<'_, Self>) -> u32 {
+            STOCK.with(|stock| {
+                stock
+                    .borrow()
+                    .iter()
+                    .filter(|(item, _)| item == query.0)
+                    .map(|(_, n)| n)
+                    .sum()
+            })
+        }
+    }
+
+    impl QueryHandler<Items, Stock> for BasicMediator<Stock> {
+        type Response = Vec<String>;
+
+        fn handle(_: Items, _: &QueryContext<'_, Self>) -> Vec<String> {
+            STOCK.with(|stock| {
+                let mut items: Vec<String> = stock
+                    .borrow()
+                    .iter()
+                    .map(|(item, _)| item.clone())
+                    .collect();
+                items.dedup();
+                items
+            })
+        }
+    }
+
+    let received = Arc::new(Mutex::new(vec![]));
+    let c1 = received.clone();
+    let mediator = BasicMediator::<Stock>::builder()
+        .add_listener(move |ev| c1.lock().unwrap().push(ev))
+        .build();
+
+    assert_eq!(mediator.query(InStock("bolt")), 0);
+    mediator.execute(Restock("bolt", 3));
+    mediator.execute(Restock("bolt", 4));
+    mediator.execute(Restock("nut", 1));
+
+    assert_eq!(mediator.query(InStock("bolt")), 7);
+    assert_eq!(mediator.query(Items), vec!["bolt", "nut"]);
+
+    // Only commands publish events.
+    while mediator.next().is_ok() {}
+    assert_eq!(
+        *received.lock().unwrap(),
+        vec![
+            Stock::Restocked(String::from("bolt"), 3),
+            Stock::Restocked(String::from("bolt"), 4),
+            Stock::Restocked(String::from("nut"), 1),
+        ]
+    );
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn cqrs_test_async() {
+    use async_trait::async_trait;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use crate::asynchronous::contextaware::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Account {
+        Credited(u32),
+    }
+
+    #[derive(Debug, Default)]
+    struct Ledger {
+        version: u32,
+        entries: Mutex<Vec<u32>>,
+    }
+
+    struct Credit(u32);
+
+    struct Total;
+
+    struct SlowVersion(Arc<Mutex<Vec<&'static str>>>);
+
+    #[async_trait]
+    impl CxAwareAsyncCommandHandler<Ledger, Credit, Account> for CxAwareAsyncMediator<Ledger, Account> {
+        async fn handle(&self, cmd: Credit, dep: &Ledger) {
+            dep.entries.lock().unwrap().push(cmd.0);
+            self.publish(Account::Credited(cmd.0)).await;
+        }
+    }
+
+    #[async_trait]
+    impl CxAwareAsyncQueryHandler<Ledger, Total, Account> for CxAwareAsyncMediator<Ledger, Account> {
+        type Response = u32;
+
+        async fn handle(_: Total, cx: &CxAwareQueryContext<'_, Ledger, Account>) -> u32 {
+            cx.dep().entries.lock().unwrap().iter().sum()
+        }
+    }
+
+    #[async_trait]
+    impl CxAwareAsyncQueryHandler<Ledger, SlowVersion, Account>
+        for CxAwareAsyncMediator<Ledger, Account>
+    {
+        type Response = u32;
+
+        async fn handle(query: SlowVersion, cx: &CxAwareQueryContext<'_, Ledger, Account>) -> u32 {
+            async_std::task::sleep(Duration::from_millis(50)).await;
+            query.0.lock().unwrap().push("queried");
+            // Nested queries read the same dependency without locking it again.
+            cx.dep().version + cx.query(Total).await
+        }
+    }
+
+    async_std::task::block_on(async {
+        let received = Arc::new(Mutex::new(vec![]));
+        let c1 = received.clone();
+        let mediator = CxAwareAsyncMediator::<Ledger, Account>::builder()
+            .add_dependency(Ledger::default())
+            .add_listener(move |ev| c1.lock().unwrap().push(ev))
+            .build()
+            .unwrap();
+
+        mediator.execute(Credit(5)).await;
+        mediator.execute(Credit(10)).await;
+        assert_eq!(mediator.query(Total).await, 15);
+
+        mediator.next().await.ok();
+        mediator.next().await.ok();
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![Account::Credited(5), Account::Credited(10)]
+        );
+
+        // The dependency is not replaced while a query reads it.
+        let order = Arc::new(Mutex::new(vec![]));
+        let replace = async {
+            async_std::task::sleep(Duration::from_millis(10)).await;
+            mediator
+                .replace_dependency(Ledger {
+                    version: 1,
+                    entries: Mutex::new(vec![]),
+                })
+                .await;
+            order.lock().unwrap().push("replaced");
+        };
+        let (version, _) = futures::join!(mediator.query(SlowVersion(order.clone())), replace);
+
+        assert_eq!(version, 15);
+        assert_eq!(*order.lock().unwrap(), vec!["queried", "replaced"]);
+        assert_eq!(mediator.query(Total).await, 0);
+    })
+}
+
+#[cfg(not(feature = "async"))]
+#[test]
+fn query_cache_test_sync() {
+    use crate::cache::QueryCache;
+    use crate::synchronous::basic::*;
+
+    use std::{cell::RefCell, collections::HashMap, sync::Arc, time::Duration};
+
+    thread_local! {
+        static PRICES: RefCell<HashMap<&'static str, u32>> = RefCell::new(HashMap::new());
+        static HANDLED: RefCell<Vec<&'static str>> = const { RefCell::new(vec![]) };
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Catalog {
+        Repriced(&'static str),
+        Viewed(&'static str),
+    }
+
+    struct Reprice(&'static str, u32);
+
+    #[derive(Hash, PartialEq, Eq, Clone)]
+    struct Price(&'static str);
+
+    impl CommandHandler<Reprice, Catalog> for BasicMediator<Catalog> {
+        fn handle(&self, cmd: Reprice) {
+            PRICES.with(|prices| prices.borrow_mut().insert(cmd.0, cmd.1));
+            self.publish(Catalog::Repriced(cmd.0));
+        }
+    }
+
+    impl QueryHandler<Price, Catalog> for BasicMediator<Catalog> {
+        type Response = u32;
+
+        fn handle(query: Price, _: &QueryContext<'_, Self>) -> u32 {
+            HANDLED.with(|handled| handled.borrow_mut().push(query.0));
+            PRICES.with(|prices| prices.borrow().get(query.0).copied().unwrap_or_default())
+        }
+    }
+
+    let handled = || HANDLED.with(|handled| handled.borrow_mut().drain(..).collect::<Vec<_>>());
+
+    let clock = Arc::new(MockClock::new());
+    let mediator = BasicMediator::<Catalog>::builder()
+        .cache_queries::<Price>(
+            QueryCache::new(Duration::from_secs(10), 2)
+                .invalidated_by(|ev| matches!(ev, Catalog::Repriced(_))),
+        )
+        .clock(clock.clone())
+        .build();
+
+    mediator.execute(Reprice("apple", 3));
+    mediator.execute(Reprice("pear", 4));
+
+    // Hit: the response is cached per query value.
+    assert_eq!(mediator.query(Price("apple")), 3);
+    assert_eq!(mediator.query(Price("apple")), 3);
+    assert_eq!(mediator.query(Price("pear")), 4);
+    assert_eq!(handled(), vec!["apple", "pear"]);
+
+    // Events of other types do not invalidate the cache.
+    mediator.publish(Catalog::Viewed("apple"));
+    assert_eq!(mediator.query(Price("apple")), 3);
+    assert!(handled().is_empty());
+
+    // Expiry: responses are handled again once their TTL passed.
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(mediator.query(Price("pear")), 4);
+    assert!(handled().is_empty());
+    clock.advance(Duration::from_secs(5));
+    assert_eq!(mediator.query(Price("pear")), 4);
+    assert_eq!(handled(), vec!["pear"]);
+
+    // Eviction: "apple" expired, so it is dropped first to make room for "plum".
+    clock.advance(Duration::from_secs(1));
+    assert_eq!(mediator.query(Price("plum")), 0);
+    assert_eq!(mediator.query(Price("pear")), 4);
+    assert_eq!(handled(), vec!["plum"]);
+
+    // Both "pear" and "plum" are valid, so the oldest one, "pear", is evicted.
+    assert_eq!(mediator.query(Price("fig")), 0);
+    assert_eq!(mediator.query(Price("plum")), 0);
+    assert_eq!(mediator.query(Price("pear")), 4);
+    assert_eq!(handled(), vec!["fig", "pear"]);
+
+    // Invalidation: a published `Catalog::Repriced` drops every cached response.
+    mediator.execute(Reprice("plum", 2));
+    assert_eq!(mediator.query(Price("plum")), 2);
+    assert_eq!(mediator.query(Price("pear")), 4);
+    assert_eq!(handled(), vec!["plum", "pear"]);
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn query_cache_test_async() {
+    use async_trait::async_trait;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use crate::asynchronous::contextaware::*;
+    use crate::cache::QueryCache;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Account {
+        Credited(u32),
+        Audited,
+    }
+
+    #[derive(Debug, Default)]
+    struct Ledger {
+        entries: Mutex<Vec<u32>>,
+        handled: Mutex<Vec<&'static str>>,
+    }
+
+    struct Credit(u32);
+
+    #[derive(Hash, PartialEq, Eq, Clone)]
+    struct Total;
+
+    #[derive(Hash, PartialEq, Eq, Clone)]
+    struct Doubled;
+
+    #[async_trait]
+    impl CxAwareAsyncCommandHandler<Ledger, Credit, Account> for CxAwareAsyncMediator<Ledger, Account> {
+        async fn handle(&self, cmd: Credit, dep: &Ledger) {
+            dep.entries.lock().unwrap().push(cmd.0);
+            self.publish(Account::Credited(cmd.0)).await;
+        }
+    }
+
+    #[async_trait]
+    impl CxAwareAsyncQueryHandler<Ledger, Total, Account> for CxAwareAsyncMediator<Ledger, Account> {
+        type Response = u32;
+
+        async fn handle(_: Total, cx: &CxAwareQueryContext<'_, Ledger, Account>) -> u32 {
+            cx.dep().handled.lock().unwrap().push("total");
+            cx.dep().entries.lock().unwrap().iter().sum()
+        }
+    }
+
+    #[async_trait]
+    impl CxAwareAsyncQueryHandler<Ledger, Doubled, Account> for CxAwareAsyncMediator<Ledger, Account> {
+        type Response = u32;
+
+        async fn handle(_: Doubled, cx: &CxAwareQueryContext<'_, Ledger, Account>) -> u32 {
+            cx.dep().handled.lock().unwrap().push("doubled");
+            cx.query(Total).await * 2
+        }
+    }
+
+    async_std::task::block_on(async {
+        let clock = Arc::new(MockClock::new());
+        let mediator = CxAwareAsyncMediator::<Ledger, Account>::builder()
+            .add_dependency(Ledger::default())
+            .cache_queries::<Total>(
+                QueryCache::new(Duration::from_secs(10), 10)
+                    .invalidated_by(|ev| matches!(ev, Account::Credited(_))),
+            )
+            .clock(clock.clone())
+            .build()
+            .unwrap();
+        let handled = || async {
+            let dep = mediator.dependency().await;
+            let handled = dep.handled.lock().unwrap().drain(..).collect::<Vec<_>>();
+            handled
+        };
+
+        mediator.execute(Credit(5)).await;
+
+        // Hit, also for queries answered from within another query.
+        assert_eq!(mediator.query(Total).await, 5);
+        assert_eq!(mediator.query(Doubled).await, 10);
+        assert_eq!(mediator.query(Doubled).await, 10);
+        assert_eq!(handled().await, vec!["total", "doubled", "doubled"]);
+
+        // Events of other types do not invalidate the cache.
+        mediator.publish(Account::Audited).await;
+        assert_eq!(mediator.query(Total).await, 5);
+        assert!(handled().await.is_empty());
+
+        // Invalidation: a published `Account::Credited` drops the cached total.
+        mediator.execute(Credit(10)).await;
+        assert_eq!(mediator.query(Total).await, 15);
+        assert_eq!(handled().await, vec!["total"]);
+
+        // Expiry.
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(mediator.query(Total).await, 15);
+        assert_eq!(handled().await, vec!["total"]);
+
+        // Replacing the dependency drops the cached total.
+        mediator.replace_dependency(Ledger::default()).await;
+        assert_eq!(mediator.query(Total).await, 0);
+        assert_eq!(handled().await, vec!["total"]);
+    })
+}